@@ -0,0 +1,140 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::{Client, Url};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{
+    chat_messages,
+    http::{endpoint_url, ensure_success},
+    redact::redact,
+    ExecutePromptResponse, LLMService, PromptOptions, ToolCallResult, ToolDefinition, UsageStats,
+};
+
+pub const DEFAULT_BASE_URL: &str = "https://api.anthropic.com";
+const API_VERSION: &str = "2023-06-01";
+/// Upper bound on the length of a reply; the Messages API requires one.
+const MAX_TOKENS: u32 = 4096;
+/// The Messages API accepts temperatures up to 1.0 rather than 2.0.
+const MAX_TEMPERATURE: f32 = 1.0;
+
+pub struct AnthropicService {
+    client: Client,
+    messages_url: Url,
+    api_key: String,
+    model: String,
+}
+
+#[derive(Deserialize)]
+struct MessagesResponse {
+    content: Vec<ContentBlock>,
+    #[serde(default)]
+    usage: Option<UsageStats>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ContentBlock {
+    Text {
+        text: String,
+    },
+    ToolUse {
+        name: String,
+        input: Value,
+    },
+    #[serde(other)]
+    Other,
+}
+
+impl AnthropicService {
+    pub fn new(api_key: &str, model: &str) -> Result<Self> {
+        if api_key.trim().is_empty() {
+            return Err(anyhow::anyhow!("Anthropic API key must not be empty"));
+        }
+
+        Ok(Self {
+            client: Client::new(),
+            messages_url: endpoint_url(DEFAULT_BASE_URL, "v1/messages", "Anthropic")?,
+            api_key: api_key.trim().to_string(),
+            model: model.to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl LLMService for AnthropicService {
+    async fn execute_prompt(
+        &self,
+        prompt: &str,
+        tools: Vec<ToolDefinition>,
+        options: &PromptOptions,
+    ) -> Result<ExecutePromptResponse> {
+        let mut request = json!({
+            "model": self.model,
+            "max_tokens": MAX_TOKENS,
+            "messages": chat_messages(&options.history, prompt),
+        });
+        if !tools.is_empty() {
+            request["tools"] = tools
+                .iter()
+                .map(|tool| {
+                    json!({
+                        "name": tool.name,
+                        "description": tool.description,
+                        "input_schema": tool.parameters,
+                    })
+                })
+                .collect();
+        }
+        if let Some(temperature) = options.temperature {
+            request["temperature"] = json!(temperature.min(MAX_TEMPERATURE));
+        }
+
+        log::debug!(
+            "Anthropic request: model {}, prompt {}, {} tools",
+            self.model,
+            redact(prompt),
+            tools.len()
+        );
+
+        let response = self
+            .client
+            .post(self.messages_url.clone())
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", API_VERSION)
+            .json(&request)
+            .send()
+            .await
+            .context("Failed to send request to Anthropic")?;
+
+        let messages: MessagesResponse = ensure_success(response)
+            .await?
+            .json()
+            .await
+            .context("Failed to parse Anthropic response")?;
+
+        let mut response = ExecutePromptResponse {
+            message: String::new(),
+            tool_calls: vec![],
+            usage: messages.usage,
+        };
+        for block in messages.content {
+            match block {
+                ContentBlock::Text { text } => response.message.push_str(&text),
+                ContentBlock::ToolUse { name, input } => response.tool_calls.push(ToolCallResult {
+                    name,
+                    arguments: input,
+                }),
+                ContentBlock::Other => {}
+            }
+        }
+
+        log::debug!(
+            "Anthropic response: message {}, {} tool calls",
+            redact(&response.message),
+            response.tool_calls.len()
+        );
+
+        Ok(response)
+    }
+}
@@ -0,0 +1,78 @@
+use anyhow::{Context, Result};
+use reqwest::{Response, StatusCode, Url};
+use std::fmt;
+
+use crate::redact::redact;
+
+/// A backend answered with a non-success status.
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    /// Already redacted unless content logging is on.
+    pub body: String,
+}
+
+impl ApiError {
+    /// Rate limiting, timeouts and server errors may pass; other client
+    /// errors (bad key, invalid request) will fail the same way again.
+    pub fn is_retryable(&self) -> bool {
+        is_retryable_status(self.status)
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "API returned error status: {}, body: {}",
+            self.status, self.body
+        )
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+pub(crate) fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS
+        || status == StatusCode::REQUEST_TIMEOUT
+        || status.is_server_error()
+}
+
+/// Parses `base_url` and appends `path` to it, treating the base as a
+/// directory. `service` names the backend in error messages.
+pub(crate) fn endpoint_url(base_url: &str, path: &str, service: &str) -> Result<Url> {
+    let mut url = Url::parse(base_url.trim())
+        .with_context(|| format!("Invalid {} base URL: {}", service, base_url))?;
+
+    if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+        return Err(anyhow::anyhow!(
+            "{} base URL must be an http(s) URL with a host: {}",
+            service,
+            base_url
+        ));
+    }
+
+    // Treat the base as a directory so the endpoint path is appended to it
+    if !url.path().ends_with('/') {
+        let path = format!("{}/", url.path());
+        url.set_path(&path);
+    }
+
+    url.join(path)
+        .with_context(|| format!("Failed to build {} endpoint URL", service))
+}
+
+/// Turns a non-success response into an error carrying the redacted body.
+pub(crate) async fn ensure_success(response: Response) -> Result<Response> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+
+    let status = response.status();
+    let error_text = response.text().await.unwrap_or_default();
+    Err(ApiError {
+        status,
+        body: redact(&error_text),
+    }
+    .into())
+}
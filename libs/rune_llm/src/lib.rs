@@ -1,15 +1,40 @@
 use anyhow::Result;
 use async_trait::async_trait;
+use futures_util::Stream;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::time::Duration;
+use std::{pin::Pin, sync::Mutex, time::Duration};
 use tokio::time::timeout;
 
+pub mod anthropic;
+mod http;
+pub use http::ApiError;
+pub mod ollama;
+pub mod openai;
+pub mod redact;
 pub mod rune_api;
+mod sse;
 
 #[derive(Debug, Clone)]
 pub enum LLMProvider {
-    RuneAPI,
+    /// The Rune engine. `base_url` points at a self-hosted or staging instance;
+    /// `None` uses the production API.
+    RuneAPI {
+        base_url: Option<String>,
+    },
+    OpenAI {
+        api_key: String,
+        model: String,
+    },
+    Anthropic {
+        api_key: String,
+        model: String,
+    },
+    /// A local Ollama server. `base_url` defaults to `http://localhost:11434`.
+    Ollama {
+        base_url: Option<String>,
+        model: String,
+    },
 }
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ToolDefinition {
@@ -23,6 +48,111 @@ pub struct ExecutePromptRequest {
     pub prompt: String,
     #[serde(default)]
     pub tools: Vec<ToolDefinition>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Earlier turns of the conversation, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<Message>,
+    /// Ask for the reply as server-sent `StreamChunk` events.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stream: bool,
+}
+
+pub const MIN_TEMPERATURE: f32 = 0.0;
+pub const MAX_TEMPERATURE: f32 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    User,
+    Assistant,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::User => "user",
+            Role::Assistant => "assistant",
+        }
+    }
+}
+
+/// One earlier turn of a conversation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Message {
+    pub role: Role,
+    pub content: String,
+}
+
+impl Message {
+    pub fn user(content: impl Into<String>) -> Self {
+        Self {
+            role: Role::User,
+            content: content.into(),
+        }
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self {
+            role: Role::Assistant,
+            content: content.into(),
+        }
+    }
+}
+
+/// Per-request settings. Unset sampling fields fall back to the backend's
+/// defaults.
+#[derive(Debug, Clone, Default)]
+pub struct PromptOptions {
+    pub temperature: Option<f32>,
+    pub seed: Option<u64>,
+    /// Earlier turns sent ahead of the prompt, oldest first, so follow-ups
+    /// like "make that shorter" have something to refer to.
+    pub history: Vec<Message>,
+    /// Overrides `LLMClientConfig::timeout` for this request, per attempt.
+    pub timeout: Option<Duration>,
+}
+
+impl PromptOptions {
+    pub fn with_history(mut self, history: Vec<Message>) -> Self {
+        self.history = history;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+/// The chat messages for `prompt` after `history`, in the role/content shape
+/// shared by the OpenAI, Anthropic and Ollama APIs.
+pub(crate) fn chat_messages(history: &[Message], prompt: &str) -> Vec<Value> {
+    history
+        .iter()
+        .map(|message| serde_json::json!({ "role": message.role.as_str(), "content": message.content }))
+        .chain(std::iter::once(
+            serde_json::json!({ "role": "user", "content": prompt }),
+        ))
+        .collect()
+}
+
+impl PromptOptions {
+    pub fn validate(&self) -> Result<()> {
+        if let Some(temperature) = self.temperature {
+            if !(MIN_TEMPERATURE..=MAX_TEMPERATURE).contains(&temperature) {
+                return Err(anyhow::anyhow!(
+                    "Temperature must be between {} and {}, got {}",
+                    MIN_TEMPERATURE,
+                    MAX_TEMPERATURE,
+                    temperature
+                ));
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -31,10 +161,71 @@ pub struct ToolCallResult {
     pub arguments: Value,
 }
 
+/// Tokens and cost of a request as reported by the backend. Only the Rune
+/// API prices requests; other providers report a cost of 0.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageStats {
+    #[serde(default)]
+    pub input_tokens: u64,
+    #[serde(default)]
+    pub output_tokens: u64,
+    /// In US dollars.
+    #[serde(default, alias = "total_cost")]
+    pub cost: f64,
+}
+
+impl UsageStats {
+    pub fn add(&mut self, other: &UsageStats) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cost += other.cost;
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ExecutePromptResponse {
     pub message: String,
     pub tool_calls: Vec<ToolCallResult>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<UsageStats>,
+}
+
+/// A piece of a reply that is still being generated.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamChunk {
+    TextDelta {
+        text: String,
+    },
+    /// A fragment of a tool call's JSON arguments. Fragments with the same
+    /// `index` belong to the same call; `name` is set on the first of them.
+    ToolCallDelta {
+        index: usize,
+        #[serde(default)]
+        name: Option<String>,
+        #[serde(default)]
+        arguments: String,
+    },
+}
+
+pub type ChunkStream = Pin<Box<dyn Stream<Item = Result<StreamChunk>> + Send>>;
+
+impl ExecutePromptResponse {
+    /// The whole response as chunks, for backends that can't stream.
+    pub fn into_chunks(self) -> Vec<StreamChunk> {
+        let mut chunks = Vec::new();
+        if !self.message.is_empty() {
+            chunks.push(StreamChunk::TextDelta { text: self.message });
+        }
+        for (index, call) in self.tool_calls.into_iter().enumerate() {
+            chunks.push(StreamChunk::ToolCallDelta {
+                index,
+                name: Some(call.name),
+                arguments: call.arguments.to_string(),
+            });
+        }
+        chunks
+    }
 }
 
 #[async_trait]
@@ -43,12 +234,33 @@ pub trait LLMService {
         &self,
         prompt: &str,
         tools: Vec<ToolDefinition>,
+        options: &PromptOptions,
     ) -> Result<ExecutePromptResponse>;
+
+    /// Yields the reply as it is generated. Backends without streaming
+    /// support deliver the complete response once it is ready.
+    async fn execute_prompt_streaming(
+        &self,
+        prompt: &str,
+        tools: Vec<ToolDefinition>,
+        options: &PromptOptions,
+    ) -> Result<ChunkStream>
+    where
+        Self: Sync,
+    {
+        let response = self.execute_prompt(prompt, tools, options).await?;
+        Ok(Box::pin(futures_util::stream::iter(
+            response.into_chunks().into_iter().map(Ok),
+        )))
+    }
 }
 
 pub struct RetryConfig {
     pub max_retries: u32,
     pub base_delay: Duration,
+    /// Wait a random time between zero and the backoff delay ("full jitter"),
+    /// so clients recovering at the same moment don't retry in lockstep.
+    pub jitter: bool,
 }
 
 impl Default for RetryConfig {
@@ -56,6 +268,19 @@ impl Default for RetryConfig {
         Self {
             max_retries: 3,
             base_delay: Duration::from_secs(1),
+            jitter: true,
+        }
+    }
+}
+
+impl RetryConfig {
+    /// How long to wait before retry number `retries + 1`.
+    pub fn delay(&self, retries: u32) -> Duration {
+        let delay = self.base_delay.saturating_mul(2u32.saturating_pow(retries));
+        if self.jitter {
+            delay.mul_f64(rand::random::<f64>())
+        } else {
+            delay
         }
     }
 }
@@ -74,24 +299,59 @@ impl Default for LLMClientConfig {
     }
 }
 
+/// Whether `error` might go away on retry: timeouts, connection failures,
+/// rate limiting and server errors. Authentication and validation failures
+/// never do. Errors of unknown origin are treated as retryable.
+pub fn is_retryable(error: &anyhow::Error) -> bool {
+    for cause in error.chain() {
+        if let Some(api_error) = cause.downcast_ref::<ApiError>() {
+            return api_error.is_retryable();
+        }
+        if let Some(request_error) = cause.downcast_ref::<reqwest::Error>() {
+            return match request_error.status() {
+                Some(status) => http::is_retryable_status(status),
+                None => !request_error.is_builder(),
+            };
+        }
+    }
+    true
+}
+
 pub struct LLMClient {
     service: Box<dyn LLMService + Send + Sync>,
     config: LLMClientConfig,
+    usage: Mutex<UsageStats>,
 }
 
 impl LLMClient {
-    pub fn new(provider: LLMProvider, config: Option<LLMClientConfig>) -> Self {
+    pub fn new(provider: LLMProvider, config: Option<LLMClientConfig>) -> Result<Self> {
         let service: Box<dyn LLMService + Send + Sync> = match provider {
-            LLMProvider::RuneAPI => Box::new(rune_api::RuneAPIService::new()),
+            LLMProvider::RuneAPI { base_url } => {
+                Box::new(rune_api::RuneAPIService::new(base_url.as_deref())?)
+            }
+            LLMProvider::OpenAI { api_key, model } => {
+                Box::new(openai::OpenAIService::new(&api_key, &model)?)
+            }
+            LLMProvider::Anthropic { api_key, model } => {
+                Box::new(anthropic::AnthropicService::new(&api_key, &model)?)
+            }
+            LLMProvider::Ollama { base_url, model } => {
+                Box::new(ollama::OllamaService::new(base_url.as_deref(), &model)?)
+            }
         };
 
-        Self {
+        Ok(Self {
             service,
             config: config.unwrap_or_default(),
-        }
+            usage: Mutex::new(UsageStats::default()),
+        })
     }
 
-    async fn execute_with_retry<F, Fut, T>(&self, operation: F) -> Result<T>
+    async fn execute_with_retry<F, Fut, T>(
+        &self,
+        attempt_timeout: Duration,
+        operation: F,
+    ) -> Result<T>
     where
         F: Fn() -> Fut,
         Fut: std::future::Future<Output = Result<T>>,
@@ -99,14 +359,16 @@ impl LLMClient {
     {
         let mut retries = 0;
         let max_retries = self.config.retry_config.max_retries;
-        let base_delay = self.config.retry_config.base_delay;
 
         loop {
-            match timeout(self.config.timeout, operation()).await {
+            match timeout(attempt_timeout, operation()).await {
                 Ok(result) => {
                     match result {
                         Ok(value) => return Ok(value),
                         Err(e) => {
+                            if !is_retryable(&e) {
+                                return Err(e.context("Request failed with a non-retryable error"));
+                            }
                             if retries >= max_retries {
                                 return Err(e.context(format!(
                                     "Operation failed after {} retries",
@@ -118,8 +380,7 @@ impl LLMClient {
                             log::error!("Attempt {} failed: {}", retries + 1, e);
 
                             // Exponential backoff
-                            let delay = base_delay * 2u32.pow(retries);
-                            tokio::time::sleep(delay).await;
+                            tokio::time::sleep(self.config.retry_config.delay(retries)).await;
                             retries += 1;
                         }
                     }
@@ -134,8 +395,7 @@ impl LLMClient {
 
                     log::error!("Attempt {} timed out", retries + 1);
 
-                    let delay = base_delay * 2u32.pow(retries);
-                    tokio::time::sleep(delay).await;
+                    tokio::time::sleep(self.config.retry_config.delay(retries)).await;
                     retries += 1;
                 }
             }
@@ -147,17 +407,66 @@ impl LLMClient {
         prompt: &str,
         tools: Vec<ToolDefinition>,
     ) -> Result<ExecutePromptResponse> {
+        self.execute_prompt_with_options(prompt, tools, &PromptOptions::default())
+            .await
+    }
+
+    pub async fn execute_prompt_with_options(
+        &self,
+        prompt: &str,
+        tools: Vec<ToolDefinition>,
+        options: &PromptOptions,
+    ) -> Result<ExecutePromptResponse> {
+        options.validate()?;
+
         let prompt_string = prompt.to_string();
         let tools_clone = tools;
 
-        self.execute_with_retry(|| async {
+        let response = self
+            .execute_with_retry(self.attempt_timeout(options), || async {
+                self.service
+                    .execute_prompt(&prompt_string, tools_clone.clone(), options)
+                    .await
+            })
+            .await?;
+
+        if let Some(usage) = &response.usage {
+            if let Ok(mut total) = self.usage.lock() {
+                total.add(usage);
+            }
+        }
+
+        Ok(response)
+    }
+
+    /// Totals over every successful prompt since this client was created.
+    /// Streamed replies carry no usage and aren't counted.
+    pub fn cumulative_usage(&self) -> UsageStats {
+        self.usage.lock().map(|usage| *usage).unwrap_or_default()
+    }
+
+    /// Starts a streamed reply. Only establishing the stream is retried; an
+    /// error partway through arrives as an item of the stream.
+    pub async fn execute_prompt_streaming(
+        &self,
+        prompt: &str,
+        tools: Vec<ToolDefinition>,
+        options: &PromptOptions,
+    ) -> Result<ChunkStream> {
+        options.validate()?;
+
+        self.execute_with_retry(self.attempt_timeout(options), || async {
             self.service
-                .execute_prompt(&prompt_string, tools_clone.clone())
+                .execute_prompt_streaming(prompt, tools.clone(), options)
                 .await
         })
         .await
     }
 
+    fn attempt_timeout(&self, options: &PromptOptions) -> Duration {
+        options.timeout.unwrap_or(self.config.timeout)
+    }
+
     pub fn with_config(mut self, config: LLMClientConfig) -> Self {
         self.config = config;
         self
@@ -0,0 +1,145 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::{Client, Url};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use crate::{
+    chat_messages,
+    http::{endpoint_url, ensure_success},
+    openai::function_tools,
+    redact::redact,
+    ExecutePromptResponse, LLMService, PromptOptions, ToolCallResult, ToolDefinition, UsageStats,
+};
+
+pub const DEFAULT_BASE_URL: &str = "http://localhost:11434";
+
+/// A model served by a local (or self-hosted) Ollama instance, so prompts
+/// never leave the machine.
+pub struct OllamaService {
+    client: Client,
+    chat_url: Url,
+    model: String,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    message: ChatMessage,
+    #[serde(default)]
+    prompt_eval_count: Option<u64>,
+    #[serde(default)]
+    eval_count: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct ChatMessage {
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    tool_calls: Vec<ToolCall>,
+}
+
+#[derive(Deserialize)]
+struct ToolCall {
+    function: FunctionCall,
+}
+
+#[derive(Deserialize)]
+struct FunctionCall {
+    name: String,
+    arguments: Value,
+}
+
+impl OllamaService {
+    /// Creates a service for `model` on the Ollama server at `base_url`, or on
+    /// the default local port when `None`.
+    pub fn new(base_url: Option<&str>, model: &str) -> Result<Self> {
+        let base_url = base_url.unwrap_or(DEFAULT_BASE_URL);
+
+        Ok(Self {
+            client: Client::new(),
+            chat_url: endpoint_url(base_url, "api/chat", "Ollama")?,
+            model: model.to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl LLMService for OllamaService {
+    async fn execute_prompt(
+        &self,
+        prompt: &str,
+        tools: Vec<ToolDefinition>,
+        options: &PromptOptions,
+    ) -> Result<ExecutePromptResponse> {
+        let mut model_options = Map::new();
+        if let Some(temperature) = options.temperature {
+            model_options.insert("temperature".to_string(), json!(temperature));
+        }
+        if let Some(seed) = options.seed {
+            model_options.insert("seed".to_string(), json!(seed));
+        }
+
+        let mut request = json!({
+            "model": self.model,
+            "messages": chat_messages(&options.history, prompt),
+            "stream": false,
+            "options": model_options,
+        });
+        if !tools.is_empty() {
+            request["tools"] = Value::Array(function_tools(&tools));
+        }
+
+        log::debug!(
+            "Ollama request: model {}, prompt {}, {} tools",
+            self.model,
+            redact(prompt),
+            tools.len()
+        );
+
+        let response = self
+            .client
+            .post(self.chat_url.clone())
+            .json(&request)
+            .send()
+            .await
+            .context("Failed to send request to Ollama")?;
+
+        let chat: ChatResponse = ensure_success(response)
+            .await?
+            .json()
+            .await
+            .context("Failed to parse Ollama response")?;
+
+        let usage = match (chat.prompt_eval_count, chat.eval_count) {
+            (None, None) => None,
+            (input, output) => Some(UsageStats {
+                input_tokens: input.unwrap_or(0),
+                output_tokens: output.unwrap_or(0),
+                cost: 0.0,
+            }),
+        };
+
+        let response = ExecutePromptResponse {
+            message: chat.message.content,
+            tool_calls: chat
+                .message
+                .tool_calls
+                .into_iter()
+                .map(|call| ToolCallResult {
+                    name: call.function.name,
+                    arguments: call.function.arguments,
+                })
+                .collect(),
+            usage,
+        };
+
+        log::debug!(
+            "Ollama response: message {}, {} tool calls",
+            redact(&response.message),
+            response.tool_calls.len()
+        );
+
+        Ok(response)
+    }
+}
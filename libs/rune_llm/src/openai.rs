@@ -0,0 +1,177 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::{Client, Url};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{
+    chat_messages,
+    http::{endpoint_url, ensure_success},
+    redact::redact,
+    ExecutePromptResponse, LLMService, PromptOptions, ToolCallResult, ToolDefinition, UsageStats,
+};
+
+pub const DEFAULT_BASE_URL: &str = "https://api.openai.com";
+
+pub struct OpenAIService {
+    client: Client,
+    completions_url: Url,
+    api_key: String,
+    model: String,
+}
+
+#[derive(Deserialize)]
+struct ChatCompletion {
+    choices: Vec<Choice>,
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+#[derive(Deserialize)]
+struct Usage {
+    prompt_tokens: u64,
+    completion_tokens: u64,
+}
+
+#[derive(Deserialize)]
+struct Choice {
+    message: ChatMessage,
+}
+
+#[derive(Deserialize)]
+struct ChatMessage {
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default)]
+    tool_calls: Vec<ToolCall>,
+}
+
+#[derive(Deserialize)]
+struct ToolCall {
+    function: FunctionCall,
+}
+
+#[derive(Deserialize)]
+struct FunctionCall {
+    name: String,
+    /// JSON-encoded arguments, as a string.
+    arguments: String,
+}
+
+impl OpenAIService {
+    pub fn new(api_key: &str, model: &str) -> Result<Self> {
+        if api_key.trim().is_empty() {
+            return Err(anyhow::anyhow!("OpenAI API key must not be empty"));
+        }
+
+        Ok(Self {
+            client: Client::new(),
+            completions_url: endpoint_url(DEFAULT_BASE_URL, "v1/chat/completions", "OpenAI")?,
+            api_key: api_key.trim().to_string(),
+            model: model.to_string(),
+        })
+    }
+}
+
+/// Tool definitions in the function-calling format shared by OpenAI and Ollama.
+pub(crate) fn function_tools(tools: &[ToolDefinition]) -> Vec<Value> {
+    tools
+        .iter()
+        .map(|tool| {
+            json!({
+                "type": "function",
+                "function": {
+                    "name": tool.name,
+                    "description": tool.description,
+                    "parameters": tool.parameters,
+                },
+            })
+        })
+        .collect()
+}
+
+#[async_trait]
+impl LLMService for OpenAIService {
+    async fn execute_prompt(
+        &self,
+        prompt: &str,
+        tools: Vec<ToolDefinition>,
+        options: &PromptOptions,
+    ) -> Result<ExecutePromptResponse> {
+        let mut request = json!({
+            "model": self.model,
+            "messages": chat_messages(&options.history, prompt),
+        });
+        if !tools.is_empty() {
+            request["tools"] = Value::Array(function_tools(&tools));
+        }
+        if let Some(temperature) = options.temperature {
+            request["temperature"] = json!(temperature);
+        }
+        if let Some(seed) = options.seed {
+            request["seed"] = json!(seed);
+        }
+
+        log::debug!(
+            "OpenAI request: model {}, prompt {}, {} tools",
+            self.model,
+            redact(prompt),
+            tools.len()
+        );
+
+        let response = self
+            .client
+            .post(self.completions_url.clone())
+            .bearer_auth(&self.api_key)
+            .json(&request)
+            .send()
+            .await
+            .context("Failed to send request to OpenAI")?;
+
+        let completion: ChatCompletion = ensure_success(response)
+            .await?
+            .json()
+            .await
+            .context("Failed to parse OpenAI response")?;
+
+        let message = completion
+            .choices
+            .into_iter()
+            .next()
+            .map(|choice| choice.message)
+            .ok_or_else(|| anyhow::anyhow!("OpenAI response contained no choices"))?;
+
+        let tool_calls = message
+            .tool_calls
+            .into_iter()
+            .map(|call| {
+                let arguments =
+                    serde_json::from_str(&call.function.arguments).with_context(|| {
+                        format!("Invalid arguments for tool call {}", call.function.name)
+                    })?;
+                Ok(ToolCallResult {
+                    name: call.function.name,
+                    arguments,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let response = ExecutePromptResponse {
+            message: message.content.unwrap_or_default(),
+            tool_calls,
+            usage: completion.usage.map(|usage| UsageStats {
+                input_tokens: usage.prompt_tokens,
+                output_tokens: usage.completion_tokens,
+                cost: 0.0,
+            }),
+        };
+
+        log::debug!(
+            "OpenAI response: message {}, {} tool calls",
+            redact(&response.message),
+            response.tool_calls.len()
+        );
+
+        Ok(response)
+    }
+}
@@ -0,0 +1,37 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Whether prompt and response content may be written to the logs. Never
+/// persisted, so it resets to off on every launch.
+static LOG_CONTENT: AtomicBool = AtomicBool::new(false);
+
+/// Allows dictated content to appear in logs for the rest of this session.
+pub fn set_content_logging(enabled: bool) {
+    if enabled {
+        log::warn!("LLM content logging enabled: prompts and responses will be logged in full");
+    }
+    LOG_CONTENT.store(enabled, Ordering::SeqCst);
+}
+
+pub fn content_logging_enabled() -> bool {
+    LOG_CONTENT.load(Ordering::SeqCst)
+}
+
+/// Returns `text` as-is when content logging is enabled, otherwise a summary
+/// with only its length and a short hash for correlating log lines.
+pub fn redact(text: &str) -> String {
+    if content_logging_enabled() {
+        return text.to_string();
+    }
+
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    format!(
+        "<redacted len={} hash={:08x}>",
+        text.chars().count(),
+        hasher.finish() as u32
+    )
+}
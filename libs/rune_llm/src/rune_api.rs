@@ -1,19 +1,40 @@
 use anyhow::{Context, Result};
 use async_trait::async_trait;
-use reqwest::Client;
+use reqwest::{
+    header::{ACCEPT, CONTENT_TYPE},
+    Client, Url,
+};
 use serde_json::Value;
 
-use crate::{ExecutePromptRequest, ExecutePromptResponse, LLMService, ToolDefinition};
+use crate::{
+    http::{endpoint_url, ensure_success},
+    redact::redact,
+    sse, ChunkStream, ExecutePromptRequest, ExecutePromptResponse, LLMService, PromptOptions,
+    ToolDefinition,
+};
+
+pub const DEFAULT_BASE_URL: &str = "https://api.runeapp.ai/engine";
 
 pub struct RuneAPIService {
     client: Client,
+    execute_url: Url,
 }
 
 impl RuneAPIService {
-    pub fn new() -> Self {
-        Self {
+    /// Creates a service talking to the engine at `base_url`, or to the
+    /// production API when `None`.
+    pub fn new(base_url: Option<&str>) -> Result<Self> {
+        let base_url = base_url.unwrap_or(DEFAULT_BASE_URL);
+        let execute_url = Self::execute_url(base_url)?;
+
+        Ok(Self {
             client: Client::new(),
-        }
+            execute_url,
+        })
+    }
+
+    fn execute_url(base_url: &str) -> Result<Url> {
+        endpoint_url(base_url, "v1/language-model/execute", "Rune API")
     }
 }
 
@@ -23,31 +44,33 @@ impl LLMService for RuneAPIService {
         &self,
         prompt: &str,
         tools: Vec<ToolDefinition>,
+        options: &PromptOptions,
     ) -> Result<ExecutePromptResponse> {
         let request = ExecutePromptRequest {
             prompt: prompt.to_string(),
             tools,
+            temperature: options.temperature,
+            seed: options.seed,
+            history: options.history.clone(),
+            stream: false,
         };
 
+        log::debug!(
+            "Rune API request: prompt {}, {} tools",
+            redact(&request.prompt),
+            request.tools.len()
+        );
+
         let response = self
             .client
-            .post("https://api.runeapp.ai/engine/v1/language-model/execute")
+            .post(self.execute_url.clone())
             .json(&request)
             .send()
             .await
             .context("Failed to send request to Rune API")?;
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(anyhow::anyhow!(
-                "API returned error status: {}, body: {}",
-                status,
-                error_text
-            ));
-        }
-
-        let result: Value = response
+        let result: Value = ensure_success(response)
+            .await?
             .json()
             .await
             .context("Failed to parse Rune API response")?;
@@ -55,6 +78,62 @@ impl LLMService for RuneAPIService {
         let response: ExecutePromptResponse = serde_json::from_value(result)
             .context("Failed to parse response into ExecutePromptResponse")?;
 
+        log::debug!(
+            "Rune API response: message {}, {} tool calls",
+            redact(&response.message),
+            response.tool_calls.len()
+        );
+
         Ok(response)
     }
+
+    async fn execute_prompt_streaming(
+        &self,
+        prompt: &str,
+        tools: Vec<ToolDefinition>,
+        options: &PromptOptions,
+    ) -> Result<ChunkStream> {
+        let request = ExecutePromptRequest {
+            prompt: prompt.to_string(),
+            tools,
+            temperature: options.temperature,
+            seed: options.seed,
+            history: options.history.clone(),
+            stream: true,
+        };
+
+        log::debug!(
+            "Rune API streaming request: prompt {}, {} tools",
+            redact(&request.prompt),
+            request.tools.len()
+        );
+
+        let response = self
+            .client
+            .post(self.execute_url.clone())
+            .header(ACCEPT, "text/event-stream")
+            .json(&request)
+            .send()
+            .await
+            .context("Failed to send request to Rune API")?;
+        let response = ensure_success(response).await?;
+
+        let is_event_stream = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("text/event-stream"));
+        if is_event_stream {
+            return Ok(sse::chunk_stream(response));
+        }
+
+        // An engine without streaming support answers with the whole response
+        let response: ExecutePromptResponse = response
+            .json()
+            .await
+            .context("Failed to parse Rune API response")?;
+        Ok(Box::pin(futures_util::stream::iter(
+            response.into_chunks().into_iter().map(Ok),
+        )))
+    }
 }
@@ -0,0 +1,87 @@
+use anyhow::{Context, Result};
+use futures_util::stream;
+use reqwest::Response;
+use std::collections::VecDeque;
+
+use crate::{ChunkStream, StreamChunk};
+
+struct SseState {
+    response: Response,
+    /// Bytes received but not yet part of a complete event.
+    buffer: Vec<u8>,
+    pending: VecDeque<StreamChunk>,
+    done: bool,
+}
+
+/// Reads a `text/event-stream` response whose `data:` payloads are JSON
+/// `StreamChunk`s, ending at `[DONE]` or when the body ends. An `error`
+/// event ends the stream with an error.
+pub(crate) fn chunk_stream(response: Response) -> ChunkStream {
+    let state = SseState {
+        response,
+        buffer: Vec::new(),
+        pending: VecDeque::new(),
+        done: false,
+    };
+
+    Box::pin(stream::try_unfold(state, |mut state| async move {
+        loop {
+            if let Some(chunk) = state.pending.pop_front() {
+                return Ok(Some((chunk, state)));
+            }
+            if state.done {
+                return Ok(None);
+            }
+
+            match state
+                .response
+                .chunk()
+                .await
+                .context("Failed to read streamed response")?
+            {
+                Some(bytes) => state.buffer.extend(bytes.iter().filter(|&&b| b != b'\r')),
+                None => {
+                    // A final event may lack its trailing blank line
+                    state.buffer.extend_from_slice(b"\n\n");
+                    state.done = true;
+                }
+            }
+            state.parse_events()?;
+        }
+    }))
+}
+
+impl SseState {
+    fn parse_events(&mut self) -> Result<()> {
+        while let Some(end) = self.buffer.windows(2).position(|w| w == b"\n\n") {
+            let event: Vec<u8> = self.buffer.drain(..end + 2).collect();
+            let event = String::from_utf8(event).context("Streamed event is not valid UTF-8")?;
+
+            let mut name = "message";
+            let mut data = Vec::new();
+            for line in event.lines() {
+                if let Some(value) = line.strip_prefix("event:") {
+                    name = value.trim();
+                } else if let Some(value) = line.strip_prefix("data:") {
+                    data.push(value.strip_prefix(' ').unwrap_or(value));
+                }
+            }
+            let data = data.join("\n");
+
+            if name == "error" {
+                return Err(anyhow::anyhow!("Stream returned an error: {}", data));
+            }
+            if data == "[DONE]" {
+                self.done = true;
+                self.buffer.clear();
+                return Ok(());
+            }
+            if !data.is_empty() {
+                let chunk =
+                    serde_json::from_str(&data).context("Failed to parse streamed chunk")?;
+                self.pending.push_back(chunk);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,447 @@
+//! Word timings from the decoder's cross-attention, following the reference
+//! implementation's `find_alignment`. The decoded text is run through the
+//! decoder once more, the attention its upper layers pay to each audio frame
+//! is normalised and smoothed, and dynamic time warping finds the monotonic
+//! path from tokens to frames.
+
+use anyhow::Result;
+use candle::{IndexOp, Tensor, D};
+use candle_nn::{Embedding, LayerNorm, Module};
+use candle_transformers::models::whisper::Config;
+use candle_transformers::{quantized_nn, quantized_var_builder};
+
+/// Seconds of audio per frame of the encoder output.
+pub const FRAME_SECS: f64 = 0.02;
+
+/// Width of the median filter run over each head's weights along time.
+const MEDFILT_WIDTH: usize = 7;
+
+type Layer = Box<dyn Module + Send + Sync>;
+
+/// Where the weights are read from. Tensors are shared with a model loaded
+/// from the same builder rather than copied.
+#[derive(Clone)]
+enum Source<'a> {
+    Full(candle_nn::VarBuilder<'a>),
+    Quantized(quantized_var_builder::VarBuilder),
+}
+
+impl Source<'_> {
+    fn pp(&self, name: impl ToString) -> Self {
+        match self {
+            Self::Full(vb) => Self::Full(vb.pp(name)),
+            Self::Quantized(vb) => Self::Quantized(vb.pp(name)),
+        }
+    }
+
+    fn linear(&self, size: usize, bias: bool) -> candle::Result<Layer> {
+        Ok(match (self, bias) {
+            (Self::Full(vb), true) => Box::new(candle_nn::linear(size, size, vb.clone())?),
+            (Self::Full(vb), false) => Box::new(candle_nn::linear_no_bias(size, size, vb.clone())?),
+            (Self::Quantized(vb), true) => Box::new(quantized_nn::linear(size, size, vb.clone())?),
+            (Self::Quantized(vb), false) => {
+                Box::new(quantized_nn::linear_no_bias(size, size, vb.clone())?)
+            }
+        })
+    }
+
+    fn mlp(&self, in_dim: usize, out_dim: usize) -> candle::Result<Layer> {
+        Ok(match self {
+            Self::Full(vb) => Box::new(candle_nn::linear(in_dim, out_dim, vb.clone())?),
+            Self::Quantized(vb) => Box::new(quantized_nn::linear(in_dim, out_dim, vb.clone())?),
+        })
+    }
+
+    fn layer_norm(&self, size: usize) -> candle::Result<LayerNorm> {
+        match self {
+            Self::Full(vb) => candle_nn::layer_norm(size, 1e-5, vb.clone()),
+            Self::Quantized(vb) => quantized_nn::layer_norm(size, 1e-5, vb.clone()),
+        }
+    }
+
+    /// Quantized tensors are dequantized, which for the token embedding costs
+    /// one extra copy next to the model's own.
+    fn tensor(&self, shape: (usize, usize), name: &str) -> candle::Result<Tensor> {
+        match self {
+            Self::Full(vb) => vb.get(shape, name),
+            Self::Quantized(vb) => vb.get(shape, name)?.dequantize(vb.device()),
+        }
+    }
+}
+
+struct Attention {
+    query: Layer,
+    key: Layer,
+    value: Layer,
+    out: Layer,
+    n_head: usize,
+}
+
+impl Attention {
+    fn load(n_state: usize, n_head: usize, vb: Source) -> candle::Result<Self> {
+        Ok(Self {
+            query: vb.pp("q_proj").linear(n_state, true)?,
+            key: vb.pp("k_proj").linear(n_state, false)?,
+            value: vb.pp("v_proj").linear(n_state, true)?,
+            out: vb.pp("out_proj").linear(n_state, true)?,
+            n_head,
+        })
+    }
+
+    fn reshape_head(&self, x: &Tensor) -> candle::Result<Tensor> {
+        let (n_batch, n_ctx, n_state) = x.dims3()?;
+        x.reshape((n_batch, n_ctx, self.n_head, n_state / self.n_head))?
+            .transpose(1, 2)
+    }
+
+    /// Returns the attention output and its weights, one
+    /// `(n_head, n_ctx, n_kv)` matrix per batch entry.
+    fn forward(
+        &self,
+        x: &Tensor,
+        kv: &Tensor,
+        mask: Option<&Tensor>,
+    ) -> candle::Result<(Tensor, Tensor)> {
+        let q = self.query.forward(x)?;
+        let k = self.key.forward(kv)?;
+        let v = self.value.forward(kv)?;
+
+        let (_, _, n_state) = q.dims3()?;
+        let scale = ((n_state / self.n_head) as f64).powf(-0.25);
+        let q = (self.reshape_head(&q)? * scale)?;
+        let k = (self.reshape_head(&k)?.transpose(2, 3)? * scale)?;
+        let v = self.reshape_head(&v)?.contiguous()?;
+
+        let mut qk = q.matmul(&k)?;
+        if let Some(mask) = mask {
+            qk = qk.broadcast_add(mask)?;
+        }
+        let w = candle_nn::ops::softmax_last_dim(&qk)?;
+        let wv = w.matmul(&v)?.transpose(1, 2)?.flatten_from(2)?;
+        Ok((self.out.forward(&wv)?, w))
+    }
+}
+
+struct Block {
+    attn: Attention,
+    attn_ln: LayerNorm,
+    cross_attn: Attention,
+    cross_attn_ln: LayerNorm,
+    mlp_linear1: Layer,
+    mlp_linear2: Layer,
+    mlp_ln: LayerNorm,
+}
+
+impl Block {
+    fn load(n_state: usize, n_head: usize, vb: Source) -> candle::Result<Self> {
+        let n_mlp = n_state * 4;
+        Ok(Self {
+            attn: Attention::load(n_state, n_head, vb.pp("self_attn"))?,
+            attn_ln: vb.pp("self_attn_layer_norm").layer_norm(n_state)?,
+            cross_attn: Attention::load(n_state, n_head, vb.pp("encoder_attn"))?,
+            cross_attn_ln: vb.pp("encoder_attn_layer_norm").layer_norm(n_state)?,
+            mlp_linear1: vb.pp("fc1").mlp(n_state, n_mlp)?,
+            mlp_linear2: vb.pp("fc2").mlp(n_mlp, n_state)?,
+            mlp_ln: vb.pp("final_layer_norm").layer_norm(n_state)?,
+        })
+    }
+
+    /// Returns the block's output and its cross-attention weights.
+    fn forward(&self, x: &Tensor, xa: &Tensor, mask: &Tensor) -> candle::Result<(Tensor, Tensor)> {
+        let x_ln = self.attn_ln.forward(x)?;
+        let (attn, _) = self.attn.forward(&x_ln, &x_ln, Some(mask))?;
+        let x = (x + attn)?;
+
+        let (cross_attn, weights) =
+            self.cross_attn
+                .forward(&self.cross_attn_ln.forward(&x)?, xa, None)?;
+        let x = (x + cross_attn)?;
+
+        let mlp = self.mlp_linear2.forward(
+            &self
+                .mlp_linear1
+                .forward(&self.mlp_ln.forward(&x)?)?
+                .gelu()?,
+        )?;
+        Ok(((x + mlp)?, weights))
+    }
+}
+
+/// A second, cache-free pass over the text decoder that reads out the
+/// cross-attention weights the candle model keeps to itself.
+pub struct Aligner {
+    token_embedding: Embedding,
+    positional_embedding: Tensor,
+    blocks: Vec<Block>,
+    /// Heads whose cross-attention follows the speech, as `(layer, head)`.
+    /// Without per-model tables every head in the upper half of the layers
+    /// is used, as the reference implementation does by default.
+    heads: Vec<(usize, usize)>,
+}
+
+impl Aligner {
+    /// `vb` must be the builder the model was loaded from.
+    pub fn load(vb: &candle_nn::VarBuilder, config: &Config) -> Result<Self> {
+        Self::load_from(Source::Full(vb.clone()), config)
+    }
+
+    /// `vb` must be the builder the quantized model was loaded from.
+    pub fn load_quantized(vb: &quantized_var_builder::VarBuilder, config: &Config) -> Result<Self> {
+        Self::load_from(Source::Quantized(vb.clone()), config)
+    }
+
+    fn load_from(vb: Source, config: &Config) -> Result<Self> {
+        let vb = vb.pp("model.decoder");
+        let n_state = config.d_model;
+        let n_head = config.decoder_attention_heads;
+        let n_layer = config.decoder_layers;
+
+        let token_embedding = Embedding::new(
+            vb.pp("embed_tokens")
+                .tensor((config.vocab_size, n_state), "weight")?,
+            n_state,
+        );
+        let positional_embedding = vb.tensor(
+            (config.max_target_positions, n_state),
+            "embed_positions.weight",
+        )?;
+        let blocks = (0..n_layer)
+            .map(|i| Block::load(n_state, n_head, vb.pp(format!("layers.{i}"))))
+            .collect::<candle::Result<Vec<_>>>()?;
+        let heads = (n_layer / 2..n_layer)
+            .flat_map(|layer| (0..n_head).map(move |head| (layer, head)))
+            .collect();
+
+        Ok(Self {
+            token_embedding,
+            positional_embedding,
+            blocks,
+            heads,
+        })
+    }
+
+    /// Times in seconds at which each of `text_tokens` starts, plus a final
+    /// entry for where the last one ends, relative to the start of the
+    /// window. `sot_sequence` is the start-of-transcript sequence up to and
+    /// including the no-timestamps token, `audio_features` the encoder output
+    /// the text was decoded from and `frames` how many of its frames hold
+    /// audio rather than padding.
+    pub fn token_times(
+        &self,
+        sot_sequence: &[u32],
+        text_tokens: &[u32],
+        eot_token: u32,
+        audio_features: &Tensor,
+        frames: usize,
+    ) -> Result<Vec<f64>> {
+        let tokens: Vec<u32> = sot_sequence
+            .iter()
+            .chain(text_tokens)
+            .chain([&eot_token])
+            .copied()
+            .collect();
+        let (_, n_audio, _) = audio_features.dims3()?;
+        let frames = frames.clamp(1, n_audio);
+
+        // The weights at a token are spent predicting the next one, so the
+        // rows from the no-timestamps token up to the last text token line
+        // up with the text
+        let first_row = sot_sequence.len() - 1;
+        let rows = text_tokens.len() + 1;
+        let matrix = self.attention_matrix(&tokens, audio_features, frames)?;
+        let matrix = &matrix[first_row..first_row + rows];
+
+        let cost: Vec<Vec<f32>> = matrix
+            .iter()
+            .map(|row| row.iter().map(|w| -w).collect())
+            .collect();
+        let path = dtw(&cost);
+
+        // The first frame on the path for each row is where its token starts
+        let mut times = vec![0f64; rows];
+        let mut previous = None;
+        for (row, frame) in path {
+            if previous != Some(row) {
+                times[row] = frame as f64 * FRAME_SECS;
+                previous = Some(row);
+            }
+        }
+        Ok(times)
+    }
+
+    /// Cross-attention of every token to the first `frames` audio frames,
+    /// standardised and smoothed per head and averaged over the alignment
+    /// heads. Heads are folded in one at a time so the weights of all of
+    /// them are never held at once.
+    fn attention_matrix(
+        &self,
+        tokens: &[u32],
+        audio_features: &Tensor,
+        frames: usize,
+    ) -> Result<Vec<Vec<f32>>> {
+        let device = audio_features.device();
+        let n_ctx = tokens.len();
+        let last_layer = self.heads.iter().map(|&(layer, _)| layer).max();
+
+        let tokens_t = Tensor::new(tokens, device)?.unsqueeze(0)?;
+        let positional_embedding = self.positional_embedding.narrow(0, 0, n_ctx)?;
+        let mut x = self
+            .token_embedding
+            .forward(&tokens_t)?
+            .broadcast_add(&positional_embedding)?;
+        let mask: Vec<f32> = (0..n_ctx)
+            .flat_map(|i| (0..n_ctx).map(move |j| if j > i { f32::NEG_INFINITY } else { 0f32 }))
+            .collect();
+        let mask = Tensor::from_vec(mask, (n_ctx, n_ctx), device)?;
+
+        let mut matrix = vec![vec![0f32; frames]; n_ctx];
+        for (layer, block) in self.blocks.iter().enumerate() {
+            if Some(layer) > last_layer {
+                break;
+            }
+            let (next, weights) = block.forward(&x, audio_features, &mask)?;
+            x = next;
+
+            for &(_, head) in self.heads.iter().filter(|&&(l, _)| l == layer) {
+                let weights: Vec<Vec<f32>> = weights
+                    .i((0, head))?
+                    .narrow(D::Minus1, 0, frames)?
+                    .to_vec2()?;
+                for (sum, row) in matrix.iter_mut().zip(normalize_head(weights)) {
+                    for (sum, w) in sum.iter_mut().zip(row) {
+                        *sum += w;
+                    }
+                }
+            }
+        }
+
+        let n_heads = self.heads.len().max(1) as f32;
+        for row in &mut matrix {
+            for w in row {
+                *w /= n_heads;
+            }
+        }
+        Ok(matrix)
+    }
+}
+
+/// Number of frames of `mel` (`(1, n_mel, n_frames)`) before the zero
+/// padding the spectrogram is extended with. Padding sits at the floor value
+/// in every bin.
+pub fn content_frames(mel: &Tensor) -> Result<usize> {
+    let peaks: Vec<f32> = mel.max(1)?.i(0)?.to_vec1()?;
+    let floor = peaks.iter().copied().fold(f32::INFINITY, f32::min);
+    Ok(peaks
+        .iter()
+        .rposition(|&peak| peak > floor + 1e-6)
+        .map_or(peaks.len(), |last| last + 1))
+}
+
+/// Renormalises each token's weights over the kept frames, standardises each
+/// frame over the tokens, then median-filters each token's weights along
+/// time.
+fn normalize_head(mut weights: Vec<Vec<f32>>) -> Vec<Vec<f32>> {
+    for row in &mut weights {
+        let sum: f32 = row.iter().sum();
+        if sum > 0.0 {
+            row.iter_mut().for_each(|w| *w /= sum);
+        }
+    }
+
+    let n_tokens = weights.len() as f32;
+    let frames = weights.first().map_or(0, Vec::len);
+    for frame in 0..frames {
+        let mean = weights.iter().map(|row| row[frame]).sum::<f32>() / n_tokens;
+        let variance = weights
+            .iter()
+            .map(|row| (row[frame] - mean).powi(2))
+            .sum::<f32>()
+            / n_tokens;
+        let std = variance.sqrt().max(f32::EPSILON);
+        for row in &mut weights {
+            row[frame] = (row[frame] - mean) / std;
+        }
+    }
+
+    weights.into_iter().map(|row| median_filter(&row)).collect()
+}
+
+/// Median over a `MEDFILT_WIDTH` window, reflecting at the edges. Rows too
+/// short to reflect are returned unchanged.
+fn median_filter(row: &[f32]) -> Vec<f32> {
+    let pad = MEDFILT_WIDTH / 2;
+    let n = row.len();
+    if n <= pad {
+        return row.to_vec();
+    }
+
+    let reflect = |i: isize| -> f32 {
+        let n = n as isize;
+        let i = if i < 0 {
+            -i
+        } else if i >= n {
+            2 * (n - 1) - i
+        } else {
+            i
+        };
+        row[i as usize]
+    };
+
+    let mut window = Vec::with_capacity(MEDFILT_WIDTH);
+    (0..n as isize)
+        .map(|center| {
+            window.clear();
+            window.extend((center - pad as isize..=center + pad as isize).map(reflect));
+            window.sort_unstable_by(f32::total_cmp);
+            window[pad]
+        })
+        .collect()
+}
+
+/// Lowest-cost monotonic path through `cost`, as `(row, column)` pairs from
+/// `(0, 0)` to the last cell. Ties are broken as in the reference
+/// implementation.
+fn dtw(cost: &[Vec<f32>]) -> Vec<(usize, usize)> {
+    let n = cost.len();
+    let m = cost.first().map_or(0, Vec::len);
+    if n == 0 || m == 0 {
+        return vec![];
+    }
+
+    let mut total = vec![vec![f32::INFINITY; m + 1]; n + 1];
+    let mut trace = vec![vec![0u8; m + 1]; n + 1];
+    total[0][0] = 0.0;
+
+    for j in 1..=m {
+        for i in 1..=n {
+            let diagonal = total[i - 1][j - 1];
+            let up = total[i - 1][j];
+            let left = total[i][j - 1];
+            let (best, step) = if diagonal < up && diagonal < left {
+                (diagonal, 0)
+            } else if up < diagonal && up < left {
+                (up, 1)
+            } else {
+                (left, 2)
+            };
+            total[i][j] = cost[i - 1][j - 1] + best;
+            trace[i][j] = step;
+        }
+    }
+
+    let mut path = vec![];
+    let (mut i, mut j) = (n, m);
+    while i > 0 && j > 0 {
+        path.push((i - 1, j - 1));
+        match trace[i][j] {
+            0 => {
+                i -= 1;
+                j -= 1;
+            }
+            1 => i -= 1,
+            _ => j -= 1,
+        }
+    }
+    path.reverse();
+    path
+}
@@ -1,12 +1,144 @@
 use anyhow::{Error as E, Result};
 use candle::{Device, IndexOp, Tensor};
-use candle_nn::ops::softmax;
+use candle_nn::ops::{log_softmax, softmax};
+use flate2::{write::GzEncoder, Compression};
 use rand::{distributions::Distribution, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::Write,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 use tokenizers::Tokenizer;
 
+use crate::alignment::{self, Aligner};
 use crate::model::Model;
 use candle_transformers::models::whisper::{self as m};
 
+pub const MAX_BEAM_SIZE: usize = 8;
+
+/// How tokens are picked at temperature 0. Sampling at higher temperatures
+/// during fallback is unaffected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DecodingStrategy {
+    #[default]
+    Greedy,
+    BeamSearch {
+        beam_size: usize,
+    },
+}
+
+impl DecodingStrategy {
+    pub fn from_beam_size(beam_size: Option<usize>) -> Self {
+        match beam_size {
+            Some(beam_size) if beam_size > 1 => Self::BeamSearch { beam_size },
+            _ => Self::Greedy,
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if let Self::BeamSearch { beam_size } = *self {
+            if !(2..=MAX_BEAM_SIZE).contains(&beam_size) {
+                anyhow::bail!("beam size must be between 2 and {MAX_BEAM_SIZE}, got {beam_size}")
+            }
+        }
+        Ok(())
+    }
+}
+
+/// When a window is decoded again at the next temperature. Defaults follow
+/// the reference implementation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FallbackConfig {
+    /// Tried in order until a result passes the thresholds.
+    pub temperatures: Vec<f64>,
+    /// Text that gzips better than this is likely a repetition loop.
+    pub compression_ratio_threshold: f64,
+    pub logprob_threshold: f64,
+    /// Windows above this no-speech probability (with a low logprob) are
+    /// treated as silence and dropped.
+    pub no_speech_threshold: f64,
+}
+
+impl Default for FallbackConfig {
+    fn default() -> Self {
+        Self {
+            temperatures: m::TEMPERATURES.to_vec(),
+            compression_ratio_threshold: m::COMPRESSION_RATIO_THRESHOLD,
+            logprob_threshold: m::LOGPROB_THRESHOLD,
+            no_speech_threshold: m::NO_SPEECH_THRESHOLD,
+        }
+    }
+}
+
+impl FallbackConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.temperatures.is_empty() {
+            anyhow::bail!("at least one decoding temperature is required")
+        }
+        if let Some(t) = self.temperatures.iter().find(|t| !(0.0..=2.0).contains(*t)) {
+            anyhow::bail!("decoding temperatures must be between 0 and 2, got {t}")
+        }
+        if self.compression_ratio_threshold <= 0.0 {
+            anyhow::bail!("compression ratio threshold must be positive")
+        }
+        Ok(())
+    }
+
+    fn needs_fallback(&self, dr: &DecodingResult) -> bool {
+        let failed = dr.compression_ratio > self.compression_ratio_threshold
+            || dr.avg_logprob < self.logprob_threshold;
+        // Silence is expected to decode poorly; retrying it only invents text
+        failed && dr.no_speech_prob <= self.no_speech_threshold
+    }
+}
+
+/// Errors from `Decoder::run` that callers may want to tell apart from
+/// genuine failures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecoderError {
+    /// The cancellation token was set; decoding stopped between windows.
+    Cancelled,
+}
+
+impl std::fmt::Display for DecoderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Cancelled => write!(f, "transcription cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for DecoderError {}
+
+/// Ratio of the text's size to its gzip-compressed size. Repetitive output
+/// compresses unusually well.
+pub fn compression_ratio(text: &str) -> f64 {
+    if text.is_empty() {
+        return 0.0;
+    }
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    match encoder
+        .write_all(text.as_bytes())
+        .and_then(|_| encoder.finish())
+    {
+        Ok(compressed) => text.len() as f64 / compressed.len() as f64,
+        Err(_) => 0.0,
+    }
+}
+
+impl std::fmt::Display for DecodingStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Greedy => write!(f, "greedy"),
+            Self::BeamSearch { beam_size } => write!(f, "beam search (size {beam_size})"),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct DecodingResult {
     pub tokens: Vec<u32>,
@@ -15,13 +147,55 @@ pub struct DecodingResult {
     pub no_speech_prob: f64,
     pub temperature: f64,
     pub compression_ratio: f64,
+    pub strategy: DecodingStrategy,
+}
+
+#[derive(Debug, Clone)]
+struct Beam {
+    tokens: Vec<u32>,
+    sum_logprob: f64,
+}
+
+impl Beam {
+    /// Averaged over the tokens after the `prefix_len` prompt tokens.
+    fn avg_logprob(&self, prefix_len: usize) -> f64 {
+        self.sum_logprob / (self.tokens.len() - prefix_len) as f64
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TranscriptionProgress {
+    pub segments_done: usize,
+    pub segments_total: usize,
+}
+
+impl TranscriptionProgress {
+    pub fn fraction(&self) -> f64 {
+        if self.segments_total == 0 {
+            return 1.0;
+        }
+        (self.segments_done as f64 / self.segments_total as f64).min(1.0)
+    }
 }
 
+pub type ProgressCallback = Arc<dyn Fn(TranscriptionProgress) + Send + Sync>;
+
 #[derive(Debug, Clone)]
 pub struct Segment {
     pub start: f64,
     pub duration: f64,
     pub dr: DecodingResult,
+    /// Empty unless word timestamps were requested.
+    pub words: Vec<Word>,
+}
+
+/// A word with its time span in seconds from the start of the recording, see
+/// `WhisperConfig::word_timestamps`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Word {
+    pub text: String,
+    pub start: f64,
+    pub end: f64,
 }
 
 pub struct Decoder {
@@ -29,6 +203,7 @@ pub struct Decoder {
     rng: rand::rngs::StdRng,
     timestamps: bool,
     verbose: bool,
+    strategy: DecodingStrategy,
     tokenizer: Tokenizer,
     suppress_tokens: Tensor,
     sot_token: u32,
@@ -37,8 +212,17 @@ pub struct Decoder {
     no_speech_token: u32,
     no_timestamps_token: u32,
     language_token: Option<u32>,
+    progress: Option<ProgressCallback>,
+    aligner: Option<Arc<Aligner>>,
+    /// Encoder output of the window decoded last, kept for the aligner.
+    audio_features: Option<Tensor>,
+    fallback: FallbackConfig,
+    cancel: Option<Arc<AtomicBool>>,
+    prompt_tokens: Vec<u32>,
 }
 
+const SOT_PREV_TOKEN: &str = "<|startofprev|>";
+
 pub fn token_id(tokenizer: &Tokenizer, token: &str) -> candle::Result<u32> {
     match tokenizer.token_to_id(token) {
         None => candle::bail!("no token-id for {token}"),
@@ -46,6 +230,30 @@ pub fn token_id(tokenizer: &Tokenizer, token: &str) -> candle::Result<u32> {
     }
 }
 
+/// Tokenizes `prompt` as previous-text context for the decoder. Only the last
+/// tokens that fit in half the model's context are kept, as in the reference
+/// implementation, so a long vocabulary list can't crowd out the transcript.
+pub fn encode_prompt(
+    tokenizer: &Tokenizer,
+    prompt: &str,
+    max_target_positions: usize,
+) -> Result<Vec<u32>> {
+    let prompt = prompt.trim();
+    if prompt.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let encoding = tokenizer
+        .encode(format!(" {prompt}"), false)
+        .map_err(E::msg)?;
+    let ids = encoding.get_ids();
+    let keep = (max_target_positions / 2).saturating_sub(1).min(ids.len());
+
+    let mut tokens = vec![token_id(tokenizer, SOT_PREV_TOKEN)?];
+    tokens.extend_from_slice(&ids[ids.len() - keep..]);
+    Ok(tokens)
+}
+
 impl Decoder {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
@@ -56,7 +264,9 @@ impl Decoder {
         language_token: Option<u32>,
         timestamps: bool,
         verbose: bool,
+        strategy: DecodingStrategy,
     ) -> Result<Self> {
+        strategy.validate()?;
         let no_timestamps_token = token_id(&tokenizer, m::NO_TIMESTAMPS_TOKEN)?;
         let suppress_tokens: Vec<f32> = (0..model.config().vocab_size as u32)
             .map(|i| {
@@ -86,6 +296,7 @@ impl Decoder {
             tokenizer,
             timestamps,
             verbose,
+            strategy,
             suppress_tokens,
             sot_token,
             transcribe_token,
@@ -93,26 +304,88 @@ impl Decoder {
             no_speech_token,
             language_token,
             no_timestamps_token,
+            progress: None,
+            aligner: None,
+            audio_features: None,
+            fallback: FallbackConfig::default(),
+            cancel: None,
+            prompt_tokens: vec![],
         })
     }
 
+    pub fn with_progress(mut self, progress: Option<ProgressCallback>) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// `fallback` should be validated first; an empty schedule decodes once
+    /// at temperature 0.
+    pub fn with_fallback(mut self, fallback: FallbackConfig) -> Self {
+        self.fallback = fallback;
+        self
+    }
+
+    /// Decoding stops before the next window once `cancel` is set.
+    pub fn with_cancellation(mut self, cancel: Option<Arc<AtomicBool>>) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    fn check_cancelled(&self) -> Result<()> {
+        match &self.cancel {
+            Some(cancel) if cancel.load(Ordering::SeqCst) => Err(DecoderError::Cancelled.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Context the model sees before every window, from `encode_prompt`.
+    pub fn with_prompt_tokens(mut self, prompt_tokens: Vec<u32>) -> Self {
+        self.prompt_tokens = prompt_tokens;
+        self
+    }
+
+    /// Segments get word timings when an aligner is given.
+    pub fn with_aligner(mut self, aligner: Option<Arc<Aligner>>) -> Self {
+        self.aligner = aligner;
+        self
+    }
+
+    /// The prompt followed by the start-of-transcript sequence. Decoded tokens
+    /// are appended after it, and the prompt is stripped again afterwards.
+    fn initial_tokens(&self) -> Vec<u32> {
+        let mut tokens = self.prompt_tokens.clone();
+        tokens.push(self.sot_token);
+        if let Some(language_token) = self.language_token {
+            tokens.push(language_token);
+        }
+        tokens.push(self.transcribe_token);
+        if !self.timestamps {
+            tokens.push(self.no_timestamps_token);
+        }
+        tokens
+    }
+
     fn decode(&mut self, mel: &Tensor, t: f64) -> Result<DecodingResult> {
+        if let DecodingStrategy::BeamSearch { beam_size } = self.strategy {
+            if t == 0f64 {
+                return self.decode_beam(mel, beam_size);
+            }
+        }
+
+        let initial_tokens = self.initial_tokens();
+        let prompt_len = self.prompt_tokens.len();
         let model = &mut self.model;
         let audio_features = model.encoder_forward(mel, true)?;
+        if self.aligner.is_some() {
+            self.audio_features = Some(audio_features.clone());
+        }
         if self.verbose {
             log::info!("audio features: {:?}", audio_features.dims());
         }
         let sample_len = model.config().max_target_positions / 2;
         let mut sum_logprob = 0f64;
         let mut no_speech_prob = f64::NAN;
-        let mut tokens = vec![self.sot_token];
-        if let Some(language_token) = self.language_token {
-            tokens.push(language_token);
-        }
-        tokens.push(self.transcribe_token);
-        if !self.timestamps {
-            tokens.push(self.no_timestamps_token);
-        }
+        let mut tokens = initial_tokens;
 
         for i in 0..sample_len {
             let tokens_t = Tensor::new(tokens.as_slice(), mel.device())?;
@@ -120,7 +393,11 @@ impl Decoder {
             let ys = model.decoder_forward(&tokens_t, &audio_features, i == 0)?;
 
             if i == 0 {
-                let logits = model.decoder_final_linear(&ys.i(..1)?)?.i(0)?.i(0)?;
+                // Read at the start-of-transcript token, which follows the prompt
+                let logits = model
+                    .decoder_final_linear(&ys.i(..1)?)?
+                    .i(0)?
+                    .i(prompt_len)?;
                 no_speech_prob = softmax(&logits, 0)?
                     .i(self.no_speech_token as usize)?
                     .to_scalar::<f32>()? as f64;
@@ -156,62 +433,208 @@ impl Decoder {
             }
             sum_logprob += prob.ln();
         }
+        tokens.drain(..prompt_len);
         let text = self.tokenizer.decode(&tokens, true).map_err(E::msg)?;
         let avg_logprob = sum_logprob / tokens.len() as f64;
 
         Ok(DecodingResult {
             tokens,
+            compression_ratio: compression_ratio(&text),
             text,
             avg_logprob,
             no_speech_prob,
             temperature: t,
-            compression_ratio: f64::NAN,
+            strategy: DecodingStrategy::Greedy,
+        })
+    }
+
+    fn decode_beam(&mut self, mel: &Tensor, beam_size: usize) -> Result<DecodingResult> {
+        let initial_tokens = self.initial_tokens();
+        let prompt_len = self.prompt_tokens.len();
+        let model = &mut self.model;
+        let audio_features = model.encoder_forward(mel, true)?;
+        if self.aligner.is_some() {
+            self.audio_features = Some(audio_features.clone());
+        }
+        if self.verbose {
+            log::info!("audio features: {:?}", audio_features.dims());
+        }
+        let sample_len = model.config().max_target_positions / 2;
+        let max_target_positions = model.config().max_target_positions;
+        let mut no_speech_prob = f64::NAN;
+
+        let mut beams = vec![Beam {
+            tokens: initial_tokens,
+            sum_logprob: 0f64,
+        }];
+        let mut finished: Vec<Beam> = Vec::new();
+
+        for i in 0..sample_len {
+            let mut candidates: Vec<Beam> = Vec::with_capacity(beams.len() * beam_size);
+
+            for (beam_index, beam) in beams.iter().enumerate() {
+                let tokens_t = Tensor::new(beam.tokens.as_slice(), mel.device())?.unsqueeze(0)?;
+                // The cross-attention cache only depends on the audio, so it is
+                // computed once and shared by every beam
+                let flush_kv_cache = i == 0 && beam_index == 0;
+                let ys = model.decoder_forward(&tokens_t, &audio_features, flush_kv_cache)?;
+
+                if i == 0 {
+                    let logits = model
+                        .decoder_final_linear(&ys.i(..1)?)?
+                        .i(0)?
+                        .i(prompt_len)?;
+                    no_speech_prob = softmax(&logits, 0)?
+                        .i(self.no_speech_token as usize)?
+                        .to_scalar::<f32>()? as f64;
+                }
+
+                let (_, seq_len, _) = ys.dims3()?;
+                let logits = model
+                    .decoder_final_linear(&ys.i((..1, seq_len - 1..))?)?
+                    .i(0)?
+                    .i(0)?
+                    .broadcast_add(&self.suppress_tokens)?;
+                let logprobs: Vec<f32> = log_softmax(&logits, candle::D::Minus1)?.to_vec1()?;
+
+                let mut ranked: Vec<(usize, f32)> = logprobs
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(_, lp)| lp.is_finite())
+                    .collect();
+                ranked.sort_unstable_by(|(_, a), (_, b)| b.total_cmp(a));
+
+                for (token, logprob) in ranked.into_iter().take(beam_size) {
+                    let mut tokens = beam.tokens.clone();
+                    tokens.push(token as u32);
+                    candidates.push(Beam {
+                        tokens,
+                        sum_logprob: beam.sum_logprob + logprob as f64,
+                    });
+                }
+            }
+
+            candidates.sort_unstable_by(|a, b| b.sum_logprob.total_cmp(&a.sum_logprob));
+
+            beams.clear();
+            for candidate in candidates {
+                let last = *candidate.tokens.last().unwrap_or(&self.eot_token);
+                if last == self.eot_token || candidate.tokens.len() > max_target_positions {
+                    finished.push(candidate);
+                } else if beams.len() < beam_size {
+                    beams.push(candidate);
+                }
+                if beams.len() >= beam_size {
+                    break;
+                }
+            }
+
+            if beams.is_empty() || finished.len() >= beam_size {
+                break;
+            }
+        }
+
+        let best = finished
+            .into_iter()
+            .chain(beams)
+            .max_by(|a, b| {
+                a.avg_logprob(prompt_len)
+                    .total_cmp(&b.avg_logprob(prompt_len))
+            })
+            .ok_or_else(|| anyhow::anyhow!("beam search produced no hypotheses"))?;
+
+        let avg_logprob = best.avg_logprob(prompt_len);
+        let tokens = best.tokens[prompt_len..].to_vec();
+        let text = self.tokenizer.decode(&tokens, true).map_err(E::msg)?;
+
+        Ok(DecodingResult {
+            avg_logprob,
+            tokens,
+            compression_ratio: compression_ratio(&text),
+            text,
+            no_speech_prob,
+            temperature: 0f64,
+            strategy: DecodingStrategy::BeamSearch { beam_size },
         })
     }
 
     fn decode_with_fallback(&mut self, segment: &Tensor) -> Result<DecodingResult> {
-        for (i, &t) in m::TEMPERATURES.iter().enumerate() {
+        let temperatures = self.fallback.temperatures.clone();
+        let last = temperatures.len().saturating_sub(1);
+
+        for (i, &t) in temperatures.iter().enumerate() {
+            if i > 0 {
+                self.check_cancelled()?;
+            }
             let dr: Result<DecodingResult> = self.decode(segment, t);
-            if i == m::TEMPERATURES.len() - 1 {
+            if i == last {
                 return dr;
             }
             match dr {
                 Ok(dr) => {
-                    let needs_fallback = dr.compression_ratio > m::COMPRESSION_RATIO_THRESHOLD
-                        || dr.avg_logprob < m::LOGPROB_THRESHOLD;
-                    if !needs_fallback || dr.no_speech_prob > m::NO_SPEECH_THRESHOLD {
+                    if !self.fallback.needs_fallback(&dr) {
                         return Ok(dr);
                     }
+                    log::info!(
+                        "Retrying window decoded at {t}: compression ratio {:.2}, avg logprob {:.2}",
+                        dr.compression_ratio,
+                        dr.avg_logprob
+                    );
                 }
                 Err(err) => {
                     log::info!("Error running at {t}: {err}")
                 }
             }
         }
-        unreachable!()
+        self.decode(segment, 0f64)
     }
 
     pub fn run(&mut self, mel: &Tensor) -> Result<Vec<Segment>> {
         let (_, _, content_frames) = mel.dims3()?;
+        let segments_total = content_frames.div_ceil(m::N_FRAMES);
+        let mut segments_done = 0;
         let mut seek = 0;
         let mut segments = vec![];
         while seek < content_frames {
+            self.check_cancelled()?;
             let segment_size = usize::min(content_frames - seek, m::N_FRAMES);
             let mel_segment = mel.narrow(2, seek, segment_size)?;
             let segment_duration = (segment_size * m::HOP_LENGTH) as f64 / m::SAMPLE_RATE as f64;
             let time_offset = (seek * m::HOP_LENGTH) as f64 / m::SAMPLE_RATE as f64;
             let dr = self.decode_with_fallback(&mel_segment)?;
+            if self.verbose {
+                log::info!(
+                    "{time_offset:.1}s: decoded with {} at temperature {}",
+                    dr.strategy,
+                    dr.temperature
+                );
+            }
 
             seek += segment_size;
+            segments_done += 1;
+            if let Some(progress) = &self.progress {
+                progress(TranscriptionProgress {
+                    segments_done,
+                    segments_total,
+                });
+            }
 
-            if dr.no_speech_prob > m::NO_SPEECH_THRESHOLD && dr.avg_logprob < m::LOGPROB_THRESHOLD {
+            if dr.no_speech_prob > self.fallback.no_speech_threshold
+                && dr.avg_logprob < self.fallback.logprob_threshold
+            {
                 continue;
             }
 
+            let words = match &self.aligner {
+                Some(aligner) => self.align_words(aligner, &dr, &mel_segment, time_offset)?,
+                None => vec![],
+            };
+
             let segment = Segment {
                 start: time_offset,
                 duration: segment_duration,
                 dr,
+                words,
             };
 
             segments.push(segment);
@@ -219,6 +642,74 @@ impl Decoder {
         Ok(segments)
     }
 
+    /// Times the words of a decoded window by aligning its text tokens to the
+    /// audio through the decoder's cross-attention. A word runs from the start
+    /// of its first token to the start of the token after its last.
+    fn align_words(
+        &self,
+        aligner: &Aligner,
+        dr: &DecodingResult,
+        mel: &Tensor,
+        offset: f64,
+    ) -> Result<Vec<Word>> {
+        // Everything from EOT upwards, timestamps included, is a control token
+        let text_tokens: Vec<u32> = dr
+            .tokens
+            .iter()
+            .copied()
+            .filter(|&token| token < self.eot_token)
+            .collect();
+        if text_tokens.is_empty() {
+            return Ok(vec![]);
+        }
+        let audio_features = self
+            .audio_features
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("no audio features to align against"))?;
+
+        let mut sot_sequence = vec![self.sot_token];
+        sot_sequence.extend(self.language_token);
+        sot_sequence.extend([self.transcribe_token, self.no_timestamps_token]);
+        let frames = alignment::content_frames(mel)?.div_ceil(2);
+        let times = aligner.token_times(
+            &sot_sequence,
+            &text_tokens,
+            self.eot_token,
+            audio_features,
+            frames,
+        )?;
+
+        // A new word starts where a token begins with a space
+        let mut starts = vec![];
+        for (index, &token) in text_tokens.iter().enumerate() {
+            let piece = self.tokenizer.decode(&[token], false).map_err(E::msg)?;
+            if index == 0 || piece.starts_with(' ') {
+                starts.push(index);
+            }
+        }
+        starts.push(text_tokens.len());
+
+        let mut words = vec![];
+        for bounds in starts.windows(2) {
+            let (first, end) = (bounds[0], bounds[1]);
+            let text = self
+                .tokenizer
+                .decode(&text_tokens[first..end], false)
+                .map_err(E::msg)?;
+            let text = text.trim();
+            if text.is_empty() {
+                continue;
+            }
+            words.push(Word {
+                text: text.to_string(),
+                start: offset + times[first],
+                end: offset + times[end],
+            });
+        }
+
+        Ok(words)
+    }
+
     pub fn take_model(self) -> Model {
         // Take ownership of the model
         self.model
@@ -0,0 +1,124 @@
+//! Fetches stock models from the Hugging Face Hub, for apps that don't ship
+//! the weights with their installer.
+
+use crate::model::{ModelFiles, WhichModel};
+use anyhow::{Context, Result};
+use hf_hub::{
+    api::{sync::ApiBuilder, Progress},
+    Repo, RepoType,
+};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Everything `ModelFiles::locate` needs, the weights last so a directory is
+/// only complete once they are in place.
+const MODEL_FILES: [&str; 3] = ["config.json", "tokenizer.json", "model.safetensors"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadProgress {
+    pub file: String,
+    pub file_index: usize,
+    pub file_count: usize,
+    pub downloaded_bytes: u64,
+    pub total_bytes: u64,
+}
+
+struct Reporter<'a, F> {
+    on_progress: &'a mut F,
+    progress: DownloadProgress,
+}
+
+impl<F: FnMut(&DownloadProgress)> Progress for Reporter<'_, F> {
+    fn init(&mut self, size: usize, _filename: &str) {
+        self.progress.total_bytes = size as u64;
+        self.progress.downloaded_bytes = 0;
+        (self.on_progress)(&self.progress);
+    }
+
+    fn update(&mut self, size: usize) {
+        self.progress.downloaded_bytes += size as u64;
+        (self.on_progress)(&self.progress);
+    }
+
+    fn finish(&mut self) {
+        self.progress.downloaded_bytes = self.progress.total_bytes;
+        (self.on_progress)(&self.progress);
+    }
+}
+
+/// Downloads `model` into `models_dir/<dir_name>` unless it is already there
+/// and returns that directory. Files are fetched into a cache under
+/// `models_dir`, copied into a sibling staging directory and renamed into
+/// place in one step, so an interrupted download never leaves a directory
+/// that looks complete.
+pub fn download_model<F>(
+    model: WhichModel,
+    models_dir: &Path,
+    mut on_progress: F,
+) -> Result<PathBuf>
+where
+    F: FnMut(&DownloadProgress),
+{
+    let target = models_dir.join(model.dir_name());
+    if ModelFiles::locate(&target).is_ok() {
+        return Ok(target);
+    }
+
+    let cache_dir = models_dir.join(".cache");
+    let api = ApiBuilder::new()
+        .with_progress(false)
+        .with_cache_dir(cache_dir.clone())
+        .build()?;
+    let (model_id, revision) = model.model_and_revision();
+    let repo = api.repo(Repo::with_revision(
+        model_id.to_string(),
+        RepoType::Model,
+        revision.to_string(),
+    ));
+
+    let mut fetched = Vec::with_capacity(MODEL_FILES.len());
+    for (file_index, file) in MODEL_FILES.into_iter().enumerate() {
+        let reporter = Reporter {
+            on_progress: &mut on_progress,
+            progress: DownloadProgress {
+                file: file.to_string(),
+                file_index,
+                file_count: MODEL_FILES.len(),
+                downloaded_bytes: 0,
+                total_bytes: 0,
+            },
+        };
+        let path = repo
+            .download_with_progress(file, reporter)
+            .with_context(|| format!("failed to download {file} of {model_id}"))?;
+        fetched.push((file, path));
+    }
+
+    // Leftovers of an earlier interrupted attempt
+    let staging = models_dir.join(format!(".{}.partial", model.dir_name()));
+    if staging.exists() {
+        std::fs::remove_dir_all(&staging)?;
+    }
+    std::fs::create_dir_all(&staging)?;
+    for (file, path) in fetched {
+        std::fs::copy(&path, staging.join(file))
+            .with_context(|| format!("failed to copy {file} into {}", staging.display()))?;
+    }
+    // An incomplete directory that `locate` rejected would block the rename
+    if target.exists() {
+        std::fs::remove_dir_all(&target)?;
+    }
+    std::fs::rename(&staging, &target)
+        .with_context(|| format!("failed to move the model into {}", target.display()))?;
+
+    // The cache holds a second copy of the weights
+    if let Err(e) = std::fs::remove_dir_all(&cache_dir) {
+        log::warn!(
+            "Failed to remove download cache {}: {}",
+            cache_dir.display(),
+            e
+        );
+    }
+
+    Ok(target)
+}
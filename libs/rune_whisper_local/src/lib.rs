@@ -1,32 +1,117 @@
+pub mod alignment;
 pub mod decoder;
+pub mod download;
 pub mod model;
 pub mod multilingual;
 pub mod pcm_decode;
+pub mod streaming;
+pub mod vad;
 
+use alignment::Aligner;
 use anyhow::Result;
 use candle::Device;
 use candle::Tensor;
-use candle_transformers::models::whisper::{self as m, Config};
-use model::Model;
-use std::path::PathBuf;
+use candle_transformers::models::whisper::{self as m};
+use decoder::{DecodingStrategy, FallbackConfig, ProgressCallback, Word};
+use model::{mel_filter_bytes, Model, ModelFiles, Weights};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{atomic::AtomicBool, Arc},
+    time::{Duration, Instant},
+};
 use tokenizers::Tokenizer;
 
+#[derive(Clone)]
 pub struct WhisperConfig {
     pub device: Device,
+    /// Called after each decoded 30s window. Optional so the library works
+    /// without any UI attached.
+    pub progress: Option<ProgressCallback>,
     pub timestamps: bool,
+    /// Fill in `TranscriptSegment::words`, aligned to the audio by dynamic
+    /// time warping over the decoder's cross-attention. Costs one more
+    /// decoder pass per window; the weights are shared with the model.
+    pub word_timestamps: bool,
     pub seed: u64,
     pub model_dir: Option<PathBuf>,
     pub verbose: bool,
+    pub strategy: DecodingStrategy,
+    /// Temperature schedule and thresholds for re-decoding windows that look
+    /// like repetition loops or garbage.
+    pub fallback: FallbackConfig,
+    /// Text shown to the model as preceding context for every window, which
+    /// biases it towards the spelling of names and jargon it contains.
+    pub initial_prompt: Option<String>,
+    /// Decode only the stretches an energy-based voice activity detector
+    /// marks as speech, skipping long pauses.
+    pub vad_enabled: bool,
 }
 
 impl WhisperConfig {
     pub fn new(model_dir: Option<PathBuf>) -> Self {
         Self {
             device: Device::Cpu,
+            progress: None,
             timestamps: false,
+            word_timestamps: false,
             seed: 299792458,
             model_dir,
             verbose: false,
+            strategy: DecodingStrategy::default(),
+            fallback: FallbackConfig::default(),
+            initial_prompt: None,
+            vad_enabled: false,
+        }
+    }
+
+    /// Runs the model on `device`. An unavailable GPU falls back to the CPU
+    /// when the model is loaded.
+    pub fn with_device(mut self, device: Device) -> Self {
+        self.device = device;
+        self
+    }
+}
+
+/// Metal or CUDA when this crate was built with support for it and a device
+/// is present, otherwise the CPU.
+pub fn preferred_device() -> Device {
+    if candle::utils::metal_is_available() {
+        if let Ok(device) = Device::new_metal(0) {
+            return device;
+        }
+    }
+    if candle::utils::cuda_is_available() {
+        if let Ok(device) = Device::new_cuda(0) {
+            return device;
+        }
+    }
+    Device::Cpu
+}
+
+/// One decoded window of audio. Times are in seconds from the start of the
+/// recording.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptSegment {
+    pub text: String,
+    pub start: f64,
+    pub end: f64,
+    pub avg_logprob: f32,
+    pub no_speech_prob: f32,
+    #[serde(default)]
+    pub words: Vec<Word>,
+}
+
+impl From<decoder::Segment> for TranscriptSegment {
+    fn from(segment: decoder::Segment) -> Self {
+        Self {
+            text: segment.dr.text,
+            start: segment.start,
+            end: segment.start + segment.duration,
+            avg_logprob: segment.dr.avg_logprob as f32,
+            no_speech_prob: segment.dr.no_speech_prob as f32,
+            words: segment.words,
         }
     }
 }
@@ -34,39 +119,32 @@ impl WhisperConfig {
 pub struct Whisper {
     config: WhisperConfig,
     model: Option<Model>,
+    /// Loaded with the model when word timestamps are on.
+    aligner: Option<Arc<Aligner>>,
     tokenizer: Tokenizer,
     mel_filters: Vec<f32>,
     device: Device,
     language_token: Option<u32>,
+    cancel: Option<Arc<AtomicBool>>,
 }
 
 impl Whisper {
-    pub fn new(config: WhisperConfig) -> Result<Self> {
-        let device = &config.device.clone();
+    pub fn new(mut config: WhisperConfig) -> Result<Self> {
+        let mut device = Self::checked_device(&config.device);
 
-        // Get paths to model files
-        let (config_filename, tokenizer_filename, weights_filename) =
-            if let Some(model_dir) = &config.model_dir {
-                (
-                    model_dir.join("config.json"),
-                    model_dir.join("tokenizer.json"),
-                    model_dir.join("model.safetensors"),
-                )
-            } else {
-                anyhow::bail!("model_dir must be specified")
-            };
+        let model_dir = config
+            .model_dir
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("model_dir must be specified"))?;
+        let files = ModelFiles::locate(model_dir)?;
 
         // Load configuration and tokenizer
-        let model_config: Config =
-            serde_json::from_str(&std::fs::read_to_string(config_filename)?)?;
-        let tokenizer = Tokenizer::from_file(tokenizer_filename).map_err(anyhow::Error::msg)?;
+        let model_config = files.load_config()?;
+        let tokenizer = Tokenizer::from_file(&files.tokenizer).map_err(anyhow::Error::msg)?;
 
         // Load mel filters
-        let mel_bytes = match model_config.num_mel_bins {
-            80 => include_bytes!("melfilters.bytes").as_slice(),
-            128 => include_bytes!("melfilters128.bytes").as_slice(),
-            nmel => anyhow::bail!("unexpected num_mel_bins {nmel}"),
-        };
+        let mel_bytes = mel_filter_bytes(model_config.num_mel_bins)
+            .ok_or_else(|| anyhow::anyhow!("unexpected num_mel_bins"))?;
         let mut mel_filters = vec![0f32; mel_bytes.len() / 4];
         <byteorder::LittleEndian as byteorder::ByteOrder>::read_f32_into(
             mel_bytes,
@@ -74,10 +152,22 @@ impl Whisper {
         );
 
         // Create model
-        let vb = unsafe {
-            candle_nn::VarBuilder::from_mmaped_safetensors(&[weights_filename], m::DTYPE, device)?
+        let word_timestamps = config.word_timestamps;
+        let (model, aligner) = match Self::load_model(
+            &files.weights,
+            model_config.clone(),
+            &device,
+            word_timestamps,
+        ) {
+            Ok(loaded) => loaded,
+            Err(e) if !device.is_cpu() => {
+                log::warn!("Failed to load model on {:?}, using CPU: {}", device, e);
+                device = Device::Cpu;
+                Self::load_model(&files.weights, model_config, &device, word_timestamps)?
+            }
+            Err(e) => return Err(e),
         };
-        let model = Model::Normal(m::model::Whisper::load(&vb, model_config)?);
+        config.device = device.clone();
 
         // Get English language token
         let language_token = decoder::token_id(&tokenizer, "<|en|>")
@@ -86,39 +176,228 @@ impl Whisper {
         Ok(Self {
             config,
             model: Some(model),
+            aligner: aligner.map(Arc::new),
             tokenizer,
             mel_filters,
-            device: device.clone(),
+            device,
             language_token: Some(language_token),
+            cancel: None,
         })
     }
 
+    /// Returns `device` if it can actually run tensor ops, and the CPU with a
+    /// warning otherwise, so a missing GPU doesn't surface as a panic in candle.
+    fn checked_device(device: &Device) -> Device {
+        if device.is_cpu() {
+            return Device::Cpu;
+        }
+
+        match Tensor::zeros(1, candle::DType::F32, device).and_then(|t| t.to_vec1::<f32>()) {
+            Ok(_) => device.clone(),
+            Err(e) => {
+                log::warn!("Device {:?} is not available, using CPU: {}", device, e);
+                Device::Cpu
+            }
+        }
+    }
+
+    /// Loads the model, and with `word_timestamps` an aligner that shares its
+    /// decoder weights.
+    fn load_model(
+        weights: &Weights,
+        config: m::Config,
+        device: &Device,
+        word_timestamps: bool,
+    ) -> Result<(Model, Option<Aligner>)> {
+        Ok(match weights {
+            Weights::Safetensors(path) => {
+                // Tensors are read once, already in the model's dtype, so both
+                // builders hand out the same storage instead of loading a copy
+                // each
+                let safetensors = unsafe { candle::safetensors::MmapedSafetensors::new(path)? };
+                let tensors = safetensors
+                    .tensors()
+                    .into_iter()
+                    .map(|(name, _)| {
+                        let tensor = safetensors.load(&name, device)?.to_dtype(m::DTYPE)?;
+                        Ok((name, tensor))
+                    })
+                    .collect::<candle::Result<HashMap<_, _>>>()?;
+                let vb = candle_nn::VarBuilder::from_tensors(tensors, m::DTYPE, device);
+
+                let aligner = if word_timestamps {
+                    Some(Aligner::load(&vb, &config)?)
+                } else {
+                    None
+                };
+                (
+                    Model::Normal(m::model::Whisper::load(&vb, config)?),
+                    aligner,
+                )
+            }
+            Weights::Gguf(path) => {
+                let vb = candle_transformers::quantized_var_builder::VarBuilder::from_gguf(
+                    path, device,
+                )?;
+                let aligner = if word_timestamps {
+                    Some(Aligner::load_quantized(&vb, &config)?)
+                } else {
+                    None
+                };
+                (
+                    Model::Quantized(m::quantized_model::Whisper::load(&vb, config)?),
+                    aligner,
+                )
+            }
+        })
+    }
+
+    pub fn model_dir(&self) -> Option<&Path> {
+        self.config.model_dir.as_deref()
+    }
+
+    /// Loads the model in `model_dir` with the current settings. The loaded
+    /// model is only replaced once the new one is ready, so a failed reload
+    /// leaves this instance usable.
+    pub fn reload_model(&mut self, model_dir: PathBuf) -> Result<()> {
+        let mut config = self.config.clone();
+        config.model_dir = Some(model_dir);
+
+        let reloaded = Self::new(config)?;
+        *self = Self {
+            cancel: self.cancel.take(),
+            ..reloaded
+        };
+        Ok(())
+    }
+
+    pub fn set_decoding_strategy(&mut self, strategy: DecodingStrategy) -> Result<()> {
+        strategy.validate()?;
+        self.config.strategy = strategy;
+        Ok(())
+    }
+
+    pub fn decoding_strategy(&self) -> DecodingStrategy {
+        self.config.strategy
+    }
+
+    pub fn set_initial_prompt(&mut self, initial_prompt: Option<String>) {
+        self.config.initial_prompt = initial_prompt;
+    }
+
+    pub fn initial_prompt(&self) -> Option<&str> {
+        self.config.initial_prompt.as_deref()
+    }
+
+    pub fn set_vad_enabled(&mut self, vad_enabled: bool) {
+        self.config.vad_enabled = vad_enabled;
+    }
+
+    pub fn vad_enabled(&self) -> bool {
+        self.config.vad_enabled
+    }
+
+    /// Token checked between decoded windows. Once set, transcription stops
+    /// with `DecoderError::Cancelled`.
+    pub fn set_cancel_token(&mut self, cancel: Option<Arc<AtomicBool>>) {
+        self.cancel = cancel;
+    }
+
     pub fn transcribe(&mut self, audio_path: PathBuf) -> Result<Vec<String>> {
-        // Process audio
+        Ok(self
+            .transcribe_segments(audio_path)?
+            .into_iter()
+            .map(|segment| segment.text)
+            .collect())
+    }
+
+    /// Returns the ISO code (e.g. "es") of the language spoken in the first
+    /// 30 seconds of `audio_path`, without transcribing it.
+    pub fn detect_language(&mut self, audio_path: PathBuf) -> Result<String> {
+        let mel = self.load_mel(audio_path)?;
+        let model = self
+            .model
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("Model not available"))?;
+
+        let token = multilingual::detect_language(model, &self.tokenizer, &mel)?;
+        multilingual::language_code(&self.tokenizer, token)
+            .ok_or_else(|| anyhow::anyhow!("unknown language token {token}"))
+    }
+
+    /// Decodes a second of silence so the first real transcription doesn't
+    /// pay for lazy kernel setup. Returns how long that took.
+    pub fn warmup(&mut self) -> Result<Duration> {
+        let started = Instant::now();
+        let mel = self.pcm_mel(&vec![0.0; m::SAMPLE_RATE])?;
         let model = self
             .model
             .take()
             .ok_or_else(|| anyhow::anyhow!("Model not available"))?;
 
-        let (pcm_data, sample_rate) = pcm_decode::pcm_decode(audio_path)?;
-        if sample_rate != m::SAMPLE_RATE as u32 {
-            anyhow::bail!("input file must have a {} sampling rate", m::SAMPLE_RATE)
-        }
+        // One pass is enough; retries at higher temperatures would only add time
+        let fallback = FallbackConfig {
+            temperatures: vec![0.0],
+            ..self.config.fallback.clone()
+        };
+        let mut dc = self
+            .build_decoder(model, vec![])?
+            .with_progress(None)
+            .with_fallback(fallback);
+
+        let result = dc.run(&mel);
+        self.model = Some(dc.take_model());
+        result?;
+
+        Ok(started.elapsed())
+    }
+
+    fn load_mel(&self, audio_path: PathBuf) -> Result<Tensor> {
+        let config = self
+            .model
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Model not available"))?
+            .config();
+
+        let mel = compute_mel(config, &self.mel_filters, &audio_path)?;
+        self.mel_tensor(config, mel)
+    }
+
+    fn pcm_mel(&self, pcm_data: &[f32]) -> Result<Tensor> {
+        let config = self
+            .model
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Model not available"))?
+            .config();
 
-        let mel = m::audio::pcm_to_mel(model.config(), &pcm_data, &self.mel_filters);
+        let mel = m::audio::pcm_to_mel(config, pcm_data, &self.mel_filters);
+        self.mel_tensor(config, mel)
+    }
+
+    fn mel_tensor(&self, config: &m::Config, mel: Vec<f32>) -> Result<Tensor> {
         let mel_len = mel.len();
-        let mel = Tensor::from_vec(
+        Ok(Tensor::from_vec(
             mel,
-            (
-                1,
-                model.config().num_mel_bins,
-                mel_len / model.config().num_mel_bins,
-            ),
+            (1, config.num_mel_bins, mel_len / config.num_mel_bins),
             &self.device,
-        )?;
+        )?)
+    }
 
-        // Create decoder and run transcription
-        let mut dc = decoder::Decoder::new(
+    fn prompt_tokens(&self) -> Result<Vec<u32>> {
+        let Some(prompt) = &self.config.initial_prompt else {
+            return Ok(vec![]);
+        };
+        let config = self
+            .model
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Model not available"))?
+            .config();
+
+        decoder::encode_prompt(&self.tokenizer, prompt, config.max_target_positions)
+    }
+
+    fn build_decoder(&self, model: Model, prompt_tokens: Vec<u32>) -> Result<decoder::Decoder> {
+        Ok(decoder::Decoder::new(
             model,
             self.tokenizer.clone(),
             self.config.seed,
@@ -126,13 +405,263 @@ impl Whisper {
             self.language_token,
             self.config.timestamps,
             self.config.verbose,
-        )?;
+            self.config.strategy,
+        )?
+        .with_fallback(self.config.fallback.clone())
+        .with_progress(self.config.progress.clone())
+        .with_aligner(self.aligner.clone())
+        .with_cancellation(self.cancel.clone())
+        .with_prompt_tokens(prompt_tokens))
+    }
+
+    pub fn transcribe_segments(&mut self, audio_path: PathBuf) -> Result<Vec<TranscriptSegment>> {
+        self.config.fallback.validate()?;
+
+        // Process audio, packing the speech together first when VAD is on
+        let (mel, speech) = if self.config.vad_enabled {
+            let pcm_data = pcm_decode::pcm_decode_resampled(audio_path, m::SAMPLE_RATE as u32)?;
+            let speech = vad::SpeechAudio::extract(&pcm_data, m::SAMPLE_RATE);
+            if speech.is_empty() {
+                log::info!("No speech detected, skipping decoding");
+                return Ok(vec![]);
+            }
+            log::info!(
+                "Trimmed {:.1}s of silence",
+                speech.trimmed_secs(pcm_data.len())
+            );
+            (self.pcm_mel(&speech.samples)?, Some(speech))
+        } else {
+            (self.load_mel(audio_path)?, None)
+        };
+        let prompt_tokens = self.prompt_tokens()?;
+        let model = self
+            .model
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("Model not available"))?;
+
+        // Create decoder and run transcription
+        let mut dc = self.build_decoder(model, prompt_tokens)?;
+
+        let result = dc.run(&mel);
+
+        // Get model back from decoder, even if decoding failed
+        self.model = Some(dc.take_model());
+        let segments = result?;
+
+        log::info!(
+            "Transcribed {} segment(s) using {}",
+            segments.len(),
+            self.config.strategy
+        );
+
+        let mut segments: Vec<TranscriptSegment> =
+            segments.into_iter().map(TranscriptSegment::from).collect();
+        if let Some(speech) = &speech {
+            for segment in &mut segments {
+                speech.restore_times(segment);
+            }
+        }
+
+        Ok(segments)
+    }
+
+    /// Transcribes `paths` in order with a single decoder, so the model, mel
+    /// filters and suppression mask are set up once for the whole batch. Each
+    /// file gets its own result; a file that can't be read or decoded doesn't
+    /// stop the rest.
+    ///
+    /// Only one file's PCM samples and spectrogram (about 32KB per second of
+    /// audio, ~115MB for an hour) are held at a time, plus the returned
+    /// segments. With the `rayon` feature the spectrograms of up to one file per
+    /// rayon thread are computed in parallel ahead of decoding, which
+    /// multiplies that peak by the pool size. Decoding itself stays sequential
+    /// because it runs on the one loaded model.
+    pub fn transcribe_batch(&mut self, paths: Vec<PathBuf>) -> Vec<Result<Vec<TranscriptSegment>>> {
+        let prompt_tokens = match self
+            .config
+            .fallback
+            .validate()
+            .and_then(|_| self.prompt_tokens())
+        {
+            Ok(tokens) => tokens,
+            Err(e) => return paths.iter().map(|_| Err(anyhow::anyhow!("{e}"))).collect(),
+        };
+
+        let Some(model) = self.model.take() else {
+            return paths
+                .iter()
+                .map(|_| Err(anyhow::anyhow!("Model not available")))
+                .collect();
+        };
+        let config = model.config().clone();
+
+        let mut dc = match self.build_decoder(model, prompt_tokens) {
+            Ok(dc) => dc,
+            Err(e) => return paths.iter().map(|_| Err(anyhow::anyhow!("{e}"))).collect(),
+        };
+
+        let mut results = Vec::with_capacity(paths.len());
+        for batch in paths.chunks(Self::mel_batch_size()) {
+            for mel in self.compute_mels(&config, batch) {
+                let segments = mel
+                    .and_then(|mel| self.mel_tensor(&config, mel))
+                    .and_then(|mel| dc.run(&mel));
+                results.push(
+                    segments.map(|segments| {
+                        segments.into_iter().map(TranscriptSegment::from).collect()
+                    }),
+                );
+            }
+        }
+
+        self.model = Some(dc.take_model());
+
+        log::info!(
+            "Transcribed {} of {} file(s) using {}",
+            results.iter().filter(|result| result.is_ok()).count(),
+            results.len(),
+            self.config.strategy
+        );
+
+        results
+    }
+
+    /// Transcribes `audio_path` in windows of `window_secs` (at most 30s),
+    /// each overlapping the previous one by `STREAM_OVERLAP_SECS`, and hands
+    /// every segment to `callback` as soon as its window is decoded. Words the
+    /// overlap repeats are dropped from the start of the later segment.
+    ///
+    /// The decoded PCM is kept for the whole file (about 64KB per second of
+    /// audio), but only one window's spectrogram exists at a time.
+    pub fn transcribe_streaming<F>(
+        &mut self,
+        audio_path: PathBuf,
+        window_secs: f64,
+        mut callback: F,
+    ) -> Result<()>
+    where
+        F: FnMut(TranscriptSegment),
+    {
+        // NaN would survive the clamp below and give an empty window
+        if !window_secs.is_finite() {
+            return Err(anyhow::anyhow!(
+                "window_secs must be a finite number, got {window_secs}"
+            ));
+        }
+        self.config.fallback.validate()?;
+        let prompt_tokens = self.prompt_tokens()?;
+        let pcm_data = pcm_decode::pcm_decode_resampled(audio_path, m::SAMPLE_RATE as u32)?;
+        if pcm_data.is_empty() {
+            return Ok(());
+        }
 
-        let segments = dc.run(&mel)?;
+        let model = self
+            .model
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("Model not available"))?;
+        let config = model.config().clone();
 
-        // Get model back from decoder
+        // Progress is reported per streaming window rather than per decoder run
+        let mut dc = self
+            .build_decoder(model, prompt_tokens)?
+            .with_progress(None);
+        let result = self.stream_windows(&mut dc, &config, &pcm_data, window_secs, &mut callback);
+
+        // Get model back from decoder, even if decoding failed
         self.model = Some(dc.take_model());
+        result
+    }
+
+    fn stream_windows(
+        &self,
+        dc: &mut decoder::Decoder,
+        config: &m::Config,
+        pcm_data: &[f32],
+        window_secs: f64,
+        callback: &mut impl FnMut(TranscriptSegment),
+    ) -> Result<()> {
+        let sample_rate = m::SAMPLE_RATE as f64;
+        let window_secs =
+            window_secs.clamp(2.0 * streaming::STREAM_OVERLAP_SECS, m::CHUNK_LENGTH as f64);
+        let window_len = (window_secs * sample_rate) as usize;
+        let step = window_len - (streaming::STREAM_OVERLAP_SECS * sample_rate) as usize;
+        let windows_total = 1 + pcm_data.len().saturating_sub(window_len).div_ceil(step);
+
+        let mut previous_text = String::new();
+        let mut start = 0;
+        for windows_done in 1..=windows_total {
+            let end = usize::min(start + window_len, pcm_data.len());
+            let offset = start as f64 / sample_rate;
 
-        Ok(segments.into_iter().map(|s| s.dr.text).collect())
+            let mel = m::audio::pcm_to_mel(config, &pcm_data[start..end], &self.mel_filters);
+            let mel = self.mel_tensor(config, mel)?;
+            for segment in dc.run(&mel)? {
+                let mut segment = TranscriptSegment::from(segment);
+                segment.start += offset;
+                segment.end += offset;
+                for word in &mut segment.words {
+                    word.start += offset;
+                    word.end += offset;
+                }
+
+                let repeated = streaming::overlapping_words(&previous_text, &segment.text);
+                streaming::drop_leading_words(&mut segment, repeated);
+                if segment.text.trim().is_empty() {
+                    continue;
+                }
+
+                previous_text.clone_from(&segment.text);
+                callback(segment);
+            }
+
+            if let Some(progress) = &self.config.progress {
+                progress(decoder::TranscriptionProgress {
+                    segments_done: windows_done,
+                    segments_total: windows_total,
+                });
+            }
+            start += step;
+        }
+
+        log::info!(
+            "Streamed {} window(s) using {}",
+            windows_total,
+            self.config.strategy
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "rayon")]
+    fn mel_batch_size() -> usize {
+        rayon::current_num_threads().max(1)
     }
+
+    #[cfg(not(feature = "rayon"))]
+    fn mel_batch_size() -> usize {
+        1
+    }
+
+    #[cfg(feature = "rayon")]
+    fn compute_mels(&self, config: &m::Config, paths: &[PathBuf]) -> Vec<Result<Vec<f32>>> {
+        use rayon::prelude::*;
+
+        paths
+            .par_iter()
+            .map(|path| compute_mel(config, &self.mel_filters, path))
+            .collect()
+    }
+
+    #[cfg(not(feature = "rayon"))]
+    fn compute_mels(&self, config: &m::Config, paths: &[PathBuf]) -> Vec<Result<Vec<f32>>> {
+        paths
+            .iter()
+            .map(|path| compute_mel(config, &self.mel_filters, path))
+            .collect()
+    }
+}
+
+/// Decodes `audio_path` to 16kHz mono and returns its log-mel spectrogram.
+fn compute_mel(config: &m::Config, mel_filters: &[f32], audio_path: &Path) -> Result<Vec<f32>> {
+    let pcm_data = pcm_decode::pcm_decode_resampled(audio_path, m::SAMPLE_RATE as u32)?;
+    Ok(m::audio::pcm_to_mel(config, &pcm_data, mel_filters))
 }
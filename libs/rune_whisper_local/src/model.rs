@@ -1,12 +1,102 @@
+use anyhow::Result;
 use candle::Tensor;
 use candle_transformers::models::whisper::{self as m, Config};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 
 pub enum Model {
     Normal(m::model::Whisper),
     Quantized(m::quantized_model::Whisper),
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Weights {
+    Safetensors(PathBuf),
+    /// Quantized weights, loaded into `Model::Quantized`.
+    Gguf(PathBuf),
+}
+
+/// The files that make up a model directory.
+#[derive(Debug, Clone)]
+pub struct ModelFiles {
+    pub config: PathBuf,
+    pub tokenizer: PathBuf,
+    pub weights: Weights,
+}
+
+impl ModelFiles {
+    /// Finds the model files in `dir`, preferring `model.safetensors` over any
+    /// `.gguf` file.
+    pub fn locate(dir: &Path) -> Result<Self> {
+        if !dir.is_dir() {
+            anyhow::bail!("model directory {} does not exist", dir.display())
+        }
+
+        let config = dir.join("config.json");
+        let tokenizer = dir.join("tokenizer.json");
+        for required in [&config, &tokenizer] {
+            if !required.is_file() {
+                anyhow::bail!("missing {} in {}", file_name(required), dir.display())
+            }
+        }
+
+        let safetensors = dir.join("model.safetensors");
+        let weights = if safetensors.is_file() {
+            Weights::Safetensors(safetensors)
+        } else {
+            let gguf = std::fs::read_dir(dir)?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .find(|path| path.extension().is_some_and(|ext| ext == "gguf"));
+            match gguf {
+                Some(path) => Weights::Gguf(path),
+                None => anyhow::bail!(
+                    "missing model.safetensors or a .gguf file in {}",
+                    dir.display()
+                ),
+            }
+        };
+
+        Ok(Self {
+            config,
+            tokenizer,
+            weights,
+        })
+    }
+
+    /// Parses `config.json` and checks that it describes a model we can run.
+    pub fn load_config(&self) -> Result<Config> {
+        let config: Config = serde_json::from_str(&std::fs::read_to_string(&self.config)?)
+            .map_err(|e| anyhow::anyhow!("invalid {}: {e}", self.config.display()))?;
+
+        if mel_filter_bytes(config.num_mel_bins).is_none() {
+            anyhow::bail!(
+                "unsupported num_mel_bins {} in {}, expected 80 or 128",
+                config.num_mel_bins,
+                self.config.display()
+            )
+        }
+
+        Ok(config)
+    }
+}
+
+/// Mel filterbank bundled for the given number of mel bins.
+pub fn mel_filter_bytes(num_mel_bins: usize) -> Option<&'static [u8]> {
+    match num_mel_bins {
+        80 => Some(include_bytes!("melfilters.bytes").as_slice()),
+        128 => Some(include_bytes!("melfilters128.bytes").as_slice()),
+        _ => None,
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum WhichModel {
     Tiny,
     TinyEn,
@@ -98,4 +188,10 @@ impl WhichModel {
             Self::DistilLargeV3 => ("distil-whisper/distil-large-v3", "main"),
         }
     }
+
+    /// Directory name the model's files are stored under, e.g. `whisper-base`.
+    pub fn dir_name(&self) -> &'static str {
+        let (model_id, _) = self.model_and_revision();
+        model_id.rsplit('/').next().unwrap_or(model_id)
+    }
 }
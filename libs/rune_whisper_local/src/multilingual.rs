@@ -1,47 +1,58 @@
 use anyhow::Result;
-use candle::Tensor;
+use candle::{IndexOp, Tensor};
+use candle_transformers::models::whisper::{self as m};
 use tokenizers::Tokenizer;
 
 use crate::decoder::token_id;
 use crate::model::Model;
 
+/// ISO codes of the languages whisper was trained on, in token order.
+pub const LANGUAGES: [&str; 99] = [
+    "en", "zh", "de", "es", "ru", "ko", "fr", "ja", "pt", "tr", "pl", "ca", "nl", "ar", "sv", "it",
+    "id", "hi", "fi", "vi", "he", "uk", "el", "ms", "cs", "ro", "da", "hu", "ta", "no", "th", "ur",
+    "hr", "bg", "lt", "la", "mi", "ml", "cy", "sk", "te", "fa", "lv", "bn", "sr", "az", "sl", "kn",
+    "et", "mk", "br", "eu", "is", "hy", "ne", "mn", "bs", "kk", "sq", "sw", "gl", "mr", "pa", "si",
+    "km", "sn", "yo", "so", "af", "oc", "ka", "be", "tg", "sd", "gu", "am", "yi", "lo", "uz", "fo",
+    "ht", "ps", "tk", "nn", "mt", "sa", "lb", "my", "bo", "tl", "mg", "as", "tt", "haw", "ln",
+    "ha", "ba", "jw", "su",
+];
+
 // Language detection using the audio encoder's output and the tokenizer
 pub fn detect_language(model: &mut Model, tokenizer: &Tokenizer, mel: &Tensor) -> Result<u32> {
-    const SOT_TOKEN: &str = "<|startoftranscript|>";
-    const LANGUAGE_TOKEN: &str = "<|language|>";
+    // The first 30s window is enough to tell the language
+    let (_, _, seq_len) = mel.dims3()?;
+    let mel = mel.narrow(2, 0, usize::min(seq_len, m::N_FRAMES))?;
 
-    // Get the tokens for special markers
-    let sot_token = token_id(tokenizer, SOT_TOKEN)?;
-    let language_token = token_id(tokenizer, LANGUAGE_TOKEN)?;
+    let sot_token = token_id(tokenizer, m::SOT_TOKEN)?;
+    let language_tokens = LANGUAGES
+        .iter()
+        .map(|code| token_id(tokenizer, &format!("<|{code}|>")))
+        .collect::<candle::Result<Vec<_>>>()?;
 
     // Run the encoder
-    let features = model.encoder_forward(mel, true)?;
+    let features = model.encoder_forward(&mel, true)?;
 
     // Prepare decoder input with just the SOT token
     let tokens = Tensor::new(&[sot_token], mel.device())?.unsqueeze(0)?;
-
-    // Get decoder output
     let ys = model.decoder_forward(&tokens, &features, true)?;
-
-    // Get logits and find the most likely language token
-    let logits = model.decoder_final_linear(&ys)?;
-    let logits = logits.squeeze(0)?;
-    let logits = logits.squeeze(0)?;
+    let logits = model.decoder_final_linear(&ys.i(..1)?)?.i(0)?.i(0)?;
+    let logits_v: Vec<f32> = logits.to_vec1()?;
 
     // Only consider language tokens
-    let mut best_lang_token = None;
-    let mut best_prob = f32::NEG_INFINITY;
+    language_tokens
+        .into_iter()
+        .filter_map(|token| Some((token, *logits_v.get(token as usize)?)))
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(token, _)| token)
+        .ok_or_else(|| anyhow::anyhow!("no language token found"))
+}
 
-    let logits_v: Vec<f32> = logits.to_vec1()?;
-    for (token_id, &logit) in logits_v.iter().enumerate() {
-        let token_id = token_id as u32;
-        if token_id > language_token && token_id < language_token + 100 {
-            if logit > best_prob {
-                best_prob = logit;
-                best_lang_token = Some(token_id);
-            }
-        }
-    }
-
-    best_lang_token.ok_or_else(|| anyhow::anyhow!("no language token found"))
+/// Maps a language token such as `<|es|>` back to its ISO code.
+pub fn language_code(tokenizer: &Tokenizer, token: u32) -> Option<String> {
+    let token = tokenizer.id_to_token(token)?;
+    token
+        .strip_prefix("<|")
+        .and_then(|t| t.strip_suffix("|>"))
+        .filter(|code| LANGUAGES.contains(code))
+        .map(str::to_string)
 }
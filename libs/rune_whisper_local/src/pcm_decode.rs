@@ -1,3 +1,4 @@
+use rubato::{FftFixedIn, Resampler};
 use symphonia::core::audio::{AudioBufferRef, Signal};
 use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
 use symphonia::core::conv::FromSample;
@@ -10,6 +11,65 @@ where
     samples.extend(data.chan(0).iter().map(|v| f32::from_sample(*v)))
 }
 
+const RESAMPLE_CHUNK_SIZE: usize = 1024;
+
+/// Decodes `path` and resamples the first channel to `target_rate` when the
+/// file uses a different rate.
+pub(crate) fn pcm_decode_resampled<P: AsRef<std::path::Path>>(
+    path: P,
+    target_rate: u32,
+) -> anyhow::Result<Vec<f32>> {
+    let (pcm_data, sample_rate) = pcm_decode(path)?;
+    if sample_rate == target_rate {
+        return Ok(pcm_data);
+    }
+    if sample_rate == 0 {
+        anyhow::bail!("input file does not declare a sample rate")
+    }
+
+    resample(&pcm_data, sample_rate, target_rate)
+}
+
+pub(crate) fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> anyhow::Result<Vec<f32>> {
+    let mut resampler = FftFixedIn::<f32>::new(
+        from_rate as usize,
+        to_rate as usize,
+        RESAMPLE_CHUNK_SIZE,
+        1,
+        1,
+    )
+    .map_err(|e| anyhow::anyhow!("cannot resample from {from_rate}Hz to {to_rate}Hz: {e}"))?;
+
+    let expected_len = (samples.len() as u64 * to_rate as u64 / from_rate as u64) as usize;
+    let delay = resampler.output_delay();
+    let mut output = Vec::with_capacity(expected_len + delay + RESAMPLE_CHUNK_SIZE);
+
+    let mut chunks = samples.chunks_exact(RESAMPLE_CHUNK_SIZE);
+    for chunk in chunks.by_ref() {
+        let out = resampler.process(&[chunk], None)?;
+        output.extend_from_slice(&out[0]);
+    }
+
+    let remainder = chunks.remainder();
+    if !remainder.is_empty() {
+        let out = resampler.process_partial(Some(&[remainder]), None)?;
+        output.extend_from_slice(&out[0]);
+    }
+
+    // Flush what is still buffered so the tail of the recording isn't lost
+    while output.len() < expected_len + delay {
+        let out = resampler.process_partial::<&[f32]>(None, None)?;
+        if out[0].is_empty() {
+            break;
+        }
+        output.extend_from_slice(&out[0]);
+    }
+
+    // Drop the filter delay at the start and any padding at the end
+    let end = (delay + expected_len).min(output.len());
+    Ok(output[delay.min(end)..end].to_vec())
+}
+
 pub(crate) fn pcm_decode<P: AsRef<std::path::Path>>(path: P) -> anyhow::Result<(Vec<f32>, u32)> {
     // Open the media source.
     let src = std::fs::File::open(path)?;
@@ -0,0 +1,53 @@
+use crate::TranscriptSegment;
+
+/// Audio shared by consecutive streaming windows, so a word cut off at the
+/// end of one window is heard whole at the start of the next.
+pub const STREAM_OVERLAP_SECS: f64 = 1.0;
+
+/// Upper bound on how many words the overlap can repeat.
+const MAX_OVERLAP_WORDS: usize = 10;
+
+/// Number of leading words of `next` that repeat the end of `previous`,
+/// compared case- and punctuation-insensitively.
+pub(crate) fn overlapping_words(previous: &str, next: &str) -> usize {
+    let previous: Vec<String> = previous.split_whitespace().map(normalize).collect();
+    let next: Vec<String> = next.split_whitespace().map(normalize).collect();
+    let max = MAX_OVERLAP_WORDS.min(previous.len()).min(next.len());
+
+    (1..=max)
+        .rev()
+        .find(|&count| previous[previous.len() - count..] == next[..count])
+        .unwrap_or(0)
+}
+
+/// Removes the first `count` words from the segment's text and word timings.
+pub(crate) fn drop_leading_words(segment: &mut TranscriptSegment, count: usize) {
+    if count == 0 {
+        return;
+    }
+
+    let rest = segment
+        .text
+        .split_whitespace()
+        .skip(count)
+        .collect::<Vec<_>>()
+        .join(" ");
+    segment.text = if rest.is_empty() {
+        rest
+    } else {
+        format!(" {rest}")
+    };
+
+    let words = count.min(segment.words.len());
+    segment.words.drain(..words);
+    if let Some(first) = segment.words.first() {
+        segment.start = first.start;
+    }
+}
+
+fn normalize(word: &str) -> String {
+    word.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
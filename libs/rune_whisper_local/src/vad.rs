@@ -0,0 +1,134 @@
+use crate::TranscriptSegment;
+
+/// Length of the frames whose energy is compared against the threshold.
+const FRAME_MS: usize = 30;
+/// Pauses shorter than this stay inside the surrounding speech.
+const MIN_SILENCE_MS: usize = 500;
+/// Bursts shorter than this (clicks, bumps) are not treated as speech.
+const MIN_SPEECH_MS: usize = 200;
+/// Kept on both sides of a span so soft word onsets and endings survive.
+const PADDING_MS: usize = 200;
+/// Frames this many times louder (in RMS) than the noise floor count as speech.
+const NOISE_FLOOR_RATIO: f32 = 3.0;
+/// RMS below which a frame is never speech, whatever the noise floor.
+const MIN_SPEECH_RMS: f32 = 0.005;
+
+/// A stretch of speech, in samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpeechSpan {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Finds the speech in `pcm` with an energy detector. The threshold adapts
+/// to the recording's noise floor, taken as the 10th percentile of frame
+/// energies.
+pub fn speech_spans(pcm: &[f32], sample_rate: usize) -> Vec<SpeechSpan> {
+    let frame_len = (sample_rate * FRAME_MS / 1000).max(1);
+    let energies: Vec<f32> = pcm.chunks(frame_len).map(rms).collect();
+    if energies.is_empty() {
+        return vec![];
+    }
+
+    let mut sorted = energies.clone();
+    sorted.sort_unstable_by(f32::total_cmp);
+    let noise_floor = sorted[sorted.len() / 10];
+    let threshold = (noise_floor * NOISE_FLOOR_RATIO).max(MIN_SPEECH_RMS);
+
+    let to_frames = |ms: usize| (ms / FRAME_MS).max(1);
+    let min_silence = to_frames(MIN_SILENCE_MS);
+    let min_speech = to_frames(MIN_SPEECH_MS);
+    let padding = PADDING_MS * sample_rate / 1000;
+
+    // Runs of loud frames, with short pauses bridged
+    let mut runs: Vec<(usize, usize)> = vec![];
+    for (frame, _) in energies
+        .iter()
+        .enumerate()
+        .filter(|(_, &energy)| energy >= threshold)
+    {
+        match runs.last_mut() {
+            Some((_, end)) if frame - *end <= min_silence => *end = frame + 1,
+            _ => runs.push((frame, frame + 1)),
+        }
+    }
+
+    let mut spans: Vec<SpeechSpan> = vec![];
+    for (start, end) in runs.into_iter().filter(|(s, e)| e - s >= min_speech) {
+        let span = SpeechSpan {
+            start: (start * frame_len).saturating_sub(padding),
+            end: (end * frame_len + padding).min(pcm.len()),
+        };
+        match spans.last_mut() {
+            Some(last) if span.start <= last.end => last.end = span.end,
+            _ => spans.push(span),
+        }
+    }
+
+    spans
+}
+
+/// The speech of a recording packed back to back, with what is needed to map
+/// times in it back to the original recording.
+pub struct SpeechAudio {
+    pub samples: Vec<f32>,
+    /// `(packed_start, span)` for each span, in order.
+    spans: Vec<(usize, SpeechSpan)>,
+    sample_rate: usize,
+}
+
+impl SpeechAudio {
+    pub fn extract(pcm: &[f32], sample_rate: usize) -> Self {
+        let mut samples = vec![];
+        let mut spans = vec![];
+        for span in speech_spans(pcm, sample_rate) {
+            spans.push((samples.len(), span));
+            samples.extend_from_slice(&pcm[span.start..span.end]);
+        }
+
+        Self {
+            samples,
+            spans,
+            sample_rate,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Seconds of audio that were trimmed as silence.
+    pub fn trimmed_secs(&self, original_len: usize) -> f64 {
+        (original_len - self.samples.len()) as f64 / self.sample_rate as f64
+    }
+
+    /// Maps a time in the packed audio to the original recording.
+    pub fn original_time(&self, packed_secs: f64) -> f64 {
+        let packed = (packed_secs * self.sample_rate as f64).max(0.0) as usize;
+        let (packed_start, span) = self
+            .spans
+            .iter()
+            .rev()
+            .find(|(packed_start, _)| *packed_start <= packed)
+            .or(self.spans.first())
+            .copied()
+            .unwrap_or((0, SpeechSpan { start: 0, end: 0 }));
+
+        let original = (span.start + packed - packed_start).min(span.end);
+        original as f64 / self.sample_rate as f64
+    }
+
+    /// Rewrites the times of a segment decoded from the packed audio.
+    pub fn restore_times(&self, segment: &mut TranscriptSegment) {
+        segment.start = self.original_time(segment.start);
+        segment.end = self.original_time(segment.end);
+        for word in &mut segment.words {
+            word.start = self.original_time(word.start);
+            word.end = self.original_time(word.end);
+        }
+    }
+}
+
+fn rms(frame: &[f32]) -> f32 {
+    (frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32).sqrt()
+}
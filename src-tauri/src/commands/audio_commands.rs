@@ -1,10 +1,28 @@
-use std::sync::Arc;
-use tauri::{command, AppHandle, State};
+use rune_whisper_local::{
+    model::{ModelFiles, WhichModel},
+    TranscriptSegment,
+};
+use std::{path::PathBuf, sync::Arc};
+use tauri::{command, ipc::Response, AppHandle, State};
 use tauri_plugin_store::StoreExt;
 
 use crate::{
-    core::{app::AppState, state_machine::AppCommand},
-    services::audio_device_service::{AudioDevice, AudioDeviceService},
+    core::{
+        app::AppState,
+        config::CustomModel,
+        state_machine::{AppCommand, AppStateType},
+    },
+    services::{
+        audio_device_service::{AudioDevice, AudioDeviceService},
+        file_transcription_service::{
+            ExternalTranscription, FileTranscriptionResult, FileTranscriptionService,
+        },
+        model_comparison_service::ComparisonTranscription,
+        text_transcript_history_service::{
+            HistoryPage, TextTranscriptHistoryService, DEFAULT_SEARCH_PAGE_SIZE,
+        },
+        text_transcription_service::TextTranscriptionService,
+    },
 };
 
 #[command]
@@ -51,6 +69,50 @@ pub async fn set_default_device(
     Ok(())
 }
 
+#[command]
+pub async fn search_history(
+    app_handle: AppHandle,
+    query: String,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<HistoryPage, String> {
+    TextTranscriptHistoryService::search_history(
+        &app_handle,
+        &query,
+        offset.unwrap_or(0),
+        limit.unwrap_or(DEFAULT_SEARCH_PAGE_SIZE),
+    )
+    .map_err(|e| format!("Failed to search history: {}", e))
+}
+
+#[command]
+pub async fn delete_history_entry(app_handle: AppHandle, id: u32) -> Result<(), String> {
+    TextTranscriptHistoryService::delete_entry(&app_handle, id)
+        .map_err(|e| format!("Failed to delete history entry: {}", e))
+}
+
+#[command]
+pub async fn redact_history_entry(app_handle: AppHandle, id: u32) -> Result<(), String> {
+    TextTranscriptHistoryService::redact_entry(&app_handle, id)
+        .map_err(|e| format!("Failed to redact history entry: {}", e))
+}
+
+#[command]
+pub async fn clear_history(app_handle: AppHandle) -> Result<(), String> {
+    TextTranscriptHistoryService::clear_all(&app_handle)
+        .map_err(|e| format!("Failed to clear history: {}", e))
+}
+
+/// The kept audio of history entry `id`, as the raw bytes of its file.
+#[command]
+pub async fn get_recording_audio(app_handle: AppHandle, id: u32) -> Result<Response, String> {
+    let path =
+        TextTranscriptHistoryService::recording_path(&app_handle, id).map_err(|e| e.to_string())?;
+    let bytes = std::fs::read(&path).map_err(|e| format!("Failed to read recording: {}", e))?;
+
+    Ok(Response::new(bytes))
+}
+
 #[command]
 pub async fn get_transcription_history(
     app_handle: AppHandle,
@@ -107,6 +169,29 @@ pub fn stop_recording(state: State<'_, Arc<AppState>>) -> Result<(), String> {
     Ok(())
 }
 
+#[command]
+pub fn pause_recording(state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    if let Some(machine) = &*state.state_machine.lock() {
+        machine.send_command(AppCommand::PauseRecording);
+    }
+
+    Ok(())
+}
+
+#[command]
+pub fn resume_recording(state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    if let Some(machine) = &*state.state_machine.lock() {
+        machine.send_command(AppCommand::ResumeRecording);
+    }
+
+    Ok(())
+}
+
+#[command]
+pub fn get_processing_status(state: State<'_, Arc<AppState>>) -> String {
+    state.processing_status()
+}
+
 #[command]
 pub async fn cancel_recording(
     app_handle: AppHandle,
@@ -132,3 +217,271 @@ pub async fn cancel_recording(
     }
     Ok(())
 }
+
+#[command]
+pub async fn transcribe_with_model(
+    app_handle: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    path: PathBuf,
+    model: WhichModel,
+) -> Result<ComparisonTranscription, String> {
+    log::info!("Comparison transcription of {:?} with {:?}", path, model);
+
+    let comparison = Arc::clone(&state.model_comparison);
+    tauri::async_runtime::spawn_blocking(move || comparison.transcribe(&app_handle, path, model))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// Detects the spoken language of a file with the pipeline's model and
+/// returns its ISO code.
+#[command]
+pub async fn detect_language(
+    state: State<'_, Arc<AppState>>,
+    path: PathBuf,
+) -> Result<String, String> {
+    let pipeline = state
+        .audio_pipeline
+        .lock()
+        .clone()
+        .ok_or_else(|| "Audio pipeline not initialized".to_string())?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        pipeline.transcription_service.detect_language(path)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
+
+/// Emits `audio-levels` from the selected microphone without recording, for
+/// checking it before the first dictation.
+#[command]
+pub fn start_level_monitoring(
+    app_handle: AppHandle,
+    state: State<'_, Arc<AppState>>,
+) -> Result<(), String> {
+    let recording = state.state_machine.lock().as_ref().is_some_and(|machine| {
+        matches!(
+            machine.get_state(),
+            AppStateType::Recording | AppStateType::Paused
+        )
+    });
+    if recording {
+        return Err("Cannot monitor levels while recording".to_string());
+    }
+
+    state
+        .level_monitor
+        .start(app_handle)
+        .map_err(|e| format!("Failed to start level monitoring: {}", e))
+}
+
+#[command]
+pub fn stop_level_monitoring(state: State<'_, Arc<AppState>>) {
+    state.level_monitor.stop();
+}
+
+/// Runs the pipeline's model on `audio_path` and returns the decoded segments
+/// with their timings and scores, skipping text processing and injection. For
+/// telling model problems apart from microphone ones.
+#[command]
+pub async fn test_transcription(
+    state: State<'_, Arc<AppState>>,
+    audio_path: PathBuf,
+) -> Result<Vec<TranscriptSegment>, String> {
+    if !audio_path.is_file() {
+        return Err(format!("File not found: {}", audio_path.display()));
+    }
+
+    let pipeline = state
+        .audio_pipeline
+        .lock()
+        .clone()
+        .ok_or_else(|| "Audio pipeline not initialized".to_string())?;
+
+    log::info!("Test transcription of {:?}", audio_path);
+    tauri::async_runtime::spawn_blocking(move || {
+        pipeline
+            .transcription_service
+            .transcribe_segments(audio_path, None)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
+
+#[command]
+pub async fn transcribe_files(
+    app_handle: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    paths: Vec<PathBuf>,
+    transform: bool,
+) -> Result<Vec<FileTranscriptionResult>, String> {
+    log::info!("Transcribing {} dropped file(s)", paths.len());
+
+    let state = Arc::clone(&state);
+    let (tx, rx) = tokio::sync::oneshot::channel();
+
+    // Text processing blocks on the shared runtime, so run off the async executor
+    std::thread::spawn(move || {
+        let results =
+            FileTranscriptionService::transcribe_files(&state, &app_handle, paths, transform);
+        let _ = tx.send(results);
+    });
+
+    rx.await.map_err(|e| e.to_string())
+}
+
+/// Transcribes an audio file the user picked as if it had been dictated:
+/// typed into the focused app with `inject`, otherwise copied to the
+/// clipboard.
+#[command]
+pub async fn transcribe_external_file(
+    app_handle: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    path: PathBuf,
+    transform: bool,
+    inject: bool,
+) -> Result<ExternalTranscription, String> {
+    log::info!("Transcribing external file {:?}", path);
+
+    let state = Arc::clone(&state);
+    let (tx, rx) = tokio::sync::oneshot::channel();
+
+    // Text processing blocks on the shared runtime, so run off the async executor
+    std::thread::spawn(move || {
+        let result = FileTranscriptionService::transcribe_external_file(
+            &state,
+            &app_handle,
+            &path,
+            transform,
+            inject,
+        );
+        let _ = tx.send(result);
+    });
+
+    rx.await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// Validates a fine-tuned whisper checkpoint and registers it under `name`,
+/// defaulting to the directory name.
+#[command]
+pub async fn import_custom_model(
+    app_handle: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    dir: PathBuf,
+    name: Option<String>,
+) -> Result<CustomModel, String> {
+    let files = ModelFiles::locate(&dir).map_err(|e| format!("Invalid model directory: {}", e))?;
+    files
+        .load_config()
+        .map_err(|e| format!("Invalid model config: {}", e))?;
+
+    let name = name
+        .filter(|name| !name.trim().is_empty())
+        .or_else(|| dir.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .ok_or_else(|| "Could not derive a name for the model".to_string())?;
+
+    let model = CustomModel {
+        name,
+        path: dir.canonicalize().unwrap_or(dir),
+    };
+    log::info!("Importing custom model {:?}", model);
+
+    state
+        .settings
+        .write()
+        .add_custom_model(&app_handle, model.clone())
+        .map_err(|e| format!("Failed to save custom model: {}", e))?;
+
+    Ok(model)
+}
+
+/// Switches the stock model to `model`, e.g. tiny on slow machines or small
+/// for accuracy. The model is reloaded right away unless a custom model is
+/// active. A model that isn't on disk is downloaded first when downloads are
+/// enabled, and loaded once it has arrived.
+#[command]
+pub fn set_whisper_model(
+    app_handle: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    model: WhichModel,
+) -> Result<(), String> {
+    let available = TextTranscriptionService::find_model_dir(&app_handle, model).is_some();
+    if !available && !state.settings.read().transcription.download_models {
+        return Err(format!("Model files for {:?} not found", model));
+    }
+
+    state
+        .settings
+        .write()
+        .update_whisper_model(&app_handle, model)
+        .map_err(|e| format!("Failed to update model: {}", e))?;
+
+    if !available {
+        state.model_downloads.start(app_handle, model);
+    } else if let Some(machine) = &*state.state_machine.lock() {
+        machine.send_command(AppCommand::PurgeResources);
+    }
+
+    Ok(())
+}
+
+/// Takes effect right away: the pipeline is rebuilt with the new number of
+/// model instances.
+#[command]
+pub fn set_model_pool_size(
+    app_handle: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    model_pool_size: usize,
+) -> Result<(), String> {
+    state
+        .settings
+        .write()
+        .update_model_pool_size(&app_handle, model_pool_size)
+        .map_err(|e| format!("Failed to update model pool size: {}", e))?;
+
+    if let Some(machine) = &*state.state_machine.lock() {
+        machine.send_command(AppCommand::PurgeResources);
+    }
+
+    Ok(())
+}
+
+#[command]
+pub fn set_download_models(
+    app_handle: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    enabled: bool,
+) -> Result<(), String> {
+    state
+        .settings
+        .write()
+        .update_download_models(&app_handle, enabled)
+        .map_err(|e| format!("Failed to update model downloads: {}", e))
+}
+
+/// Switches transcription to the named custom model, or back to the bundled
+/// model with `None`. The model is reloaded right away.
+#[command]
+pub fn set_active_custom_model(
+    app_handle: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    name: Option<String>,
+) -> Result<(), String> {
+    state
+        .settings
+        .write()
+        .update_active_custom_model(&app_handle, name)
+        .map_err(|e| e.to_string())?;
+
+    if let Some(machine) = &*state.state_machine.lock() {
+        machine.send_command(AppCommand::PurgeResources);
+    }
+
+    Ok(())
+}
@@ -1,8 +1,29 @@
-use std::{str::FromStr, sync::Arc};
+use std::{path::PathBuf, sync::Arc};
 
-use crate::core::{app::AppState, config::Settings, system::permission_manager::PermissionManager};
+use crate::{
+    controllers::audio_pipleine_controller::AudioPipelineController,
+    core::{
+        app::AppState,
+        config::{
+            AppProfile, AudioFormat, ChunkingConfig, HistoryConfig, InjectionMethod,
+            LLMProviderConfig, LevelMode, LlmCacheConfig, MergeConfig, NoiseReduction,
+            NormalizationMode, ProcessingMode, PromptTimeouts, RecordingMode, Settings,
+            ShortcutConfig, ShortcutOptions,
+        },
+        system::{permission_manager::PermissionManager, shortcut_manager::ShortcutManager},
+    },
+    services::{
+        correction_feedback_service::{CorrectionFeedback, CorrectionFeedbackService},
+        metrics_service::{MetricsService, MetricsSummary},
+        text_injector_service::{InjectionReport, TextInjectorService},
+        text_processing_service::TextProcessingService,
+        text_transcript_history_service::TextTranscriptHistoryService,
+        text_transcription_service::TranscriberStatus,
+    },
+};
+use rune_llm::UsageStats;
 use tauri::{command, AppHandle};
-use tauri_plugin_global_shortcut::{Code, Modifiers};
+use tauri_plugin_autostart::ManagerExt;
 
 #[command]
 pub async fn check_accessibility_permissions() -> Result<bool, String> {
@@ -47,11 +68,6 @@ pub async fn update_shortcuts(
     state: tauri::State<'_, Arc<AppState>>,
     app_handle: AppHandle,
 ) -> Result<(), String> {
-    let _parsed_modifier = Modifiers::from_name(&modifier)
-        .ok_or_else(|| format!("Failed to parse shortcut modifier '{}'", modifier))?;
-    let _parsed_key = Code::from_str(&key)
-        .map_err(|e| format!("Failed to parse shortcut key '{}': {}", key, e))?;
-
     let mut settings = state.settings.write();
     settings
         .update_shortcuts(&app_handle, key, modifier)
@@ -63,6 +79,105 @@ pub async fn update_shortcuts(
     Ok(())
 }
 
+#[tauri::command]
+pub fn update_cancel_shortcut(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    key: Option<String>,
+    modifier: Option<String>,
+) -> Result<(), String> {
+    let previous = {
+        let mut settings = state.settings.write();
+        let previous = settings.shortcuts.clone();
+        settings
+            .update_cancel_shortcut(&app_handle, key, modifier)
+            .map_err(|e| format!("Failed to update cancel shortcut: {}", e))?;
+        previous
+    };
+
+    ShortcutManager::replace_cancel_shortcut(&app_handle, &previous);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn export_settings(
+    state: tauri::State<'_, Arc<AppState>>,
+    path: PathBuf,
+    include_secrets: Option<bool>,
+) -> Result<(), String> {
+    state
+        .settings
+        .read()
+        .export(&path, include_secrets.unwrap_or(false))
+        .map_err(|e| format!("Failed to export settings: {}", e))
+}
+
+#[tauri::command]
+pub fn import_settings(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    path: PathBuf,
+) -> Result<Settings, String> {
+    if state.safe_mode {
+        return Err("Settings can't be imported in safe mode".to_string());
+    }
+
+    let current = state.settings.read().clone();
+    let imported = Settings::import(&app_handle, &path, &current)
+        .map_err(|e| format!("Failed to import settings: {}", e))?;
+    *state.settings.write() = imported.clone();
+    // Also clears the LLM cache, as the provider may have changed
+    let llm_result = state.init_llm_client();
+
+    ShortcutManager::reregister_shortcuts(&app_handle, &imported.shortcuts).map_err(|e| {
+        format!(
+            "Settings imported, but the shortcut failed to register: {}",
+            e
+        )
+    })?;
+    llm_result.map_err(|e| {
+        format!(
+            "Settings imported, but the LLM provider failed to set up: {}",
+            e
+        )
+    })?;
+
+    Ok(imported)
+}
+
+#[tauri::command]
+pub fn get_shortcut_options() -> ShortcutOptions {
+    ShortcutConfig::options()
+}
+
+#[tauri::command]
+pub fn update_tap_threshold(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    tap_threshold_ms: u64,
+) -> Result<(), String> {
+    let mut settings = state.settings.write();
+    settings
+        .update_tap_threshold(&app_handle, tap_threshold_ms)
+        .map_err(|e| format!("Failed to update tap threshold: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn update_recording_mode(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    recording_mode: RecordingMode,
+) -> Result<(), String> {
+    let mut settings = state.settings.write();
+    settings
+        .update_recording_mode(&app_handle, recording_mode)
+        .map_err(|e| format!("Failed to update recording mode: {}", e))?;
+
+    Ok(())
+}
+
 #[tauri::command]
 pub fn update_user_profile(
     app_handle: tauri::AppHandle,
@@ -101,3 +216,564 @@ pub fn complete_onboarding(
 
     Ok(())
 }
+
+#[tauri::command]
+pub fn update_feedback_consent(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    enabled: bool,
+    include_app_name: Option<bool>,
+) -> Result<(), String> {
+    log::info!("Correction feedback consent changed: {}", enabled);
+
+    let mut settings = state.settings.write();
+    let include_app_name = include_app_name.unwrap_or(settings.feedback.include_app_name);
+    settings
+        .update_feedback_consent(&app_handle, enabled, include_app_name)
+        .map_err(|e| format!("Failed to update feedback consent: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_feedback_payloads(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Vec<CorrectionFeedback>, String> {
+    Ok(CorrectionFeedbackService::sent_payloads(&state))
+}
+
+#[tauri::command]
+pub fn update_llm_sampling(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    temperature: f32,
+    seed: Option<u64>,
+) -> Result<(), String> {
+    let mut settings = state.settings.write();
+    settings
+        .update_llm_sampling(&app_handle, temperature, seed)
+        .map_err(|e| format!("Failed to update LLM sampling: {}", e))?;
+
+    Ok(())
+}
+
+/// Switches the LLM backend. The client is rebuilt right away, so the next
+/// dictation uses the new provider.
+#[tauri::command]
+pub fn update_llm_provider(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    provider: LLMProviderConfig,
+) -> Result<(), String> {
+    state
+        .settings
+        .write()
+        .update_llm_provider(&app_handle, provider)
+        .map_err(|e| format!("Failed to update LLM provider: {}", e))?;
+
+    state
+        .init_llm_client()
+        .map_err(|e| format!("Failed to set up LLM provider: {}", e))
+}
+
+#[tauri::command]
+pub fn update_llm_base_url(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    base_url: Option<String>,
+) -> Result<(), String> {
+    state
+        .settings
+        .write()
+        .update_llm_base_url(&app_handle, base_url)
+        .map_err(|e| format!("Failed to update LLM base URL: {}", e))?;
+
+    state
+        .init_llm_client()
+        .map_err(|e| format!("Failed to set up LLM provider: {}", e))
+}
+
+/// Why the configured LLM provider isn't available, if it isn't.
+#[tauri::command]
+pub fn get_llm_error(state: tauri::State<'_, Arc<AppState>>) -> Option<String> {
+    state.llm_error.lock().clone()
+}
+
+#[tauri::command]
+pub fn update_llm_history_turns(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    history_turns: usize,
+) -> Result<(), String> {
+    let mut settings = state.settings.write();
+    settings
+        .update_llm_history_turns(&app_handle, history_turns)
+        .map_err(|e| format!("Failed to update LLM history: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn update_llm_timeouts(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    timeouts: PromptTimeouts,
+) -> Result<(), String> {
+    let mut settings = state.settings.write();
+    settings
+        .update_llm_timeouts(&app_handle, timeouts)
+        .map_err(|e| format!("Failed to update LLM timeouts: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn update_llm_cache(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    cache: LlmCacheConfig,
+) -> Result<(), String> {
+    let enabled = cache.enabled;
+    let mut settings = state.settings.write();
+    settings
+        .update_llm_cache(&app_handle, cache)
+        .map_err(|e| format!("Failed to update LLM cache: {}", e))?;
+
+    if !enabled {
+        state.llm_cache.clear();
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn clear_llm_cache(state: tauri::State<'_, Arc<AppState>>) {
+    state.llm_cache.clear();
+}
+
+/// Makes the next dictation skip the LLM cache, for one-off commands.
+#[tauri::command]
+pub fn bypass_llm_cache_once(state: tauri::State<'_, Arc<AppState>>) {
+    state.llm_cache.bypass_next();
+}
+
+#[tauri::command]
+pub fn update_beam_size(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    beam_size: Option<usize>,
+) -> Result<(), String> {
+    let mut settings = state.settings.write();
+    settings
+        .update_beam_size(&app_handle, beam_size)
+        .map_err(|e| format!("Failed to update beam size: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn update_reset_timeout(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    reset_timeout_secs: u64,
+) -> Result<(), String> {
+    let mut settings = state.settings.write();
+    settings
+        .update_reset_timeout(&app_handle, reset_timeout_secs)
+        .map_err(|e| format!("Failed to update reset timeout: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn update_min_confidence(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    min_confidence: Option<f32>,
+) -> Result<(), String> {
+    let mut settings = state.settings.write();
+    settings
+        .update_min_confidence(&app_handle, min_confidence)
+        .map_err(|e| format!("Failed to update minimum confidence: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn update_initial_prompt(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    initial_prompt: Option<String>,
+) -> Result<(), String> {
+    let mut settings = state.settings.write();
+    settings
+        .update_initial_prompt(&app_handle, initial_prompt)
+        .map_err(|e| format!("Failed to update vocabulary prompt: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn update_vad_enabled(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    vad_enabled: bool,
+) -> Result<(), String> {
+    let mut settings = state.settings.write();
+    settings
+        .update_vad_enabled(&app_handle, vad_enabled)
+        .map_err(|e| format!("Failed to update silence trimming: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn update_chunking(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    chunking: ChunkingConfig,
+) -> Result<(), String> {
+    let mut settings = state.settings.write();
+    settings
+        .update_chunking(&app_handle, chunking)
+        .map_err(|e| format!("Failed to update chunking: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn update_merge(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    merge: MergeConfig,
+) -> Result<(), String> {
+    let mut settings = state.settings.write();
+    settings
+        .update_merge(&app_handle, merge)
+        .map_err(|e| format!("Failed to update dictation merging: {}", e))?;
+
+    if !settings.merge.enabled {
+        state.dictation_merge.reset();
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn update_silence_detection(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    silence_timeout_ms: Option<u64>,
+    silence_threshold: f32,
+) -> Result<(), String> {
+    let mut settings = state.settings.write();
+    settings
+        .update_silence_detection(&app_handle, silence_timeout_ms, silence_threshold)
+        .map_err(|e| format!("Failed to update silence detection: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn update_min_recording_ms(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    min_recording_ms: u64,
+) -> Result<(), String> {
+    let mut settings = state.settings.write();
+    settings
+        .update_min_recording_ms(&app_handle, min_recording_ms)
+        .map_err(|e| format!("Failed to update minimum recording length: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn update_input_gain(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    input_gain: f32,
+    normalization: NormalizationMode,
+) -> Result<(), String> {
+    let mut settings = state.settings.write();
+    settings
+        .update_input_gain(&app_handle, input_gain, normalization)
+        .map_err(|e| format!("Failed to update input gain: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn update_noise_reduction(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    noise_reduction: NoiseReduction,
+) -> Result<(), String> {
+    let mut settings = state.settings.write();
+    settings
+        .update_noise_reduction(&app_handle, noise_reduction)
+        .map_err(|e| format!("Failed to update noise reduction: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn update_output_format(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    output_format: AudioFormat,
+) -> Result<(), String> {
+    let mut settings = state.settings.write();
+    settings
+        .update_output_format(&app_handle, output_format)
+        .map_err(|e| format!("Failed to update recording format: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn update_keep_recordings(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    keep_recordings: bool,
+) -> Result<(), String> {
+    let mut settings = state.settings.write();
+    settings
+        .update_keep_recordings(&app_handle, keep_recordings)
+        .map_err(|e| format!("Failed to update recording retention: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn update_level_mode(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    level_mode: LevelMode,
+) -> Result<(), String> {
+    let mut settings = state.settings.write();
+    settings
+        .update_level_mode(&app_handle, level_mode)
+        .map_err(|e| format!("Failed to update level mode: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn update_injection_method(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    injection_method: InjectionMethod,
+) -> Result<(), String> {
+    let mut settings = state.settings.write();
+    settings
+        .update_injection_method(&app_handle, injection_method)
+        .map_err(|e| format!("Failed to update injection method: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_mic_muted(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    muted: bool,
+) -> Result<(), String> {
+    state
+        .set_mic_muted(&app_handle, muted)
+        .map_err(|e| format!("Failed to update microphone mute: {}", e))
+}
+
+/// Saves the limits and prunes the history to them straight away.
+#[tauri::command]
+pub fn update_history_retention(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    history: HistoryConfig,
+) -> Result<usize, String> {
+    state
+        .settings
+        .write()
+        .update_history_retention(&app_handle, history.clone())
+        .map_err(|e| format!("Failed to update history retention: {}", e))?;
+
+    TextTranscriptHistoryService::prune(&app_handle, &history)
+        .map_err(|e| format!("Failed to prune history: {}", e))
+}
+
+#[tauri::command]
+pub fn update_processing_mode(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    processing_mode: ProcessingMode,
+) -> Result<(), String> {
+    let mut settings = state.settings.write();
+    settings
+        .update_processing_mode(&app_handle, processing_mode)
+        .map_err(|e| format!("Failed to update processing mode: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn update_app_profile(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    app_name: String,
+    profile: AppProfile,
+) -> Result<(), String> {
+    let mut settings = state.settings.write();
+    settings
+        .update_app_profile(&app_handle, app_name, profile)
+        .map_err(|e| format!("Failed to update app profile: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn remove_app_profile(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    app_name: String,
+) -> Result<(), String> {
+    let mut settings = state.settings.write();
+    settings
+        .remove_app_profile(&app_handle, &app_name)
+        .map_err(|e| format!("Failed to remove app profile: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_usage_metrics(state: tauri::State<'_, Arc<AppState>>) -> MetricsSummary {
+    state.metrics.lock().summary()
+}
+
+/// Same counters as `get_usage_metrics`, in the Prometheus text format for
+/// anyone who wants to scrape or archive them.
+#[tauri::command]
+pub fn export_usage_metrics(state: tauri::State<'_, Arc<AppState>>) -> String {
+    state.metrics.lock().to_prometheus()
+}
+
+#[tauri::command]
+pub fn reset_usage_metrics(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<(), String> {
+    MetricsService::reset(&app_handle, &state)
+        .map_err(|e| format!("Failed to reset metrics: {}", e))
+}
+
+/// Tokens and cost of the LLM requests made since the client was last
+/// created, i.e. since launch or the last provider change.
+#[tauri::command]
+pub fn get_llm_usage(state: tauri::State<'_, Arc<AppState>>) -> UsageStats {
+    state
+        .llm
+        .lock()
+        .as_ref()
+        .map(|client| client.cumulative_usage())
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+pub fn get_transcriber_status(state: tauri::State<'_, Arc<AppState>>) -> Option<TranscriberStatus> {
+    let pipeline = state.audio_pipeline.lock().clone()?;
+    Some(pipeline.transcription_service.status())
+}
+
+/// Whether a speech model is loaded, for onboarding to wait on before the
+/// first dictation.
+#[tauri::command]
+pub fn is_model_ready(state: tauri::State<'_, Arc<AppState>>) -> bool {
+    state
+        .transcriber
+        .lock()
+        .as_ref()
+        .is_some_and(|transcriber| transcriber.service.is_loaded())
+}
+
+#[tauri::command]
+pub fn is_safe_mode(state: tauri::State<'_, Arc<AppState>>) -> bool {
+    state.safe_mode
+}
+
+#[tauri::command]
+pub fn get_autostart(app_handle: AppHandle) -> Result<bool, String> {
+    app_handle
+        .autolaunch()
+        .is_enabled()
+        .map_err(|e| format!("Failed to query autostart: {}", e))
+}
+
+/// Registers or unregisters launch at login and returns the resulting
+/// registration state as reported by the OS.
+#[tauri::command]
+pub fn set_autostart(
+    app_handle: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    enabled: bool,
+) -> Result<bool, String> {
+    let autolaunch = app_handle.autolaunch();
+    let result = if enabled {
+        autolaunch.enable()
+    } else {
+        autolaunch.disable()
+    };
+    result.map_err(|e| format!("Failed to update autostart: {}", e))?;
+
+    let registered = autolaunch
+        .is_enabled()
+        .map_err(|e| format!("Failed to query autostart: {}", e))?;
+
+    state
+        .settings
+        .write()
+        .update_launch_at_login(&app_handle, registered)
+        .map_err(|e| format!("Failed to persist autostart setting: {}", e))?;
+
+    Ok(registered)
+}
+
+/// Enables full prompt/response logging until the app restarts. Intended for
+/// debugging only; logs captured while enabled contain dictated text.
+#[tauri::command]
+pub fn set_llm_content_logging(enabled: bool) -> Result<(), String> {
+    rune_llm::redact::set_content_logging(enabled);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn undo_last_injection(app_handle: AppHandle) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        TextProcessingService::undo_last_injection(&app_handle)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| format!("Failed to undo injection: {}", e))
+}
+
+/// Types `sample` into whichever app is focused once `delay_secs` have passed,
+/// giving the user time to switch to the app they want to test. Whatever has
+/// focus at that point will receive the text.
+#[tauri::command]
+pub async fn test_injection(
+    sample: String,
+    delay_secs: Option<u64>,
+) -> Result<InjectionReport, String> {
+    let delay = std::time::Duration::from_secs(delay_secs.unwrap_or(3));
+    log::warn!(
+        "Test injection will type {} characters into the focused app in {:?}",
+        sample.chars().count(),
+        delay
+    );
+    tokio::time::sleep(delay).await;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let app_name = AudioPipelineController::get_frontmost_app_name();
+        TextInjectorService::test_injection(app_name, &sample)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
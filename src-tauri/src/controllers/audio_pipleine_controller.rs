@@ -1,18 +1,43 @@
-use std::{path::PathBuf, process::Command, sync::Arc};
+use std::{
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use tokio::sync::oneshot;
 
 use crate::{
-    core::{app::AppState, state_machine::AppCommand, utils::audio::get_recordings_path},
+    core::{
+        app::AppState,
+        config::ProcessingMode,
+        error::AudioError,
+        state_machine::AppCommand,
+        system::{
+            platform::{self, FrontmostApp},
+            window_manager::WindowManager,
+        },
+        utils::{
+            audio::{get_recordings_path, validate_wav},
+            text::grapheme_count,
+        },
+    },
     services::{
         audio_recording_service::AudioRecordingService,
-        text_processing_service::TextProcessingService,
-        text_transcript_history_service::TextTranscriptHistoryService,
-        text_transcription_service::TextTranscriptionService,
+        correction_feedback_service::CorrectionFeedbackService,
+        dictation_merge_service::MergeTarget,
+        metrics_service::{MetricsEvent, MetricsService, PipelineTimings},
+        text_injector_service::TextInjectorService,
+        text_processing_service::{InjectionOutcome, TextProcessingService},
+        text_transcript_history_service::{RecordingSource, TextTranscriptHistoryService},
+        text_transcription_service::{
+            ModelLoadComplete, ModelLoadFailed, SharedTranscriber, TextTranscriptionService,
+            Transcription,
+        },
     },
 };
 use parking_lot::{Mutex, MutexGuard};
+use serde::Serialize;
 use std::sync::atomic::{AtomicBool, Ordering};
-use tauri::{path::BaseDirectory, AppHandle, Emitter, Manager};
+use tauri::{AppHandle, Emitter, Manager};
 
 #[derive(Debug, Clone)]
 pub enum ProcessingStatus {
@@ -26,6 +51,50 @@ pub enum ProcessingStatus {
     Error(String),
 }
 
+/// The stage a dictation had reached when it was cancelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PipelineStage {
+    Recording,
+    Transcribing,
+    Processing,
+}
+
+/// Payload of the `pipeline-cancelled` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct CancellationEvent {
+    pub stage: PipelineStage,
+    /// Text had already been produced when the cancel arrived.
+    pub partial_available: bool,
+    pub copied_to_clipboard: bool,
+}
+
+/// Resets the pipeline flags and tells the state machine the run is over when
+/// dropped, so a panic in the processing thread still returns the app to Idle.
+struct ProcessingRun {
+    state: Arc<AppState>,
+    cancellation_token: Arc<AtomicBool>,
+    is_processing: Arc<AtomicBool>,
+}
+
+impl Drop for ProcessingRun {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            log::error!("Audio pipeline processing panicked, resetting state");
+        }
+
+        self.cancellation_token.store(false, Ordering::SeqCst);
+        self.is_processing.store(false, Ordering::SeqCst);
+
+        if let Some(state_machine) = &*self.state.state_machine.lock() {
+            // Also purges the pipeline, unless the run no longer owns the state
+            state_machine.send_command(AppCommand::ProcessingComplete);
+        }
+
+        log::info!("Audio pipeline processing complete and state reset");
+    }
+}
+
 impl ProcessingStatus {
     pub fn as_str(&self) -> &'static str {
         match self {
@@ -43,12 +112,14 @@ impl ProcessingStatus {
 
 pub struct AudioPipelineController {
     pub state: Arc<AppState>,
-    pub previous_app: parking_lot::Mutex<Option<String>>,
+    pub previous_app: parking_lot::Mutex<Option<FrontmostApp>>,
+    pub previous_window: parking_lot::Mutex<Option<String>>,
     pub app_handle: AppHandle,
     pub recording_service: Arc<Mutex<AudioRecordingService>>,
-    pub transcription_service: Arc<Mutex<TextTranscriptionService>>,
+    pub transcription_service: Arc<TextTranscriptionService>,
     pub cancellation_token: Arc<AtomicBool>,
     pub is_processing: Arc<AtomicBool>,
+    recording_started_at: Arc<Mutex<Option<Instant>>>,
 }
 
 impl AudioPipelineController {
@@ -57,88 +128,170 @@ impl AudioPipelineController {
 
         let recording_service_mutex = Arc::new(Mutex::new(recording_service));
 
-        let resource_dir = app_handle
-            .path()
-            .resolve("models/whisper-base", BaseDirectory::Resource)
-            .ok();
+        let (custom_model_dir, whisper_model, pool_size) = {
+            let settings = state.settings.read();
+            (
+                settings.transcription.active_custom_model_dir(),
+                settings.transcription.whisper_model,
+                settings.transcription.model_pool_size,
+            )
+        };
+
+        let resource_dir = custom_model_dir
+            .or_else(|| TextTranscriptionService::find_model_dir(&app_handle, whisper_model));
 
         log::info!("Using model directory: {:?}", resource_dir);
 
         let transcription_service =
-            match TextTranscriptionService::new(resource_dir, Some(app_handle.clone())) {
-                Ok(t) => Arc::new(Mutex::new(t)),
-                Err(e) => {
-                    log::error!("Failed to create transcriber with custom path: {}", e);
-
-                    let fallback_paths = [
-                        dirs::data_dir().map(|p| p.join("rune/models/whisper-base")),
-                        Some(PathBuf::from("./models/whisper-base")),
-                        Some(PathBuf::from("../models/whisper-base")),
-                    ];
-
-                    for path in fallback_paths.iter().flatten() {
-                        if path.exists() {
-                            log::info!("Trying fallback model path: {:?}", path);
-                            if let Ok(t) = TextTranscriptionService::new(
-                                Some(path.clone()),
-                                Some(app_handle.clone()),
-                            ) {
-                                return Self {
-                                    state,
-                                    previous_app: parking_lot::Mutex::new(None),
-                                    app_handle,
-                                    recording_service: recording_service_mutex,
-                                    transcription_service: Arc::new(Mutex::new(t)),
-                                    cancellation_token: Arc::new(AtomicBool::new(false)),
-                                    is_processing: Arc::new(AtomicBool::new(false)),
-                                };
-                            }
-                        }
-                    }
-
-                    log::warn!(
-                        "Creating transcriber without model - will not be able to transcribe"
-                    );
-                    match TextTranscriptionService::new(None, Some(app_handle.clone())) {
-                        Ok(t) => Arc::new(Mutex::new(t)),
-                        Err(e) => {
-                            log::error!("Failed to create transcriber: {}", e);
-                            panic!("Cannot initialize transcriber: {}", e);
-                        }
-                    }
-                }
-            };
+            Self::shared_transcription_service(&state, &app_handle, resource_dir, pool_size);
 
         Self {
             state,
             previous_app: parking_lot::Mutex::new(None),
+            previous_window: parking_lot::Mutex::new(None),
             app_handle,
             recording_service: recording_service_mutex,
             transcription_service,
             cancellation_token: Arc::new(AtomicBool::new(false)),
             is_processing: Arc::new(AtomicBool::new(false)),
+            recording_started_at: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Returns the transcriber kept on `AppState` when it was loaded for the
+    /// same model, so the weights survive pipeline rebuilds. A different model
+    /// is reloaded in place; anything else loads a fresh transcriber.
+    fn shared_transcription_service(
+        state: &AppState,
+        app_handle: &AppHandle,
+        model_dir: Option<PathBuf>,
+        pool_size: usize,
+    ) -> Arc<TextTranscriptionService> {
+        let mut shared = state.transcriber.lock();
+
+        if let Some(cached) = shared.as_mut() {
+            if cached.pool_size == pool_size && cached.service.is_loaded() {
+                if cached.model_dir == model_dir {
+                    log::info!("Reusing loaded transcription model");
+                    return Arc::clone(&cached.service);
+                }
+
+                if let Some(dir) = &model_dir {
+                    match cached.service.reload_model(dir.clone()) {
+                        Ok(()) => {
+                            log::info!("Reloaded transcription model from {:?}", dir);
+                            Self::emit_model_loaded(app_handle, Some(dir.clone()), pool_size);
+                            cached.model_dir = model_dir;
+                            return Arc::clone(&cached.service);
+                        }
+                        Err(e) => log::error!("Failed to reload transcription model: {}", e),
+                    }
+                }
+            }
+        }
+
+        let service = Self::load_transcription_service(app_handle, model_dir.clone(), pool_size);
+        *shared = Some(SharedTranscriber {
+            model_dir,
+            pool_size,
+            service: Arc::clone(&service),
+        });
+        service
+    }
+
+    fn load_transcription_service(
+        app_handle: &AppHandle,
+        model_dir: Option<PathBuf>,
+        pool_size: usize,
+    ) -> Arc<TextTranscriptionService> {
+        match TextTranscriptionService::with_pool_size(
+            model_dir.clone(),
+            Some(app_handle.clone()),
+            pool_size,
+        ) {
+            Ok(t) => {
+                if t.is_loaded() {
+                    Self::emit_model_loaded(app_handle, model_dir, pool_size);
+                } else {
+                    // No model on disk yet, which onboarding must hear about too
+                    Self::emit_model_load_failed(app_handle, "No speech model is installed");
+                }
+                Arc::new(t)
+            }
+            Err(e) => {
+                log::error!("Failed to create transcriber with custom path: {}", e);
+
+                let fallback_paths = [
+                    dirs::data_dir().map(|p| p.join("rune/models/whisper-base")),
+                    Some(PathBuf::from("./models/whisper-base")),
+                    Some(PathBuf::from("../models/whisper-base")),
+                ];
+
+                for path in fallback_paths.iter().flatten() {
+                    if path.exists() {
+                        log::info!("Trying fallback model path: {:?}", path);
+                        if let Ok(t) = TextTranscriptionService::with_pool_size(
+                            Some(path.clone()),
+                            Some(app_handle.clone()),
+                            pool_size,
+                        ) {
+                            Self::emit_model_loaded(app_handle, Some(path.clone()), pool_size);
+                            return Arc::new(t);
+                        }
+                    }
+                }
+
+                log::warn!("Creating transcriber without model - will not be able to transcribe");
+                Self::emit_model_load_failed(app_handle, &e.to_string());
+                match TextTranscriptionService::new(None, Some(app_handle.clone())) {
+                    Ok(t) => Arc::new(t),
+                    Err(e) => {
+                        log::error!("Failed to create transcriber: {}", e);
+                        panic!("Cannot initialize transcriber: {}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    fn emit_model_load_failed(app_handle: &AppHandle, error: &str) {
+        let failed = ModelLoadFailed {
+            error: error.to_string(),
+        };
+        if let Err(e) = app_handle.emit("model-load-failed", failed) {
+            log::warn!("Failed to emit model load failure: {}", e);
+        }
+    }
+
+    fn emit_model_loaded(app_handle: &AppHandle, model_dir: Option<PathBuf>, instances: usize) {
+        let complete = ModelLoadComplete {
+            model_dir,
+            instances: instances.max(1),
+        };
+        if let Err(e) = app_handle.emit("model-load-complete", complete) {
+            log::warn!("Failed to emit model load completion: {}", e);
         }
     }
 
     pub fn get_frontmost_app_name() -> Option<String> {
-        let output = Command::new("osascript")
-            .arg("-e")
-            .arg(r#"tell application "System Events" to get name of first application process whose frontmost is true"#)
-            .output()
-            .ok()?;
-
-        String::from_utf8(output.stdout)
-            .ok()
-            .map(|s| s.trim().to_string())
+        platform::current().frontmost_app().map(|app| app.name)
+    }
+
+    pub fn get_frontmost_app() -> Option<FrontmostApp> {
+        platform::current().frontmost_app()
     }
 
-    pub fn activate_app(app_name: &str) {
-        log::info!("Activating app: {}", app_name);
-        Command::new("osascript")
-            .arg("-e")
-            .arg(format!(r#"tell application "{}" to activate"#, app_name))
-            .output()
-            .ok();
+    /// Title of the frontmost window, used to tell apart documents or chats
+    /// within the same app.
+    pub fn get_frontmost_window_title() -> Option<String> {
+        platform::current().frontmost_window_title()
+    }
+
+    pub fn activate_app(app: &FrontmostApp) {
+        log::info!("Activating app: {:?}", app);
+        if !platform::current().activate_app(app) {
+            log::warn!("Failed to activate {}", app.name);
+        }
     }
 
     pub fn is_processing(&self) -> bool {
@@ -149,10 +302,19 @@ impl AudioPipelineController {
         self.cancellation_token.load(Ordering::SeqCst)
     }
 
+    /// Time since the current recording started, pauses included. `None`
+    /// once it has been stopped or cancelled.
+    pub fn recording_elapsed(&self) -> Option<Duration> {
+        self.recording_started_at
+            .lock()
+            .map(|started| started.elapsed())
+    }
+
     pub fn signal_cancellation(&self) {
         log::info!("Cancellation signal received");
         self.cancellation_token.store(true, Ordering::SeqCst);
         self.is_processing.store(false, Ordering::SeqCst);
+        self.recording_started_at.lock().take();
     }
 
     pub fn force_stop(&self) -> Result<(), anyhow::Error> {
@@ -160,6 +322,7 @@ impl AudioPipelineController {
 
         self.cancellation_token.store(true, Ordering::SeqCst);
         self.is_processing.store(false, Ordering::SeqCst);
+        self.recording_started_at.lock().take();
 
         {
             let recording_service = self.recording_service.lock();
@@ -172,10 +335,7 @@ impl AudioPipelineController {
 
         if let Some(window) = self.app_handle.get_webview_window("main") {
             if let Ok(true) = window.is_visible() {
-                let _ = window.emit(
-                    "audio-processing-status",
-                    ProcessingStatus::Cancelled.as_str(),
-                );
+                Self::emit_cancelled(&self.app_handle, PipelineStage::Recording, None);
                 let _ = window.hide();
             }
         }
@@ -188,6 +348,7 @@ impl AudioPipelineController {
 
         self.cancellation_token.store(true, Ordering::SeqCst);
         self.is_processing.store(false, Ordering::SeqCst);
+        self.recording_started_at.lock().take();
 
         if let Some(state_machine) = &*self.state.state_machine.lock() {
             state_machine.send_command(AppCommand::Cancel);
@@ -203,10 +364,7 @@ impl AudioPipelineController {
 
         if let Some(window) = self.app_handle.get_webview_window("main") {
             if let Ok(true) = window.is_visible() {
-                let _ = window.emit(
-                    "audio-processing-status",
-                    ProcessingStatus::Cancelled.as_str(),
-                );
+                Self::emit_cancelled(&self.app_handle, PipelineStage::Recording, None);
                 let _ = window.hide();
             }
         }
@@ -216,6 +374,41 @@ impl AudioPipelineController {
         }
     }
 
+    /// Reports a cancellation to the UI. Text produced before the cancel is
+    /// left on the clipboard so the user doesn't lose it.
+    pub fn emit_cancelled(app_handle: &AppHandle, stage: PipelineStage, partial: Option<&str>) {
+        let partial = partial.map(str::trim).filter(|text| !text.is_empty());
+        let copied_to_clipboard = match partial {
+            Some(text) => match TextInjectorService::copy_to_clipboard(text) {
+                Ok(()) => true,
+                Err(e) => {
+                    log::warn!("Failed to copy partial result to clipboard: {}", e);
+                    false
+                }
+            },
+            None => false,
+        };
+
+        let event = CancellationEvent {
+            stage,
+            partial_available: partial.is_some(),
+            copied_to_clipboard,
+        };
+        log::info!("Cancelled: {:?}", event);
+
+        if let Some(window) = app_handle.get_webview_window("main") {
+            let _ = window.emit_to(
+                "main",
+                "audio-processing-status",
+                ProcessingStatus::Cancelled.as_str(),
+            );
+        }
+
+        if let Err(e) = app_handle.emit("pipeline-cancelled", event) {
+            log::warn!("Failed to emit cancellation event: {}", e);
+        }
+    }
+
     pub fn cancel_sync(&self) {
         log::info!("Synchronous cancellation requested");
 
@@ -227,48 +420,98 @@ impl AudioPipelineController {
         }
 
         let controller_copy = self.clone();
+        let state = Arc::clone(&self.state);
         std::thread::spawn(move || {
-            let rt = tokio::runtime::Builder::new_current_thread()
-                .enable_all()
-                .build()
-                .unwrap();
-
-            rt.block_on(async {
-                controller_copy.cancel().await;
-            });
+            state.runtime.block_on(controller_copy.cancel());
         });
     }
 
+    /// Starts a recording unless this pipeline is already recording or
+    /// processing one. The check and the claim are a single atomic step, so
+    /// starts racing in from the tray and the shortcut can't both get through.
     pub async fn start(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if self
+            .is_processing
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            log::warn!("Ignoring start, a recording is already in progress");
+            return Err("A recording is already in progress".into());
+        }
+
+        let result = self.begin_recording().await;
+        if result.is_err() {
+            self.is_processing.store(false, Ordering::SeqCst);
+        }
+        result
+    }
+
+    async fn begin_recording(&self) -> Result<(), Box<dyn std::error::Error>> {
         log::info!("Starting audio pipeline recording");
 
         self.cancellation_token.store(false, Ordering::SeqCst);
-        self.is_processing.store(true, Ordering::SeqCst);
 
-        if let Some(app_name) = Self::get_frontmost_app_name() {
-            *self.previous_app.lock() = Some(app_name.clone());
-            log::info!("Previous app: {}", app_name);
+        if let Some(app) = Self::get_frontmost_app() {
+            log::info!("Previous app: {:?}", app);
+            *self.previous_app.lock() = Some(app);
         }
+        *self.previous_window.lock() = Self::get_frontmost_window_title();
 
         if let Some(window) = self.app_handle.get_webview_window("main") {
+            if let Err(e) = WindowManager::position_on_active_monitor(&window) {
+                log::warn!("Failed to position window on active monitor: {}", e);
+            }
             window.show()?;
             window.set_focus()?;
         }
 
         let settings = self.state.settings.read().clone();
         let device_id = settings.audio.default_device.clone();
+        let max_buffer_bytes = settings
+            .audio
+            .max_buffer_mb
+            .map(|mb| (mb as usize).saturating_mul(1024 * 1024));
+
+        if let Err(e) = self
+            .transcription_service
+            .set_decoding_strategy(settings.transcription.decoding_strategy())
+        {
+            log::warn!("Keeping current decoding strategy: {}", e);
+        }
+        self.transcription_service
+            .set_initial_prompt(settings.transcription.initial_prompt.clone());
+        self.transcription_service
+            .set_vad_enabled(settings.transcription.vad_enabled);
 
         {
             let recording_service = self.recording_service.lock();
             recording_service.set_device_id(device_id);
+            recording_service
+                .set_buffer_limit(max_buffer_bytes, settings.audio.buffer_overflow_policy);
+            recording_service.set_muted(settings.audio.muted);
+            recording_service.set_level_mode(settings.audio.level_mode);
+            recording_service.set_noise_reduction(settings.audio.noise_reduction);
+            recording_service.set_gain(settings.audio.input_gain, settings.audio.normalization);
+            recording_service.set_silence_detection(
+                settings.audio.silence_timeout_ms.map(Duration::from_millis),
+                settings.audio.silence_threshold,
+            );
             recording_service.set_app_handle(self.app_handle.clone());
         }
 
+        // The recording emits its own levels
+        self.state.level_monitor.stop();
+
         let result = {
             let recording_service = self.recording_service.lock();
             recording_service.start_recording(&self.app_handle).await
         };
 
+        if result.is_ok() {
+            MetricsService::record(&self.app_handle, MetricsEvent::Started);
+            *self.recording_started_at.lock() = Some(Instant::now());
+        }
+
         if let Some(state_machine) = &*self.state.state_machine.lock() {
             match &result {
                 Ok(_) => {
@@ -309,17 +552,26 @@ impl AudioPipelineController {
 
         let controller = self.clone();
         let app_handle = self.app_handle.clone();
-        let state = Arc::clone(&self.state);
+        let run = ProcessingRun {
+            state: Arc::clone(&self.state),
+            cancellation_token: Arc::clone(&self.cancellation_token),
+            is_processing: Arc::clone(&self.is_processing),
+        };
+        let mut timings = PipelineTimings {
+            record_ms: self
+                .recording_started_at
+                .lock()
+                .take()
+                .map(|started| started.elapsed().as_millis() as u64),
+            ..Default::default()
+        };
 
         std::thread::spawn(move || {
             log::info!("Processing in separate thread");
+            let state = Arc::clone(&run.state);
+            let _run = run;
 
-            let rt = tokio::runtime::Builder::new_current_thread()
-                .enable_all()
-                .build()
-                .unwrap();
-
-            rt.block_on(async {
+            state.runtime.block_on(async {
                 if let Some(window) = app_handle.get_webview_window("main") {
                     let _ = window.emit_to("main", "audio-processing-status", "transcribing");
                 }
@@ -328,9 +580,8 @@ impl AudioPipelineController {
 
                 if controller.is_cancelled() {
                     log::info!("Cancellation detected during stop preparation");
-                    if let Some(window) = app_handle.get_webview_window("main") {
-                        let _ = window.emit_to("main", "audio-processing-status", "cancelled");
-                    }
+                    MetricsService::record(&app_handle, MetricsEvent::Cancelled);
+                    Self::emit_cancelled(&app_handle, PipelineStage::Recording, None);
 
                     if let Some(app) = controller.previous_app.lock().take() {
                         Self::activate_app(&app);
@@ -349,6 +600,7 @@ impl AudioPipelineController {
 
                 if let Err(e) = recording_result {
                     log::error!("Failed to stop recording: {}", e);
+                    MetricsService::record(&app_handle, MetricsEvent::Failed);
 
                     if let Some(window) = app_handle.get_webview_window("main") {
                         let _ = window.emit_to(
@@ -370,9 +622,8 @@ impl AudioPipelineController {
 
                 if controller.is_cancelled() {
                     log::info!("Processing cancelled during transcription setup");
-                    if let Some(window) = app_handle.get_webview_window("main") {
-                        let _ = window.emit_to("main", "audio-processing-status", "cancelled");
-                    }
+                    MetricsService::record(&app_handle, MetricsEvent::Cancelled);
+                    Self::emit_cancelled(&app_handle, PipelineStage::Transcribing, None);
 
                     controller
                         .is_processing
@@ -384,69 +635,120 @@ impl AudioPipelineController {
                     return;
                 }
 
-                if !temp_path.exists() {
-                    log::error!("No recording file found to transcribe");
-                    if let Some(window) = app_handle.get_webview_window("main") {
-                        let _ = window.emit_to(
-                            "main",
-                            "audio-processing-status",
-                            "error: No recording found to transcribe",
-                        );
+                let device = controller.recording_service.lock().device_name();
+
+                let (min_recording, silence_threshold) = {
+                    let settings = state.settings.read();
+                    (
+                        Duration::from_millis(settings.audio.min_recording_ms),
+                        settings.audio.silence_threshold,
+                    )
+                };
+                match validate_wav(&temp_path, min_recording, silence_threshold) {
+                    Ok(info) => log::info!(
+                        "Recording is {} ms at {} Hz, peaking at {:.3}",
+                        info.duration.as_millis(),
+                        info.sample_rate,
+                        info.peak
+                    ),
+                    Err(AudioError::NoSpeech(reason)) => {
+                        log::info!("Skipping transcription, {}", reason);
+                        MetricsService::record(&app_handle, MetricsEvent::NoSpeech);
+                        if let Some(window) = app_handle.get_webview_window("main") {
+                            let _ = window.emit_to("main", "audio-processing-status", "no_speech");
+                        }
+                        controller
+                            .is_processing
+                            .store(false, std::sync::atomic::Ordering::SeqCst);
+
+                        if let Some(app) = controller.previous_app.lock().take() {
+                            Self::activate_app(&app);
+                        }
+                        return;
+                    }
+                    Err(e) => {
+                        log::error!("Recording can't be transcribed: {}", e);
+                        MetricsService::record(&app_handle, MetricsEvent::Failed);
+                        if let Some(window) = app_handle.get_webview_window("main") {
+                            let _ = window.emit_to(
+                                "main",
+                                "audio-processing-status",
+                                format!("error: {}", e),
+                            );
+                        }
+                        controller
+                            .is_processing
+                            .store(false, std::sync::atomic::Ordering::SeqCst);
+
+                        if let Some(app) = controller.previous_app.lock().take() {
+                            Self::activate_app(&app);
+                        }
+                        return;
                     }
-                    controller
-                        .is_processing
-                        .store(false, std::sync::atomic::Ordering::SeqCst);
-                    return;
                 }
 
-                let app_name = controller.previous_app.lock().clone().unwrap_or_default();
+                let app_name = controller
+                    .previous_app
+                    .lock()
+                    .as_ref()
+                    .map(|app| app.name.clone())
+                    .unwrap_or_default();
                 let (tx, rx) = oneshot::channel();
                 let temp_path_clone = temp_path.clone();
                 let transcription_service = controller.transcription_service.clone();
                 let cancellation_token = controller.cancellation_token.clone();
+                let transcription_start = Instant::now();
 
                 std::thread::spawn(move || {
                     log::info!("Starting transcription in separate thread");
 
-                    let transcription_result = {
-                        let mut transcription_service_guard = transcription_service.lock();
-                        transcription_service_guard.transcribe(temp_path_clone)
-                    };
-
-                    if cancellation_token.load(std::sync::atomic::Ordering::SeqCst) {
-                        log::info!("Transcription cancelled");
-                        let _ = tx.send(Err(anyhow::anyhow!("Processing cancelled")));
-                        return;
-                    }
+                    let transcription_result = transcription_service
+                        .transcribe_cancellable(temp_path_clone, Some(cancellation_token));
 
+                    // Sent even when cancelled meanwhile, so the text can be salvaged
                     let _ = tx.send(transcription_result.map_err(|e| anyhow::anyhow!("{}", e)));
                     log::info!("Transcription thread completed");
                 });
 
-                let transcription_result: Result<Vec<std::string::String>, anyhow::Error> =
-                    match rx.await {
-                        Ok(result) => result,
-                        Err(e) => {
-                            log::error!("Failed to receive transcription result: {}", e);
-                            if let Some(window) = app_handle.get_webview_window("main") {
-                                let _ = window.emit_to(
-                                    "main",
-                                    "audio-processing-status",
-                                    format!("error: Transcription failed: {}", e),
-                                );
-                            }
-                            controller
-                                .is_processing
-                                .store(false, std::sync::atomic::Ordering::SeqCst);
-                            return;
+                let transcription_result: Result<Transcription, anyhow::Error> = match rx.await {
+                    Ok(result) => {
+                        if result.is_ok() {
+                            let elapsed = transcription_start.elapsed();
+                            timings.transcription_ms = Some(elapsed.as_millis() as u64);
+                            MetricsService::record(&app_handle, MetricsEvent::Transcribed(elapsed));
+                        }
+                        result
+                    }
+                    Err(e) => {
+                        log::error!("Failed to receive transcription result: {}", e);
+                        MetricsService::record(&app_handle, MetricsEvent::Failed);
+                        if let Some(window) = app_handle.get_webview_window("main") {
+                            let _ = window.emit_to(
+                                "main",
+                                "audio-processing-status",
+                                format!("error: Transcription failed: {}", e),
+                            );
                         }
-                    };
+                        controller
+                            .is_processing
+                            .store(false, std::sync::atomic::Ordering::SeqCst);
+                        return;
+                    }
+                };
 
                 if controller.is_cancelled() {
                     log::info!("Processing cancelled after transcription");
-                    if let Some(window) = app_handle.get_webview_window("main") {
-                        let _ = window.emit_to("main", "audio-processing-status", "cancelled");
-                    }
+                    MetricsService::record(&app_handle, MetricsEvent::Cancelled);
+                    // Every window decoded before the cancel, not just the first
+                    let partial = transcription_result
+                        .as_ref()
+                        .ok()
+                        .and_then(Transcription::text);
+                    Self::emit_cancelled(
+                        &app_handle,
+                        PipelineStage::Transcribing,
+                        partial.as_deref(),
+                    );
 
                     controller
                         .is_processing
@@ -460,30 +762,81 @@ impl AudioPipelineController {
 
                 match transcription_result {
                     Ok(transcription) => {
-                        if let Some(text) = transcription.first() {
+                        let min_confidence = controller
+                            .state
+                            .settings
+                            .read()
+                            .transcription
+                            .min_confidence;
+                        let low_confidence = !transcription.segments.is_empty()
+                            && min_confidence.is_some_and(|min| transcription.confidence < min);
+
+                        if low_confidence {
+                            log::warn!(
+                                "Discarding transcription with confidence {:.2}",
+                                transcription.confidence
+                            );
+                            MetricsService::record(&app_handle, MetricsEvent::Failed);
                             if let Some(window) = app_handle.get_webview_window("main") {
                                 let _ = window.emit_to(
                                     "main",
                                     "audio-processing-status",
-                                    "thinking_action",
+                                    "error: Speech was too unclear to transcribe",
                                 );
                             }
+                            controller
+                                .is_processing
+                                .store(false, std::sync::atomic::Ordering::SeqCst);
+                        } else if let Some(text) = transcription.text().as_ref() {
+                            let raw_mode =
+                                controller.state.settings.read().app_profile(&app_name).mode
+                                    == ProcessingMode::Raw;
+                            if !raw_mode {
+                                if let Some(window) = app_handle.get_webview_window("main") {
+                                    let _ = window.emit_to(
+                                        "main",
+                                        "audio-processing-status",
+                                        "thinking_action",
+                                    );
+                                }
+                            }
 
-                            let text_clone = text.clone();
+                            let merge_config = controller.state.settings.read().merge.clone();
+                            let separator = merge_config.separator.as_str();
+                            let merge_target = MergeTarget {
+                                app_name: app_name.clone(),
+                                window_title: controller.previous_window.lock().clone(),
+                            };
+                            let continuation = controller
+                                .state
+                                .dictation_merge
+                                .continuation(&merge_target, &merge_config);
+                            let reprocess =
+                                continuation.is_some() && merge_config.reprocess_combined;
+                            let raw_combined = match &continuation {
+                                Some(previous) => {
+                                    format!("{}{}{}", previous.previous_raw, separator, text)
+                                }
+                                None => text.clone(),
+                            };
+
+                            let text_clone = if reprocess {
+                                raw_combined.clone()
+                            } else {
+                                text.clone()
+                            };
                             let app_name_clone = app_name.clone();
                             let state = controller.state.clone();
+                            let use_cache = !state.llm_cache.take_bypass();
+                            let llm_start = Instant::now();
 
                             let process_thread = std::thread::spawn(move || {
-                                let rt = tokio::runtime::Builder::new_current_thread()
-                                    .enable_all()
-                                    .build()
-                                    .unwrap();
-
-                                rt.block_on(async {
+                                state.runtime.block_on(async {
                                     TextProcessingService::process_text(
                                         &state,
                                         &app_name_clone,
                                         &text_clone,
+                                        use_cache,
                                     )
                                     .await
                                 })
@@ -493,16 +846,27 @@ impl AudioPipelineController {
                                 log::error!("Failed to join text processing thread: {:?}", e);
                                 Err(anyhow::anyhow!("Thread panic during text processing"))
                             });
+                            if !raw_mode {
+                                let elapsed = llm_start.elapsed();
+                                timings.llm_ms = Some(elapsed.as_millis() as u64);
+                                MetricsService::record(
+                                    &app_handle,
+                                    MetricsEvent::LlmProcessed(elapsed),
+                                );
+                            }
 
                             if controller.is_cancelled() {
                                 log::info!("Processing cancelled after text processing");
-                                if let Some(window) = app_handle.get_webview_window("main") {
-                                    let _ = window.emit_to(
-                                        "main",
-                                        "audio-processing-status",
-                                        "cancelled",
-                                    );
-                                }
+                                MetricsService::record(&app_handle, MetricsEvent::Cancelled);
+                                let partial = match &processed_text_result {
+                                    Ok(processed_text) => processed_text.as_str(),
+                                    Err(_) => text.as_str(),
+                                };
+                                Self::emit_cancelled(
+                                    &app_handle,
+                                    PipelineStage::Processing,
+                                    Some(partial),
+                                );
                                 controller
                                     .is_processing
                                     .store(false, std::sync::atomic::Ordering::SeqCst);
@@ -514,20 +878,73 @@ impl AudioPipelineController {
 
                             match processed_text_result {
                                 Ok(processed_text) => {
+                                    MetricsService::record(&app_handle, MetricsEvent::Completed);
+
                                     if let Some(app) = controller.previous_app.lock().take() {
                                         Self::activate_app(&app);
                                     }
 
-                                    if let Err(e) =
-                                        TextProcessingService::inject_text(&processed_text)
-                                    {
-                                        log::error!("Failed to inject text: {}", e);
+                                    let (to_inject, injected_combined) = match &continuation {
+                                        Some(previous) if reprocess => {
+                                            // The combined paragraph replaces what was typed before
+                                            if let Err(e) = TextInjectorService::delete_backward(
+                                                grapheme_count(&previous.previous_injected),
+                                            ) {
+                                                log::error!("Failed to remove merged text: {}", e);
+                                            }
+                                            (processed_text.clone(), processed_text.clone())
+                                        }
+                                        Some(previous) => {
+                                            let appended =
+                                                format!("{}{}", separator, processed_text);
+                                            let combined = format!(
+                                                "{}{}",
+                                                previous.previous_injected, appended
+                                            );
+                                            (appended, combined)
+                                        }
+                                        None => (processed_text.clone(), processed_text.clone()),
+                                    };
+
+                                    let injection_start = Instant::now();
+                                    let injection =
+                                        TextProcessingService::inject_text(&app_handle, &to_inject);
+                                    timings.injection_ms =
+                                        Some(injection_start.elapsed().as_millis() as u64);
+                                    match injection {
+                                        Ok(InjectionOutcome::Injected) => {
+                                            controller.state.dictation_merge.record(
+                                                merge_target,
+                                                raw_combined,
+                                                injected_combined,
+                                            );
+                                            CorrectionFeedbackService::watch_injection(
+                                                &controller.state,
+                                                &app_name,
+                                                &to_inject,
+                                            )
+                                        }
+                                        Ok(InjectionOutcome::CopiedToClipboard) => {
+                                            controller.state.dictation_merge.reset();
+                                            log::warn!(
+                                                "Text copied to clipboard instead of injected"
+                                            )
+                                        }
+                                        Err(e) => {
+                                            controller.state.dictation_merge.reset();
+                                            log::error!("Failed to inject text: {}", e)
+                                        }
                                     }
 
                                     if let Err(e) =
                                         TextTranscriptHistoryService::save_processed_text(
                                             &app_handle,
                                             &processed_text,
+                                            Some(timings),
+                                            Some(RecordingSource {
+                                                wav_path: &temp_path,
+                                                device: device.clone(),
+                                            }),
                                         )
                                     {
                                         log::error!(
@@ -535,6 +952,7 @@ impl AudioPipelineController {
                                             e
                                         );
                                     }
+                                    MetricsService::emit_timings(&app_handle, &timings);
 
                                     if let Some(window) = app_handle.get_webview_window("main") {
                                         let _ = window.emit_to(
@@ -554,26 +972,79 @@ impl AudioPipelineController {
                                 Err(e) => {
                                     log::error!("Text processing error: {}", e);
                                     if controller.is_cancelled() {
-                                        if let Some(window) = app_handle.get_webview_window("main")
-                                        {
-                                            let _ = window.emit_to(
-                                                "main",
-                                                "audio-processing-status",
-                                                "cancelled",
-                                            );
-                                        }
+                                        MetricsService::record(
+                                            &app_handle,
+                                            MetricsEvent::Cancelled,
+                                        );
+                                        Self::emit_cancelled(
+                                            &app_handle,
+                                            PipelineStage::Processing,
+                                            Some(text),
+                                        );
                                     } else {
+                                        // The raw transcription still reaches the user
+                                        MetricsService::record(
+                                            &app_handle,
+                                            MetricsEvent::Completed,
+                                        );
+
                                         if let Some(app) = controller.previous_app.lock().take() {
                                             Self::activate_app(&app);
                                         }
-                                        if let Err(e) = TextProcessingService::inject_text(text) {
-                                            log::error!("Failed to inject original text: {}", e);
+                                        // Nothing was reprocessed, so only this dictation is appended
+                                        let (to_inject, injected_combined) = match &continuation {
+                                            Some(previous) => {
+                                                let appended = format!("{}{}", separator, text);
+                                                let combined = format!(
+                                                    "{}{}",
+                                                    previous.previous_injected, appended
+                                                );
+                                                (appended, combined)
+                                            }
+                                            None => (text.clone(), text.clone()),
+                                        };
+
+                                        let injection_start = Instant::now();
+                                        let injection = TextProcessingService::inject_text(
+                                            &app_handle,
+                                            &to_inject,
+                                        );
+                                        timings.injection_ms =
+                                            Some(injection_start.elapsed().as_millis() as u64);
+                                        match injection {
+                                            Ok(InjectionOutcome::Injected) => {
+                                                controller.state.dictation_merge.record(
+                                                    merge_target,
+                                                    raw_combined,
+                                                    injected_combined,
+                                                );
+                                                CorrectionFeedbackService::watch_injection(
+                                                    &controller.state,
+                                                    &app_name,
+                                                    &to_inject,
+                                                )
+                                            }
+                                            Ok(InjectionOutcome::CopiedToClipboard) => {
+                                                controller.state.dictation_merge.reset();
+                                                log::warn!(
+                                                    "Text copied to clipboard instead of injected"
+                                                )
+                                            }
+                                            Err(e) => {
+                                                controller.state.dictation_merge.reset();
+                                                log::error!("Failed to inject original text: {}", e)
+                                            }
                                         }
 
                                         if let Err(e) =
                                             TextTranscriptHistoryService::save_processed_text(
                                                 &app_handle,
                                                 &text,
+                                                Some(timings),
+                                                Some(RecordingSource {
+                                                    wav_path: &temp_path,
+                                                    device: device.clone(),
+                                                }),
                                             )
                                         {
                                             log::error!(
@@ -581,6 +1052,7 @@ impl AudioPipelineController {
                                                 e
                                             );
                                         }
+                                        MetricsService::emit_timings(&app_handle, &timings);
 
                                         if let Some(window) = app_handle.get_webview_window("main")
                                         {
@@ -605,6 +1077,7 @@ impl AudioPipelineController {
                                 .store(false, std::sync::atomic::Ordering::SeqCst);
                         } else {
                             log::error!("No transcription text available");
+                            MetricsService::record(&app_handle, MetricsEvent::Failed);
                             if let Some(window) = app_handle.get_webview_window("main") {
                                 let _ = window.emit_to(
                                     "main",
@@ -619,6 +1092,7 @@ impl AudioPipelineController {
                     }
                     Err(e) => {
                         log::error!("Transcription error: {}", e);
+                        MetricsService::record(&app_handle, MetricsEvent::Failed);
                         if let Some(window) = app_handle.get_webview_window("main") {
                             let _ = window.emit_to(
                                 "main",
@@ -632,21 +1106,6 @@ impl AudioPipelineController {
                     }
                 }
             });
-            controller
-                .cancellation_token
-                .store(false, std::sync::atomic::Ordering::SeqCst);
-            controller
-                .is_processing
-                .store(false, std::sync::atomic::Ordering::SeqCst);
-
-            // Force a state machine reset
-            if let Some(state_machine) = &*state.state_machine.lock() {
-                state_machine.send_command(AppCommand::EmitStatus("idle".to_string()));
-
-                state_machine.send_command(AppCommand::PurgeResources);
-            }
-
-            log::info!("Audio pipeline processing complete and state reset");
         });
     }
 
@@ -654,11 +1113,13 @@ impl AudioPipelineController {
         Self {
             state: Arc::clone(&self.state),
             previous_app: parking_lot::Mutex::new(self.previous_app.lock().clone()),
+            previous_window: parking_lot::Mutex::new(self.previous_window.lock().clone()),
             app_handle: self.app_handle.clone(),
             recording_service: Arc::clone(&self.recording_service),
             transcription_service: Arc::clone(&self.transcription_service),
             cancellation_token: Arc::clone(&self.cancellation_token),
             is_processing: Arc::clone(&self.is_processing),
+            recording_started_at: Arc::clone(&self.recording_started_at),
         }
     }
 
@@ -666,8 +1127,8 @@ impl AudioPipelineController {
         self.recording_service.lock()
     }
 
-    pub fn get_transcription_service(&self) -> MutexGuard<TextTranscriptionService> {
-        self.transcription_service.lock()
+    pub fn get_transcription_service(&self) -> Arc<TextTranscriptionService> {
+        Arc::clone(&self.transcription_service)
     }
 }
 
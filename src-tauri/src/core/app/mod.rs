@@ -4,10 +4,14 @@ mod state;
 use crate::{
     commands,
     core::{config::Settings, error::AppError},
+    services::metrics_service::MetricsService,
 };
 pub use state::AppState;
 use std::sync::Arc;
 
+const SAFE_MODE_ENV: &str = "RUNE_SAFE_MODE";
+const SAFE_MODE_ARG: &str = "--safe-mode";
+
 pub struct App {
     state: Arc<AppState>,
 }
@@ -15,10 +19,19 @@ pub struct App {
 impl App {
     pub fn new() -> Result<Self, AppError> {
         let settings = Settings::default();
-        let state = Arc::new(AppState::new(settings));
+        let state = Arc::new(AppState::new(settings, Self::safe_mode_requested()));
         Ok(Self { state })
     }
 
+    /// Safe mode is requested with `--safe-mode` or `RUNE_SAFE_MODE=1`.
+    fn safe_mode_requested() -> bool {
+        let from_env = std::env::var(SAFE_MODE_ENV)
+            .map(|value| matches!(value.trim(), "1" | "true" | "yes"))
+            .unwrap_or(false);
+
+        from_env || std::env::args().any(|arg| arg == SAFE_MODE_ARG)
+    }
+
     pub fn run(self) -> Result<(), AppError> {
         let state = self.state.clone();
 
@@ -38,7 +51,27 @@ impl App {
                 commands::audio_commands::set_default_device,
                 commands::audio_commands::get_default_device,
                 commands::audio_commands::get_transcription_history,
+                commands::audio_commands::get_recording_audio,
+                commands::audio_commands::search_history,
+                commands::audio_commands::delete_history_entry,
+                commands::audio_commands::redact_history_entry,
+                commands::audio_commands::clear_history,
+                commands::audio_commands::pause_recording,
+                commands::audio_commands::resume_recording,
                 commands::audio_commands::cancel_recording,
+                commands::audio_commands::get_processing_status,
+                commands::audio_commands::transcribe_with_model,
+                commands::audio_commands::transcribe_files,
+                commands::audio_commands::transcribe_external_file,
+                commands::audio_commands::test_transcription,
+                commands::audio_commands::start_level_monitoring,
+                commands::audio_commands::stop_level_monitoring,
+                commands::audio_commands::detect_language,
+                commands::audio_commands::import_custom_model,
+                commands::audio_commands::set_whisper_model,
+                commands::audio_commands::set_model_pool_size,
+                commands::audio_commands::set_download_models,
+                commands::audio_commands::set_active_custom_model,
                 // System commands
                 commands::system_commands::check_accessibility_permissions,
                 commands::system_commands::request_accessibility_permissions,
@@ -47,39 +80,110 @@ impl App {
                 commands::system_commands::set_window_visibility,
                 commands::system_commands::get_settings,
                 commands::system_commands::update_shortcuts,
+                commands::system_commands::update_cancel_shortcut,
+                commands::system_commands::get_shortcut_options,
+                commands::system_commands::export_settings,
+                commands::system_commands::import_settings,
+                commands::system_commands::update_tap_threshold,
+                commands::system_commands::update_recording_mode,
                 commands::system_commands::update_user_profile,
                 commands::system_commands::complete_onboarding,
+                commands::system_commands::update_feedback_consent,
+                commands::system_commands::get_feedback_payloads,
+                commands::system_commands::set_llm_content_logging,
+                commands::system_commands::update_llm_sampling,
+                commands::system_commands::update_llm_provider,
+                commands::system_commands::update_llm_base_url,
+                commands::system_commands::get_llm_error,
+                commands::system_commands::update_llm_history_turns,
+                commands::system_commands::update_llm_timeouts,
+                commands::system_commands::update_llm_cache,
+                commands::system_commands::clear_llm_cache,
+                commands::system_commands::bypass_llm_cache_once,
+                commands::system_commands::get_autostart,
+                commands::system_commands::set_autostart,
+                commands::system_commands::test_injection,
+                commands::system_commands::undo_last_injection,
+                commands::system_commands::update_beam_size,
+                commands::system_commands::update_reset_timeout,
+                commands::system_commands::update_min_confidence,
+                commands::system_commands::update_initial_prompt,
+                commands::system_commands::update_vad_enabled,
+                commands::system_commands::update_chunking,
+                commands::system_commands::update_merge,
+                commands::system_commands::update_silence_detection,
+                commands::system_commands::update_min_recording_ms,
+                commands::system_commands::update_level_mode,
+                commands::system_commands::update_output_format,
+                commands::system_commands::update_keep_recordings,
+                commands::system_commands::update_noise_reduction,
+                commands::system_commands::set_mic_muted,
+                commands::system_commands::update_input_gain,
+                commands::system_commands::update_injection_method,
+                commands::system_commands::update_processing_mode,
+                commands::system_commands::update_history_retention,
+                commands::system_commands::update_app_profile,
+                commands::system_commands::remove_app_profile,
+                commands::system_commands::is_safe_mode,
+                commands::system_commands::get_transcriber_status,
+                commands::system_commands::is_model_ready,
+                commands::system_commands::get_usage_metrics,
+                commands::system_commands::export_usage_metrics,
+                commands::system_commands::reset_usage_metrics,
+                commands::system_commands::get_llm_usage,
             ])
             .setup(move |app| {
                 #[cfg(desktop)]
                 {
                     use tauri_plugin_autostart::MacosLauncher;
-                    use tauri_plugin_autostart::ManagerExt;
 
                     app.handle()
                         .plugin(tauri_plugin_autostart::init(
                             MacosLauncher::LaunchAgent,
-                            Some(vec!["--flag1", "--flag2"]),
+                            None,
                         ))
                         .unwrap();
-
-                    let autostart_manager = app.autolaunch();
-                    let _ = autostart_manager.enable();
-                    log::info!(
-                        "registered for autostart? {}",
-                        autostart_manager.is_enabled().unwrap()
-                    );
-                    let _ = autostart_manager.disable();
                 }
 
+                #[cfg(target_os = "macos")]
                 app.set_activation_policy(tauri::ActivationPolicy::Accessory);
                 setup::setup_app(app, self.state.clone())?;
 
+                #[cfg(desktop)]
+                {
+                    use tauri_plugin_autostart::ManagerExt;
+
+                    // The OS registration is the source of truth; it may have been
+                    // changed outside the app (e.g. in Login Items). Safe mode leaves
+                    // the stored settings untouched.
+                    match app.autolaunch().is_enabled() {
+                        Ok(registered) if !self.state.safe_mode => {
+                            log::info!("Registered for autostart: {}", registered);
+                            let mut settings = self.state.settings.write();
+                            if settings.launch_at_login != registered {
+                                if let Err(e) =
+                                    settings.update_launch_at_login(app.handle(), registered)
+                                {
+                                    log::warn!("Failed to sync autostart setting: {}", e);
+                                }
+                            }
+                        }
+                        Ok(_) => {}
+                        Err(e) => log::warn!("Failed to query autostart state: {}", e),
+                    }
+                }
+
                 Ok(())
             });
 
-        builder
-            .run(tauri::generate_context!())
-            .map_err(|e| e.into())
+        let app = builder.build(tauri::generate_context!())?;
+        app.run(|app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                // Events since the last periodic flush
+                MetricsService::flush(app_handle);
+            }
+        });
+
+        Ok(())
     }
 }
@@ -7,19 +7,31 @@ use crate::core::{
     config::Settings,
     system::{shortcut_manager::ShortcutManager, system_tray_manager::SystemTrayManager},
 };
+use crate::services::{
+    audio_device_service::AudioDeviceService, metrics_service::MetricsService,
+    text_transcript_history_service::TextTranscriptHistoryService,
+    text_transcription_service::TextTranscriptionService,
+};
 use log::error;
-use std::sync::Arc;
+use std::sync::{atomic::Ordering, Arc};
+use tauri::Listener;
 use tauri::{App as TauriApp, Manager};
-use tauri::{Listener, LogicalPosition};
+use tauri_plugin_notification::NotificationExt;
 use tauri_plugin_store::StoreExt;
 
 const SETTINGS_FILE: &str = "settings.json";
 
 pub fn setup_app(app: &TauriApp, state: Arc<AppState>) -> Result<(), AppError> {
+    if state.safe_mode {
+        return setup_safe_mode(app, state);
+    }
+
     setup_settings(app, &state)?;
 
     initialize_audio_pipeline(app, &state)?;
 
+    download_missing_model(app, &state);
+
     configure_windows(app)?;
 
     setup_shortcuts(app, &state)?;
@@ -28,6 +40,16 @@ pub fn setup_app(app: &TauriApp, state: Arc<AppState>) -> Result<(), AppError> {
 
     setup_event_listeners(app, state.clone())?;
 
+    check_input_device(app, &state);
+
+    AudioDeviceService::watch_devices(app.handle().clone());
+
+    TextTranscriptHistoryService::watch_retention(app.handle().clone());
+
+    MetricsService::watch_flush(app.handle().clone());
+
+    Arc::clone(&state.model_comparison).watch_expiry();
+
     check_onboarding_status(app, state.clone())?;
 
     let handle = app.handle().clone();
@@ -38,14 +60,68 @@ pub fn setup_app(app: &TauriApp, state: Arc<AppState>) -> Result<(), AppError> {
     Ok(())
 }
 
+/// Recording fails without a microphone, so say so up front rather than when
+/// the shortcut is first pressed.
+fn check_input_device(app: &TauriApp, state: &AppState) {
+    if AudioDeviceService::new().has_input_device() {
+        return;
+    }
+
+    log::warn!("No audio input device found, recording is unavailable");
+    state.input_available.store(false, Ordering::SeqCst);
+    SystemTrayManager::refresh_menu(app.handle(), state);
+
+    app.notification()
+        .builder()
+        .title("Rune")
+        .body("No microphone was found. Connect one to start recording.")
+        .show()
+        .unwrap_or_else(|e| log::error!("Failed to show notification: {}", e));
+}
+
+/// Brings up only what is needed to reach the settings window. Nothing here
+/// touches the audio devices or the model, and unreadable settings fall back to
+/// defaults in memory without overwriting the stored file.
+fn setup_safe_mode(app: &TauriApp, state: Arc<AppState>) -> Result<(), AppError> {
+    log::warn!("Starting in safe mode: audio pipeline, model and shortcuts are disabled");
+
+    let settings = app
+        .store(SETTINGS_FILE)
+        .ok()
+        .and_then(|store| store.get("settings"))
+        .map(Settings::migrate)
+        .unwrap_or_default();
+
+    *state.settings.write() = settings;
+
+    configure_windows(app)?;
+    setup_system_tray(app, state.clone())?;
+    setup_event_listeners(app, state)?;
+
+    if let Some(settings_window) = app.get_webview_window("settings") {
+        settings_window.show()?;
+        settings_window.set_focus()?;
+    } else {
+        error!("Window not found: settings");
+    }
+
+    Ok(())
+}
+
 fn setup_settings(app: &TauriApp, state: &Arc<AppState>) -> Result<(), AppError> {
     let store = app
         .store(SETTINGS_FILE)
         .map_err(|e| AppError::Config(format!("Failed to create store: {}", e).into()))?;
 
     let settings = if let Some(stored_settings) = store.get("settings") {
-        serde_json::from_value(stored_settings)
-            .map_err(|e| AppError::Config(format!("Failed to parse settings: {}", e).into()))?
+        let settings = Settings::migrate(stored_settings.clone());
+        if serde_json::json!(settings) != stored_settings {
+            store.set("settings", serde_json::json!(settings.clone()));
+            store.save().map_err(|e| {
+                AppError::Config(format!("Failed to persist settings: {}", e).into())
+            })?;
+        }
+        settings
     } else {
         let default_settings = Settings::default();
         store.set("settings", serde_json::json!(default_settings.clone()));
@@ -60,23 +136,19 @@ fn setup_settings(app: &TauriApp, state: &Arc<AppState>) -> Result<(), AppError>
         *state_settings = settings.clone();
     }
 
+    *state.metrics.lock() = MetricsService::load(app.app_handle());
+
     state.init_state_machine(app.app_handle().clone());
 
-    state.init_llm_client();
+    // The error is kept on the state for the settings window
+    let _ = state.init_llm_client();
 
     Ok(())
 }
 
 fn configure_windows(app: &TauriApp) -> Result<(), AppError> {
-    let monitor = app.primary_monitor().unwrap().unwrap();
-    let scale_factor = monitor.scale_factor();
-    let monitor_size = monitor.size();
-
-    let x_pos = ((monitor_size.width as f64 / scale_factor) / 2.0) - (150.0 / 2.0);
-    let y_pos = (monitor_size.height as f64 / scale_factor) - (40.0 + 80.0);
-
     if let Some(main_window) = app.get_webview_window("main") {
-        main_window.set_position(LogicalPosition::new(x_pos, y_pos))?;
+        WindowManager::position_on_active_monitor(&main_window)?;
 
         WindowManager::remove_titlebar_and_traffic_lights(main_window)?;
     } else {
@@ -144,12 +216,49 @@ fn initialize_audio_pipeline(app: &TauriApp, state: &Arc<AppState>) -> Result<()
         app_handle.clone(),
     ));
 
+    // The first inference is slow until candle's kernels are set up
+    let transcription_service = Arc::clone(&audio_pipeline.transcription_service);
+    std::thread::spawn(move || transcription_service.warmup());
+
     *state.audio_pipeline.lock() = Some(audio_pipeline);
 
     Ok(())
 }
 
+/// Installers don't carry every model, so the selected one is fetched on
+/// first launch. The pipeline reloads once it has arrived.
+fn download_missing_model(app: &TauriApp, state: &Arc<AppState>) {
+    let (whisper_model, custom_model, enabled) = {
+        let settings = state.settings.read();
+        (
+            settings.transcription.whisper_model,
+            settings.transcription.active_custom_model.clone(),
+            settings.transcription.download_models,
+        )
+    };
+    if !enabled || custom_model.is_some() {
+        return;
+    }
+
+    if TextTranscriptionService::find_model_dir(app.handle(), whisper_model).is_none() {
+        log::info!("{:?} not found locally", whisper_model);
+        state
+            .model_downloads
+            .start(app.handle().clone(), whisper_model);
+    }
+}
+
 fn setup_event_listeners(app: &TauriApp, state: Arc<AppState>) -> Result<(), AppError> {
+    // Status is emitted from several places, so it is tracked where it lands
+    let state_clone = state.clone();
+    app.listen_any(
+        "audio-processing-status",
+        move |event| match serde_json::from_str::<String>(event.payload()) {
+            Ok(status) => *state_clone.processing_status.lock() = status,
+            Err(e) => log::warn!("Unexpected processing status payload: {}", e),
+        },
+    );
+
     let state_clone = state.clone();
     app.listen("cancel-recording", move |_| {
         log::info!("Cancel event received");
@@ -1,32 +1,87 @@
-use crate::core::state_machine::{AppCommand, StateMachine};
+use crate::core::state_machine::{AppCommand, AppStateType, StateMachine};
 use crate::{
-    controllers::audio_pipleine_controller::AudioPipelineController, core::config::Settings,
+    controllers::audio_pipleine_controller::{AudioPipelineController, ProcessingStatus},
+    core::{
+        config::{LLMProviderConfig, Settings},
+        error::ConfigError,
+    },
+    services::{
+        correction_feedback_service::CorrectionFeedback,
+        dictation_merge_service::DictationMergeService, level_monitor_service::LevelMonitorService,
+        llm_cache_service::LlmCacheService, metrics_service::UsageMetrics,
+        model_comparison_service::ModelComparisonService,
+        model_download_service::ModelDownloadService, text_processing_service::LastInjection,
+        text_transcription_service::SharedTranscriber,
+    },
 };
 use parking_lot::{Mutex, RwLock};
-use rune_llm::{LLMClient, LLMProvider};
-use std::sync::Arc;
+use rune_llm::{LLMClient, LLMProvider, Message};
+use std::{
+    collections::VecDeque,
+    sync::{atomic::AtomicBool, Arc},
+};
 use tauri::AppHandle;
 use tokio::runtime::Runtime;
 
+const RUNE_API_URL_ENV: &str = "RUNE_API_BASE_URL";
+
 pub struct AppState {
     pub settings: Arc<RwLock<Settings>>,
     pub llm: Arc<Mutex<Option<LLMClient>>>,
+    /// Why the configured LLM provider couldn't be set up, for the settings
+    /// window. `llm` stays `None` meanwhile.
+    pub llm_error: Arc<Mutex<Option<String>>>,
+    /// Recent dictations and their processed text, oldest first, sent as
+    /// context when `llm.history_turns` is set.
+    pub llm_history: Arc<Mutex<VecDeque<Message>>>,
     pub audio_pipeline: Arc<Mutex<Option<Arc<AudioPipelineController>>>>,
+    pub transcriber: Arc<Mutex<Option<SharedTranscriber>>>,
     pub runtime: Runtime,
     pub state_machine: Arc<Mutex<Option<Arc<StateMachine>>>>,
+    pub feedback_log: Arc<Mutex<VecDeque<CorrectionFeedback>>>,
+    pub model_comparison: Arc<ModelComparisonService>,
+    pub metrics: Arc<Mutex<UsageMetrics>>,
+    pub dictation_merge: Arc<DictationMergeService>,
+    pub llm_cache: Arc<LlmCacheService>,
+    pub level_monitor: Arc<LevelMonitorService>,
+    pub model_downloads: Arc<ModelDownloadService>,
+    /// Cleared while no microphone is connected, which disables starting a
+    /// recording from the tray.
+    pub input_available: Arc<AtomicBool>,
+    /// Cleared once undone, so the same text can't be removed twice.
+    pub last_injection: Arc<Mutex<Option<LastInjection>>>,
+    /// The last `audio-processing-status` sent to the main window.
+    pub processing_status: Arc<Mutex<String>>,
+    /// Started with the audio pipeline, model and shortcuts disabled so a bad
+    /// configuration can be fixed from the settings window.
+    pub safe_mode: bool,
 }
 
 impl AppState {
-    pub fn new(settings: Settings) -> Self {
+    pub fn new(settings: Settings, safe_mode: bool) -> Self {
         // Create a multi-threaded runtime for async operations
         let runtime = Runtime::new().expect("Failed to create Tokio runtime");
 
         Self {
             settings: Arc::new(RwLock::new(settings)),
             llm: Arc::new(Mutex::new(None)), // Initialize as None
+            llm_error: Arc::new(Mutex::new(None)),
+            llm_history: Arc::new(Mutex::new(VecDeque::new())),
             audio_pipeline: Arc::new(Mutex::new(None)),
+            transcriber: Arc::new(Mutex::new(None)),
             runtime,
             state_machine: Arc::new(Mutex::new(None)),
+            feedback_log: Arc::new(Mutex::new(VecDeque::new())),
+            model_comparison: Arc::new(ModelComparisonService::new()),
+            metrics: Arc::new(Mutex::new(UsageMetrics::default())),
+            dictation_merge: Arc::new(DictationMergeService::new()),
+            llm_cache: Arc::new(LlmCacheService::new()),
+            level_monitor: Arc::new(LevelMonitorService::new()),
+            model_downloads: Arc::new(ModelDownloadService::new()),
+            input_available: Arc::new(AtomicBool::new(true)),
+            last_injection: Arc::new(Mutex::new(None)),
+            processing_status: Arc::new(Mutex::new(ProcessingStatus::Idle.as_str().to_string())),
+            safe_mode,
         }
     }
 
@@ -35,11 +90,64 @@ impl AppState {
         *self.state_machine.lock() = Some(machine);
     }
 
-    pub fn init_llm_client(&self) {
-        // We don't need the API key anymore as we're using the Rune API service
-        let llm_client = LLMClient::new(LLMProvider::RuneAPI, None);
+    /// Rebuilds the LLM client from settings. A provider that can't be set up,
+    /// such as a Rune engine with an invalid base URL, leaves the client unset
+    /// rather than sending dictations somewhere the user didn't choose.
+    pub fn init_llm_client(&self) -> Result<(), String> {
+        let llm_config = self.settings.read().llm.clone();
+        let provider = match llm_config.provider {
+            LLMProviderConfig::Rune => {
+                // The environment takes precedence so developers can target a local engine
+                // without touching their saved settings
+                let base_url = std::env::var(RUNE_API_URL_ENV)
+                    .ok()
+                    .filter(|url| !url.trim().is_empty())
+                    .or(llm_config.base_url);
+
+                if let Some(url) = &base_url {
+                    log::info!("Using Rune API base URL override: {}", url);
+                }
+                LLMProvider::RuneAPI { base_url }
+            }
+            LLMProviderConfig::OpenAi { api_key, model } => {
+                log::info!("Using OpenAI model {}", model);
+                LLMProvider::OpenAI { api_key, model }
+            }
+            LLMProviderConfig::Anthropic { api_key, model } => {
+                log::info!("Using Anthropic model {}", model);
+                LLMProvider::Anthropic { api_key, model }
+            }
+            LLMProviderConfig::Ollama { base_url, model } => {
+                log::info!("Using Ollama model {} at {:?}", model, base_url);
+                LLMProvider::Ollama { base_url, model }
+            }
+        };
+
+        let result = LLMClient::new(provider, None).map_err(|e| {
+            log::error!("Failed to set up the LLM provider: {:#}", e);
+            format!("{:#}", e)
+        });
+
+        // Another model would phrase things differently
+        self.llm_cache.clear();
+        match result {
+            Ok(client) => {
+                *self.llm.lock() = Some(client);
+                *self.llm_error.lock() = None;
+                Ok(())
+            }
+            Err(e) => {
+                *self.llm.lock() = None;
+                *self.llm_error.lock() = Some(e.clone());
+                Err(e)
+            }
+        }
+    }
 
-        *self.llm.lock() = Some(llm_client);
+    /// What the pipeline is doing right now, in the same terms as the
+    /// `audio-processing-status` event, for windows opened mid-dictation.
+    pub fn processing_status(&self) -> String {
+        self.processing_status.lock().clone()
     }
 
     pub fn cancel_current_operation(&self) {
@@ -47,6 +155,29 @@ impl AppState {
             machine.send_command(AppCommand::Cancel);
         }
     }
+
+    /// Muting also cancels a recording in progress, so nothing more is
+    /// captured from the moment it is switched on.
+    pub fn set_mic_muted(&self, app_handle: &AppHandle, muted: bool) -> Result<(), ConfigError> {
+        self.settings.write().update_mic_muted(app_handle, muted)?;
+        log::info!("Microphone {}", if muted { "muted" } else { "unmuted" });
+
+        if let Some(pipeline) = self.audio_pipeline.lock().clone() {
+            pipeline.get_recording_service().set_muted(muted);
+        }
+
+        let recording = self.state_machine.lock().as_ref().is_some_and(|machine| {
+            matches!(
+                machine.get_state(),
+                AppStateType::Recording | AppStateType::Paused
+            )
+        });
+        if muted && recording {
+            self.cancel_current_operation();
+        }
+
+        Ok(())
+    }
 }
 
 impl AppState {
@@ -1,19 +1,67 @@
 use crate::core::error::ConfigError;
+use rune_llm::PromptOptions;
+use rune_whisper_local::{decoder::DecodingStrategy, model::WhichModel};
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use serde_json::{json, Map, Value};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
 use tauri::AppHandle;
+use tauri_plugin_global_shortcut::{Code, Modifiers};
 use tauri_plugin_store::StoreExt;
 
 const SETTINGS_FILE: &str = "settings.json";
 
+/// Sections every version of the settings has had, which `import` looks for
+/// to tell an export from any other JSON file.
+const EXPORTED_SECTIONS: &[&str] = &["shortcuts", "audio", "window"];
+
+/// Bumped whenever stored settings need more than new defaults to load; add
+/// a step to `MIGRATIONS` alongside.
+pub const SETTINGS_VERSION: u32 = 1;
+
+/// `MIGRATIONS[n]` upgrades stored settings from version `n` to `n + 1`.
+const MIGRATIONS: &[fn(&mut Map<String, Value>)] = &[normalize_record_modifier];
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Settings {
+    /// Schema version the settings were stored with. Missing means they
+    /// predate versioning.
+    #[serde(default)]
+    pub version: u32,
     pub shortcuts: ShortcutConfig,
     pub audio: AudioConfig,
     pub window: WindowConfig,
     #[serde(default)]
     pub user_profile: UserProfile,
     pub onboarding_status: Option<String>,
+    #[serde(default)]
+    pub feedback: FeedbackConfig,
+    #[serde(default)]
+    pub llm: LLMConfig,
+    #[serde(default)]
+    pub launch_at_login: bool,
+    #[serde(default)]
+    pub transcription: TranscriptionConfig,
+    #[serde(default)]
+    pub chunking: ChunkingConfig,
+    #[serde(default)]
+    pub merge: MergeConfig,
+    #[serde(default)]
+    pub injection_method: InjectionMethod,
+    /// Used for apps without a profile of their own.
+    #[serde(default)]
+    pub processing_mode: ProcessingMode,
+    /// Keyed by the frontmost app's name; apps without one follow
+    /// `processing_mode`.
+    #[serde(default)]
+    pub app_profiles: HashMap<String, AppProfile>,
+    #[serde(default)]
+    pub history: HistoryConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -26,19 +74,32 @@ pub struct UserProfile {
 impl Default for Settings {
     fn default() -> Self {
         Self {
+            version: SETTINGS_VERSION,
             shortcuts: ShortcutConfig {
                 record_key: Some("Space".to_string()),
                 record_modifier: Some("CONTROL".to_string()),
+                tap_threshold_ms: default_tap_threshold_ms(),
+                recording_mode: RecordingMode::default(),
+                cancel_key: default_cancel_key(),
+                cancel_modifier: None,
             },
-            audio: AudioConfig {
-                default_device: None,
-            },
+            audio: AudioConfig::default(),
             window: WindowConfig {
                 width: 400.0,
                 height: 80.0,
             },
             user_profile: UserProfile::default(),
             onboarding_status: None,
+            feedback: FeedbackConfig::default(),
+            llm: LLMConfig::default(),
+            launch_at_login: false,
+            transcription: TranscriptionConfig::default(),
+            chunking: ChunkingConfig::default(),
+            merge: MergeConfig::default(),
+            injection_method: InjectionMethod::default(),
+            processing_mode: ProcessingMode::default(),
+            app_profiles: HashMap::new(),
+            history: HistoryConfig::default(),
         }
     }
 }
@@ -49,8 +110,36 @@ pub struct ShortcutConfig {
     pub record_modifier: Option<String>,
     #[serde(default = "default_record_key")]
     pub record_key: Option<String>,
+    /// Presses shorter than this toggle recording on until the next press;
+    /// longer presses record only while the shortcut is held.
+    #[serde(default = "default_tap_threshold_ms")]
+    pub tap_threshold_ms: u64,
+    #[serde(default)]
+    pub recording_mode: RecordingMode,
+    /// Cancels the dictation in progress. Only registered while one is, so
+    /// it doesn't take the key away from other apps. `None` disables it.
+    #[serde(default = "default_cancel_key")]
+    pub cancel_key: Option<String>,
+    #[serde(default)]
+    pub cancel_modifier: Option<String>,
+}
+
+/// How presses of the record shortcut start and stop a recording.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordingMode {
+    /// A tap toggles, a hold records until release; see `tap_threshold_ms`.
+    #[default]
+    TapOrHold,
+    /// Records only while the shortcut is held.
+    PushToTalk,
+    /// Each press starts or stops, however long it is held.
+    Toggle,
 }
 
+pub const MIN_TAP_THRESHOLD_MS: u64 = 100;
+pub const MAX_TAP_THRESHOLD_MS: u64 = 2000;
+
 fn default_record_modifier() -> Option<String> {
     Some("CONTROL".to_string())
 }
@@ -59,11 +148,137 @@ fn default_record_key() -> Option<String> {
     Some("Space".to_string())
 }
 
+fn default_cancel_key() -> Option<String> {
+    Some("Escape".to_string())
+}
+
+fn default_tap_threshold_ms() -> u64 {
+    300
+}
+
 impl Default for ShortcutConfig {
     fn default() -> Self {
         Self {
             record_modifier: Some("CONTROL".to_string()),
             record_key: Some("Space".to_string()),
+            tap_threshold_ms: default_tap_threshold_ms(),
+            recording_mode: RecordingMode::default(),
+            cancel_key: default_cancel_key(),
+            cancel_modifier: None,
+        }
+    }
+}
+
+/// Names accepted for the record shortcut, for the settings UI to offer.
+#[derive(Debug, Clone, Serialize)]
+pub struct ShortcutOptions {
+    pub modifiers: Vec<String>,
+    /// Common keys; any other key code the shortcut plugin knows is accepted too.
+    pub keys: Vec<String>,
+}
+
+const NAMED_KEYS: &[&str] = &[
+    "Space",
+    "Enter",
+    "Tab",
+    "Escape",
+    "Backspace",
+    "Delete",
+    "Insert",
+    "Home",
+    "End",
+    "PageUp",
+    "PageDown",
+    "ArrowUp",
+    "ArrowDown",
+    "ArrowLeft",
+    "ArrowRight",
+    "Minus",
+    "Equal",
+    "BracketLeft",
+    "BracketRight",
+    "Backslash",
+    "Semicolon",
+    "Quote",
+    "Backquote",
+    "Comma",
+    "Period",
+    "Slash",
+];
+
+impl ShortcutConfig {
+    /// Checks the record shortcut parses the same way `ShortcutManager` will
+    /// parse it when registering.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let modifier = self
+            .record_modifier
+            .as_deref()
+            .ok_or_else(|| ConfigError::Invalid("Record modifier not set".to_string()))?;
+        if Modifiers::from_name(modifier).is_none() {
+            return Err(ConfigError::Invalid(format!(
+                "Unknown modifier '{}', expected one of: {}",
+                modifier,
+                Self::options().modifiers.join(", ")
+            )));
+        }
+
+        let key = self
+            .record_key
+            .as_deref()
+            .ok_or_else(|| ConfigError::Invalid("Record key not set".to_string()))?;
+        Code::from_str(key).map_err(|_| {
+            ConfigError::Invalid(format!(
+                "Unknown key '{}', expected a key code such as KeyR, Digit1, F5 or Space",
+                key
+            ))
+        })?;
+
+        self.validate_cancel(modifier, key)
+    }
+
+    fn validate_cancel(&self, record_modifier: &str, record_key: &str) -> Result<(), ConfigError> {
+        let Some(key) = self.cancel_key.as_deref() else {
+            return Ok(());
+        };
+
+        if let Some(modifier) = self.cancel_modifier.as_deref() {
+            if Modifiers::from_name(modifier).is_none() {
+                return Err(ConfigError::Invalid(format!(
+                    "Unknown cancel modifier '{}', expected one of: {}",
+                    modifier,
+                    Self::options().modifiers.join(", ")
+                )));
+            }
+        }
+        Code::from_str(key)
+            .map_err(|_| ConfigError::Invalid(format!("Unknown cancel key '{}'", key)))?;
+
+        if key == record_key && self.cancel_modifier.as_deref() == Some(record_modifier) {
+            return Err(ConfigError::Invalid(
+                "The cancel shortcut must differ from the record shortcut".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn options() -> ShortcutOptions {
+        let letters = ('A'..='Z').map(|c| format!("Key{}", c));
+        let digits = (0..=9).map(|d| format!("Digit{}", d));
+        let functions = (1..=12).map(|f| format!("F{}", f));
+        let named = NAMED_KEYS.iter().map(|key| key.to_string());
+
+        ShortcutOptions {
+            modifiers: Modifiers::all()
+                .iter_names()
+                .map(|(name, _)| name.to_string())
+                .collect(),
+            keys: letters
+                .chain(digits)
+                .chain(functions)
+                .chain(named)
+                .filter(|key| Code::from_str(key).is_ok())
+                .collect(),
         }
     }
 }
@@ -71,6 +286,258 @@ impl Default for ShortcutConfig {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AudioConfig {
     pub default_device: Option<String>,
+    /// Upper bound for audio held in memory while recording. `None` disables the cap.
+    #[serde(default = "default_max_buffer_mb")]
+    pub max_buffer_mb: Option<u64>,
+    #[serde(default)]
+    pub buffer_overflow_policy: BufferOverflowPolicy,
+    /// Stops the recording after this much continuous silence. `None` keeps
+    /// recording until the shortcut is released.
+    #[serde(default)]
+    pub silence_timeout_ms: Option<u64>,
+    /// Peak level (0.0–1.0) below which input counts as silence.
+    #[serde(default = "default_silence_threshold")]
+    pub silence_threshold: f32,
+    /// Recordings shorter than this are dropped without being transcribed.
+    #[serde(default = "default_min_recording_ms")]
+    pub min_recording_ms: u64,
+    #[serde(default)]
+    pub level_mode: LevelMode,
+    /// Multiplier applied to recorded samples; raise it for quiet microphones.
+    #[serde(default = "default_input_gain")]
+    pub input_gain: f32,
+    #[serde(default)]
+    pub normalization: NormalizationMode,
+    /// Attenuates background noise (fans, typing) between words before
+    /// the recording is transcribed.
+    #[serde(default)]
+    pub noise_reduction: NoiseReduction,
+    /// Keeps each dictation's audio alongside its history entry so it can be
+    /// played back. Off by default, as recordings can be large and personal.
+    #[serde(default)]
+    pub keep_recordings: bool,
+    /// Format recordings are kept in. Transcription always reads a WAV.
+    #[serde(default)]
+    pub output_format: AudioFormat,
+    /// No audio is captured while set, even if the shortcut is pressed.
+    #[serde(default)]
+    pub muted: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum NoiseReduction {
+    #[default]
+    Off,
+    /// Softens steady noise between words.
+    Light,
+    /// Nearly silences everything that isn't clearly above the noise floor.
+    Aggressive,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioFormat {
+    #[default]
+    Wav,
+    Flac,
+    Mp3,
+}
+
+impl AudioFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AudioFormat::Wav => "wav",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Mp3 => "mp3",
+        }
+    }
+}
+
+/// Level adjustment applied when a recording is saved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum NormalizationMode {
+    /// Apply `input_gain` as is.
+    #[default]
+    Off,
+    /// Scale the recording so its loudest sample sits just below full scale,
+    /// ignoring `input_gain`.
+    Peak,
+}
+
+/// How the levels sent to the visualizer are measured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LevelMode {
+    /// Loudest sample per band. Reacts instantly but jumps on transients.
+    #[default]
+    Peak,
+    /// Root mean square per band, a smoother measure of speech loudness.
+    Rms,
+}
+
+/// What the recorder does once `max_buffer_mb` is exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BufferOverflowPolicy {
+    /// Move buffered samples to a temp file and keep recording. Spilling
+    /// starts at half the cap so the disk has time to keep up; if it falls a
+    /// full cap behind, the recording stops.
+    #[default]
+    SpillToDisk,
+    /// Stop the recording and process what was captured so far.
+    AutoStop,
+}
+
+fn default_max_buffer_mb() -> Option<u64> {
+    Some(256)
+}
+
+fn default_input_gain() -> f32 {
+    0.9
+}
+
+pub const MAX_INPUT_GAIN: f32 = 10.0;
+
+fn default_silence_threshold() -> f32 {
+    0.02
+}
+
+pub const MIN_SILENCE_TIMEOUT_MS: u64 = 500;
+
+fn default_min_recording_ms() -> u64 {
+    300
+}
+
+pub const MAX_MIN_RECORDING_MS: u64 = 10_000;
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self {
+            default_device: None,
+            max_buffer_mb: default_max_buffer_mb(),
+            buffer_overflow_policy: BufferOverflowPolicy::default(),
+            silence_timeout_ms: None,
+            silence_threshold: default_silence_threshold(),
+            min_recording_ms: default_min_recording_ms(),
+            level_mode: LevelMode::default(),
+            input_gain: default_input_gain(),
+            normalization: NormalizationMode::default(),
+            noise_reduction: NoiseReduction::default(),
+            keep_recordings: false,
+            output_format: AudioFormat::default(),
+            muted: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptionConfig {
+    /// Beam width for decoding. `None` or 1 decodes greedily, which is fastest.
+    #[serde(default)]
+    pub beam_size: Option<usize>,
+    /// Number of model instances kept loaded so transcriptions can run in
+    /// parallel. Each instance holds its own copy of the weights.
+    #[serde(default = "default_model_pool_size")]
+    pub model_pool_size: usize,
+    /// Size of the stock model to load. Larger ones are more accurate but
+    /// slower; an active custom model takes precedence.
+    #[serde(default = "default_whisper_model")]
+    pub whisper_model: WhichModel,
+    /// Fetch the stock model from Hugging Face when it isn't on disk.
+    #[serde(default = "default_download_models")]
+    pub download_models: bool,
+    #[serde(default)]
+    pub custom_models: Vec<CustomModel>,
+    /// Name of the custom model to load instead of the bundled one.
+    #[serde(default)]
+    pub active_custom_model: Option<String>,
+    /// Transcriptions whose average token log-probability falls below this
+    /// are discarded instead of being processed and injected. `None` keeps
+    /// everything.
+    #[serde(default)]
+    pub min_confidence: Option<f32>,
+    /// Vocabulary shown to the model before each recording, e.g. product
+    /// names it should spell a particular way.
+    #[serde(default)]
+    pub initial_prompt: Option<String>,
+    /// Skip long pauses in a recording instead of decoding them.
+    #[serde(default)]
+    pub vad_enabled: bool,
+    /// How long a stopped recording may take to transcribe and process
+    /// before the pipeline is assumed stuck and reset.
+    #[serde(default = "default_reset_timeout_secs")]
+    pub reset_timeout_secs: u64,
+}
+
+/// Only the last ~220 tokens of the prompt reach the model; this keeps the
+/// stored text in the same ballpark.
+pub const MAX_INITIAL_PROMPT_CHARS: usize = 1000;
+
+/// A user-supplied whisper checkpoint registered with `import_custom_model`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomModel {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+fn default_model_pool_size() -> usize {
+    1
+}
+
+/// Every instance loads its own copy of the weights, so more than a few can
+/// exhaust memory before the app finishes starting.
+pub const MAX_MODEL_POOL_SIZE: usize = 4;
+
+fn default_whisper_model() -> WhichModel {
+    WhichModel::Base
+}
+
+fn default_download_models() -> bool {
+    true
+}
+
+fn default_reset_timeout_secs() -> u64 {
+    300
+}
+
+pub const MIN_RESET_TIMEOUT_SECS: u64 = 5;
+pub const MAX_RESET_TIMEOUT_SECS: u64 = 3600;
+
+impl Default for TranscriptionConfig {
+    fn default() -> Self {
+        Self {
+            beam_size: None,
+            model_pool_size: default_model_pool_size(),
+            whisper_model: default_whisper_model(),
+            download_models: default_download_models(),
+            custom_models: Vec::new(),
+            active_custom_model: None,
+            min_confidence: None,
+            initial_prompt: None,
+            vad_enabled: false,
+            reset_timeout_secs: default_reset_timeout_secs(),
+        }
+    }
+}
+
+impl TranscriptionConfig {
+    pub fn reset_timeout(&self) -> Duration {
+        Duration::from_secs(self.reset_timeout_secs)
+    }
+
+    pub fn decoding_strategy(&self) -> DecodingStrategy {
+        DecodingStrategy::from_beam_size(self.beam_size)
+    }
+
+    pub fn active_custom_model_dir(&self) -> Option<PathBuf> {
+        let name = self.active_custom_model.as_ref()?;
+        self.custom_models
+            .iter()
+            .find(|model| &model.name == name)
+            .map(|model| model.path.clone())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -79,68 +546,1220 @@ pub struct WindowConfig {
     pub height: f64,
 }
 
-impl Settings {
-    pub fn load(app_handle: &AppHandle) -> Result<Self, ConfigError> {
-        let store = app_handle
-            .store(SETTINGS_FILE)
-            .map_err(|e| ConfigError::Loading(e.to_string()))?;
+/// Opt-in reporting of user corrections to injected text. Only the injected
+/// span and its edited replacement are ever sent, never audio or surrounding text.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedbackConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_feedback_endpoint")]
+    pub endpoint: String,
+    #[serde(default = "default_correction_window_secs")]
+    pub correction_window_secs: u64,
+    /// Also sends the name of the app the correction was made in.
+    #[serde(default)]
+    pub include_app_name: bool,
+}
 
-        log::info!("Loading settings from store...");
+fn default_feedback_endpoint() -> String {
+    "https://api.runeapp.ai/engine/v1/feedback/corrections".to_string()
+}
 
-        if let Some(settings) = store.get("settings") {
-            log::info!("Found existing settings: {:?}", settings);
+fn default_correction_window_secs() -> u64 {
+    30
+}
 
-            let settings: Settings = serde_json::from_value(settings)
-                .map_err(|e| ConfigError::Loading(format!("Failed to parse settings: {}", e)))?;
-            return Ok(settings);
+impl Default for FeedbackConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: default_feedback_endpoint(),
+            correction_window_secs: default_correction_window_secs(),
+            include_app_name: false,
         }
+    }
+}
 
-        log::info!("No existing settings found, creating defaults...");
-        let default_settings = Self::default();
-        log::info!("Default settings: {:?}", default_settings);
+/// Which backend runs the LLM steps. API keys are stored with the rest of the
+/// settings, but never shown by `Debug`, which ends up in the logs.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LLMProviderConfig {
+    /// The Rune engine at `LLMConfig::base_url`.
+    #[default]
+    Rune,
+    OpenAi {
+        api_key: String,
+        model: String,
+    },
+    Anthropic {
+        api_key: String,
+        model: String,
+    },
+    Ollama {
+        #[serde(default)]
+        base_url: Option<String>,
+        model: String,
+    },
+}
 
-        store.set("settings", json!(default_settings.clone()));
+impl LLMProviderConfig {
+    /// Blanks the API key, if the provider has one.
+    pub fn clear_api_key(&mut self) {
+        match self {
+            LLMProviderConfig::OpenAi { api_key, .. }
+            | LLMProviderConfig::Anthropic { api_key, .. } => api_key.clear(),
+            LLMProviderConfig::Rune | LLMProviderConfig::Ollama { .. } => {}
+        }
+    }
 
-        store
-            .save()
-            .map_err(|e| ConfigError::Loading(format!("Failed to persist settings: {}", e)))?;
+    /// Fills in a blank API key from `current` when both are the same provider,
+    /// so importing an export without secrets keeps the key already set here.
+    pub fn restore_api_key(&mut self, current: &Self) {
+        match (self, current) {
+            (
+                LLMProviderConfig::OpenAi { api_key, .. },
+                LLMProviderConfig::OpenAi { api_key: kept, .. },
+            )
+            | (
+                LLMProviderConfig::Anthropic { api_key, .. },
+                LLMProviderConfig::Anthropic { api_key: kept, .. },
+            ) if api_key.is_empty() => api_key.clone_from(kept),
+            _ => {}
+        }
+    }
+}
 
-        Ok(default_settings)
+impl std::fmt::Debug for LLMProviderConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LLMProviderConfig::Rune => f.write_str("Rune"),
+            LLMProviderConfig::OpenAi { model, .. } => f
+                .debug_struct("OpenAi")
+                .field("api_key", &"<redacted>")
+                .field("model", model)
+                .finish(),
+            LLMProviderConfig::Anthropic { model, .. } => f
+                .debug_struct("Anthropic")
+                .field("api_key", &"<redacted>")
+                .field("model", model)
+                .finish(),
+            LLMProviderConfig::Ollama { base_url, model } => f
+                .debug_struct("Ollama")
+                .field("base_url", base_url)
+                .field("model", model)
+                .finish(),
+        }
     }
+}
 
-    pub fn save(&self, app_handle: &AppHandle) -> Result<(), ConfigError> {
-        let store = app_handle
-            .store(SETTINGS_FILE)
-            .map_err(|e| ConfigError::Loading(e.to_string()))?;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LLMConfig {
+    #[serde(default)]
+    pub provider: LLMProviderConfig,
+    /// Base URL of a self-hosted or staging engine. `None` uses the production API.
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// Sampling temperature for transformation and generation. Low values keep
+    /// the output close to what was dictated.
+    #[serde(default = "default_llm_temperature")]
+    pub temperature: f32,
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Earlier dictations, with what they became, sent as context when a
+    /// dictation asks for an action such as "make that shorter". 0 disables.
+    #[serde(default)]
+    pub history_turns: usize,
+    #[serde(default)]
+    pub timeouts: PromptTimeouts,
+    #[serde(default)]
+    pub cache: LlmCacheConfig,
+}
 
-        store.set("settings", json!(self));
+pub const MAX_LLM_HISTORY_TURNS: usize = 10;
 
-        store
-            .save()
-            .map_err(|e| ConfigError::Loading(e.to_string()))
+/// How long each kind of prompt may take per attempt, in seconds. The intent
+/// check is tiny and blocks the whole pipeline, so it fails fast.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptTimeouts {
+    #[serde(default = "default_action_checker_timeout_secs")]
+    pub action_checker_secs: u64,
+    #[serde(default = "default_text_generator_timeout_secs")]
+    pub text_generator_secs: u64,
+    #[serde(default = "default_text_transformer_timeout_secs")]
+    pub text_transformer_secs: u64,
+}
+
+pub const MAX_PROMPT_TIMEOUT_SECS: u64 = 600;
+
+fn default_action_checker_timeout_secs() -> u64 {
+    10
+}
+
+fn default_text_generator_timeout_secs() -> u64 {
+    180
+}
+
+fn default_text_transformer_timeout_secs() -> u64 {
+    60
+}
+
+impl Default for PromptTimeouts {
+    fn default() -> Self {
+        Self {
+            action_checker_secs: default_action_checker_timeout_secs(),
+            text_generator_secs: default_text_generator_timeout_secs(),
+            text_transformer_secs: default_text_transformer_timeout_secs(),
+        }
     }
+}
 
-    pub fn update_shortcuts(
-        &mut self,
-        app_handle: &AppHandle,
-        key: String,
-        modifier: String,
-    ) -> Result<(), ConfigError> {
-        self.shortcuts.record_key = Some(key);
-        self.shortcuts.record_modifier = Some(modifier);
-        self.save(app_handle)
+impl PromptTimeouts {
+    pub fn action_checker(&self) -> Duration {
+        Duration::from_secs(self.action_checker_secs)
     }
 
-    pub fn update_user_profile(
-        &mut self,
-        app_handle: &AppHandle,
-        name: Option<String>,
-        email: Option<String>,
-        about: Option<String>,
-    ) -> Result<(), ConfigError> {
-        self.user_profile.name = name;
-        self.user_profile.email = email;
-        self.user_profile.about = about;
+    pub fn text_generator(&self) -> Duration {
+        Duration::from_secs(self.text_generator_secs)
+    }
+
+    pub fn text_transformer(&self) -> Duration {
+        Duration::from_secs(self.text_transformer_secs)
+    }
+}
+
+/// Reuse of processed text when the same phrase is dictated into the same app
+/// again. Dictations that ask for an action are never cached.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmCacheConfig {
+    #[serde(default = "default_llm_cache_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_llm_cache_max_entries")]
+    pub max_entries: usize,
+    #[serde(default = "default_llm_cache_ttl_secs")]
+    pub ttl_secs: u64,
+}
+
+pub const MAX_LLM_CACHE_ENTRIES: usize = 5000;
+
+fn default_llm_cache_enabled() -> bool {
+    true
+}
+
+fn default_llm_cache_max_entries() -> usize {
+    200
+}
+
+fn default_llm_cache_ttl_secs() -> u64 {
+    24 * 60 * 60
+}
+
+impl Default for LlmCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_llm_cache_enabled(),
+            max_entries: default_llm_cache_max_entries(),
+            ttl_secs: default_llm_cache_ttl_secs(),
+        }
+    }
+}
+
+impl LlmCacheConfig {
+    pub fn ttl(&self) -> Duration {
+        Duration::from_secs(self.ttl_secs)
+    }
+}
+
+fn default_llm_temperature() -> f32 {
+    0.2
+}
+
+impl Default for LLMConfig {
+    fn default() -> Self {
+        Self {
+            provider: LLMProviderConfig::default(),
+            base_url: None,
+            temperature: default_llm_temperature(),
+            seed: None,
+            history_turns: 0,
+            timeouts: PromptTimeouts::default(),
+            cache: LlmCacheConfig::default(),
+        }
+    }
+}
+
+impl LLMConfig {
+    pub fn prompt_options(&self) -> PromptOptions {
+        PromptOptions {
+            temperature: Some(self.temperature),
+            seed: self.seed,
+            ..Default::default()
+        }
+    }
+}
+
+/// Limits for long dictations. Text above these sizes is split on paragraph
+/// and sentence boundaries before it is sent to the LLM or typed out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkingConfig {
+    #[serde(default = "default_llm_chunk_chars")]
+    pub llm_chunk_chars: usize,
+    #[serde(default = "default_injection_chunk_chars")]
+    pub injection_chunk_chars: usize,
+    /// Pause between injected chunks so the target app can keep up.
+    #[serde(default = "default_injection_delay_ms")]
+    pub injection_delay_ms: u64,
+}
+
+pub const MIN_CHUNK_CHARS: usize = 100;
+
+fn default_llm_chunk_chars() -> usize {
+    2000
+}
+
+fn default_injection_chunk_chars() -> usize {
+    500
+}
+
+fn default_injection_delay_ms() -> u64 {
+    50
+}
+
+impl Default for ChunkingConfig {
+    fn default() -> Self {
+        Self {
+            llm_chunk_chars: default_llm_chunk_chars(),
+            injection_chunk_chars: default_injection_chunk_chars(),
+            injection_delay_ms: default_injection_delay_ms(),
+        }
+    }
+}
+
+/// How much dictation history is kept. Entries beyond either limit are
+/// deleted with their audio, at startup and hourly after that. Neither limit
+/// is set until the user picks one.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct HistoryConfig {
+    /// `None` keeps entries however old they are.
+    #[serde(default)]
+    pub retention_days: Option<u32>,
+    /// Only the newest entries are kept. `None` keeps any number.
+    #[serde(default)]
+    pub max_entries: Option<usize>,
+}
+
+/// Joins dictations into the same window that follow each other within
+/// `grace_window_ms` into one running paragraph.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_merge_grace_window_ms")]
+    pub grace_window_ms: u64,
+    #[serde(default)]
+    pub separator: MergeSeparator,
+    /// Run the LLM over the whole paragraph and replace what was already
+    /// injected, instead of processing each dictation on its own.
+    #[serde(default)]
+    pub reprocess_combined: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeSeparator {
+    #[default]
+    Space,
+    Newline,
+}
+
+impl MergeSeparator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MergeSeparator::Space => " ",
+            MergeSeparator::Newline => "\n",
+        }
+    }
+}
+
+/// How text reaches the target app.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum InjectionMethod {
+    /// Types the text as simulated keystrokes.
+    Keystroke,
+    /// Puts the text on the clipboard and pastes it, then puts the previous
+    /// clipboard contents back.
+    ClipboardPaste,
+    /// Pastes text that is long or not plain ASCII, types the rest.
+    #[default]
+    Auto,
+}
+
+/// What the LLM does with a dictation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessingMode {
+    /// Detects whether the dictation is an instruction and either carries it
+    /// out or cleans the text up.
+    #[default]
+    Auto,
+    /// Always cleans the text up, never treats it as an instruction. Skips
+    /// the intent check, saving an LLM round-trip.
+    Transform,
+    /// Always treats the dictation as an instruction to carry out, also
+    /// without the intent check.
+    Generate,
+    /// Injects the transcription as is, without the LLM. Also works while
+    /// the LLM provider is unreachable.
+    Raw,
+}
+
+pub const MAX_PROFILE_PROMPT_CHARS: usize = 2000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct AppProfile {
+    #[serde(default)]
+    pub mode: ProcessingMode,
+    /// Extra instructions for the LLM, such as "keep it terse".
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+}
+
+pub const MAX_MERGE_GRACE_WINDOW_MS: u64 = 60_000;
+
+fn default_merge_grace_window_ms() -> u64 {
+    5000
+}
+
+impl Default for MergeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            grace_window_ms: default_merge_grace_window_ms(),
+            separator: MergeSeparator::default(),
+            reprocess_combined: false,
+        }
+    }
+}
+
+/// Version 0 stored the record modifier unchecked. Maps the spellings people
+/// typed to the names the shortcut plugin accepts.
+fn normalize_record_modifier(settings: &mut Map<String, Value>) {
+    let Some(modifier) = settings
+        .get_mut("shortcuts")
+        .and_then(|shortcuts| shortcuts.get_mut("record_modifier"))
+    else {
+        return;
+    };
+    let Some(name) = modifier.as_str() else {
+        return;
+    };
+
+    let normalized = match name.trim().to_ascii_uppercase().as_str() {
+        "CTRL" => "CONTROL".to_string(),
+        "CMD" | "COMMAND" => "SUPER".to_string(),
+        "OPT" | "OPTION" => "ALT".to_string(),
+        other => other.to_string(),
+    };
+    *modifier = json!(normalized);
+}
+
+/// Adds whatever `defaults` has that `value` lacks, recursing into objects.
+/// Stored nulls are only replaced where the default is an object, so a
+/// cleared optional setting stays cleared.
+fn fill_defaults(value: &mut Value, defaults: &Value) {
+    let (Value::Object(value), Value::Object(defaults)) = (value, defaults) else {
+        return;
+    };
+
+    for (key, default) in defaults {
+        match value.get_mut(key) {
+            Some(existing) if existing.is_null() && default.is_object() => {
+                *existing = default.clone();
+            }
+            Some(existing) => fill_defaults(existing, default),
+            None => {
+                value.insert(key.clone(), default.clone());
+            }
+        }
+    }
+}
+
+/// Checks that a provider's base URL is something requests can be sent to.
+fn validate_base_url(service: &str, base_url: &str) -> Result<(), ConfigError> {
+    let valid = reqwest::Url::parse(base_url.trim())
+        .is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.host_str().is_some());
+    if !valid {
+        return Err(ConfigError::Invalid(format!(
+            "{} base URL must be an http(s) URL with a host: {}",
+            service, base_url
+        )));
+    }
+    Ok(())
+}
+
+impl Settings {
+    /// Upgrades stored settings step by step from whatever version wrote
+    /// them. Missing fields take their defaults, and a section that still
+    /// can't be read is reset on its own rather than losing everything.
+    pub fn migrate(stored: Value) -> Settings {
+        let Value::Object(mut stored) = stored else {
+            log::error!("Stored settings are not an object, using defaults");
+            return Settings::default();
+        };
+
+        let version = stored.get("version").and_then(Value::as_u64).unwrap_or(0) as usize;
+        if version > SETTINGS_VERSION as usize {
+            log::warn!(
+                "Settings were stored by a newer version ({}), reading what is understood",
+                version
+            );
+        }
+        for (from, step) in MIGRATIONS.iter().enumerate().skip(version) {
+            log::info!("Migrating settings from version {} to {}", from, from + 1);
+            step(&mut stored);
+        }
+        stored.insert(
+            "version".to_string(),
+            json!(SETTINGS_VERSION.max(version as u32)),
+        );
+
+        let defaults = json!(Settings::default());
+        let mut value = Value::Object(stored);
+        fill_defaults(&mut value, &defaults);
+
+        let mut settings = match serde_json::from_value(value.clone()) {
+            Ok(settings) => settings,
+            Err(e) => {
+                log::warn!("Stored settings don't fully parse: {}", e);
+                Self::reset_unreadable_sections(value, defaults)
+            }
+        };
+        settings.clamp_limits();
+        settings
+    }
+
+    /// Pulls values the updaters would reject back into range, for settings
+    /// edited by hand or imported from elsewhere.
+    fn clamp_limits(&mut self) {
+        let pool_size = &mut self.transcription.model_pool_size;
+        let clamped = (*pool_size).clamp(1, MAX_MODEL_POOL_SIZE);
+        if clamped != *pool_size {
+            log::warn!(
+                "Model pool size {} is out of range, using {}",
+                pool_size,
+                clamped
+            );
+            *pool_size = clamped;
+        }
+    }
+
+    fn reset_unreadable_sections(mut value: Value, defaults: Value) -> Settings {
+        if let (Value::Object(sections), Value::Object(default_sections)) = (&mut value, &defaults)
+        {
+            for (key, section) in sections.iter_mut() {
+                let Some(default) = default_sections.get(key) else {
+                    continue;
+                };
+
+                let mut candidate = default_sections.clone();
+                candidate.insert(key.clone(), section.clone());
+                if serde_json::from_value::<Settings>(Value::Object(candidate)).is_err() {
+                    log::warn!("Resetting unreadable settings section '{}'", key);
+                    *section = default.clone();
+                }
+            }
+        }
+
+        serde_json::from_value(value).unwrap_or_else(|e| {
+            log::error!("Stored settings are unreadable, using defaults: {}", e);
+            Settings::default()
+        })
+    }
+
+    pub fn load(app_handle: &AppHandle) -> Result<Self, ConfigError> {
+        let store = app_handle
+            .store(SETTINGS_FILE)
+            .map_err(|e| ConfigError::Loading(e.to_string()))?;
+
+        log::info!("Loading settings from store...");
+
+        if let Some(settings) = store.get("settings") {
+            // Logged once parsed: the raw JSON would include the API keys
+            let migrated = Self::migrate(settings.clone());
+            log::info!("Found existing settings: {:?}", migrated);
+            if json!(migrated) != settings {
+                migrated.save(app_handle)?;
+            }
+            return Ok(migrated);
+        }
+
+        log::info!("No existing settings found, creating defaults...");
+        let default_settings = Self::default();
+        log::info!("Default settings: {:?}", default_settings);
+
+        store.set("settings", json!(default_settings.clone()));
+
+        store
+            .save()
+            .map_err(|e| ConfigError::Loading(format!("Failed to persist settings: {}", e)))?;
+
+        Ok(default_settings)
+    }
+
+    pub fn save(&self, app_handle: &AppHandle) -> Result<(), ConfigError> {
+        let store = app_handle
+            .store(SETTINGS_FILE)
+            .map_err(|e| ConfigError::Loading(e.to_string()))?;
+
+        store.set("settings", json!(self));
+
+        store
+            .save()
+            .map_err(|e| ConfigError::Loading(e.to_string()))
+    }
+
+    /// Writes the settings as pretty JSON, for moving them to another machine.
+    /// API keys are left out unless `include_secrets` is set.
+    pub fn export(&self, path: &Path, include_secrets: bool) -> Result<(), ConfigError> {
+        let mut exported = self.clone();
+        if !include_secrets {
+            exported.llm.provider.clear_api_key();
+        }
+
+        let contents = serde_json::to_string_pretty(&exported)
+            .map_err(|e| ConfigError::Invalid(format!("Failed to serialize settings: {}", e)))?;
+
+        fs::write(path, contents)
+            .map_err(|e| ConfigError::Loading(format!("Failed to write {}: {}", path.display(), e)))
+    }
+
+    /// Reads settings exported by `export`, possibly from an older version,
+    /// and stores them in place of `current`, keeping its API key if the
+    /// export left it out. Nothing is stored unless the file has the exported
+    /// sections and its shortcuts are usable.
+    pub fn import(
+        app_handle: &AppHandle,
+        path: &Path,
+        current: &Self,
+    ) -> Result<Self, ConfigError> {
+        let contents = fs::read_to_string(path).map_err(|e| {
+            ConfigError::Loading(format!("Failed to read {}: {}", path.display(), e))
+        })?;
+
+        let value: Value = serde_json::from_str(&contents)
+            .map_err(|e| ConfigError::Invalid(format!("Not a settings file: {}", e)))?;
+        // Any JSON object would migrate to defaults, so insist on the sections
+        // every export carries
+        let is_export = EXPORTED_SECTIONS
+            .iter()
+            .all(|section| value.get(section).is_some_and(Value::is_object));
+        if !is_export {
+            return Err(ConfigError::Invalid("Not a settings file".to_string()));
+        }
+
+        let mut settings = Self::migrate(value);
+        settings.llm.provider.restore_api_key(&current.llm.provider);
+        settings.shortcuts.validate()?;
+        settings.save(app_handle)?;
+
+        Ok(settings)
+    }
+
+    pub fn update_shortcuts(
+        &mut self,
+        app_handle: &AppHandle,
+        key: String,
+        modifier: String,
+    ) -> Result<(), ConfigError> {
+        let shortcuts = ShortcutConfig {
+            record_key: Some(key),
+            record_modifier: Some(modifier),
+            ..self.shortcuts.clone()
+        };
+        shortcuts.validate()?;
+
+        self.shortcuts = shortcuts;
+        self.save(app_handle)
+    }
+
+    pub fn update_cancel_shortcut(
+        &mut self,
+        app_handle: &AppHandle,
+        key: Option<String>,
+        modifier: Option<String>,
+    ) -> Result<(), ConfigError> {
+        let shortcuts = ShortcutConfig {
+            cancel_key: key,
+            cancel_modifier: modifier,
+            ..self.shortcuts.clone()
+        };
+        shortcuts.validate()?;
+
+        self.shortcuts = shortcuts;
+        self.save(app_handle)
+    }
+
+    pub fn update_tap_threshold(
+        &mut self,
+        app_handle: &AppHandle,
+        tap_threshold_ms: u64,
+    ) -> Result<(), ConfigError> {
+        if !(MIN_TAP_THRESHOLD_MS..=MAX_TAP_THRESHOLD_MS).contains(&tap_threshold_ms) {
+            return Err(ConfigError::Invalid(format!(
+                "Tap threshold must be between {} and {} ms",
+                MIN_TAP_THRESHOLD_MS, MAX_TAP_THRESHOLD_MS
+            )));
+        }
+
+        self.shortcuts.tap_threshold_ms = tap_threshold_ms;
+        self.save(app_handle)
+    }
+
+    pub fn update_recording_mode(
+        &mut self,
+        app_handle: &AppHandle,
+        recording_mode: RecordingMode,
+    ) -> Result<(), ConfigError> {
+        self.shortcuts.recording_mode = recording_mode;
+        self.save(app_handle)
+    }
+
+    pub fn update_user_profile(
+        &mut self,
+        app_handle: &AppHandle,
+        name: Option<String>,
+        email: Option<String>,
+        about: Option<String>,
+    ) -> Result<(), ConfigError> {
+        self.user_profile.name = name;
+        self.user_profile.email = email;
+        self.user_profile.about = about;
+        self.save(app_handle)
+    }
+
+    pub fn update_feedback_consent(
+        &mut self,
+        app_handle: &AppHandle,
+        enabled: bool,
+        include_app_name: bool,
+    ) -> Result<(), ConfigError> {
+        self.feedback.enabled = enabled;
+        self.feedback.include_app_name = include_app_name;
+        self.save(app_handle)
+    }
+
+    pub fn update_llm_sampling(
+        &mut self,
+        app_handle: &AppHandle,
+        temperature: f32,
+        seed: Option<u64>,
+    ) -> Result<(), ConfigError> {
+        let options = PromptOptions {
+            temperature: Some(temperature),
+            seed,
+            ..Default::default()
+        };
+        options
+            .validate()
+            .map_err(|e| ConfigError::Invalid(e.to_string()))?;
+
+        self.llm.temperature = temperature;
+        self.llm.seed = seed;
+        self.save(app_handle)
+    }
+
+    pub fn update_llm_provider(
+        &mut self,
+        app_handle: &AppHandle,
+        provider: LLMProviderConfig,
+    ) -> Result<(), ConfigError> {
+        let required = match &provider {
+            LLMProviderConfig::Rune => vec![],
+            LLMProviderConfig::OpenAi { api_key, model }
+            | LLMProviderConfig::Anthropic { api_key, model } => {
+                vec![("API key", api_key), ("model", model)]
+            }
+            LLMProviderConfig::Ollama { model, .. } => vec![("model", model)],
+        };
+        if let Some((field, _)) = required.iter().find(|(_, value)| value.trim().is_empty()) {
+            return Err(ConfigError::Invalid(format!(
+                "The LLM provider's {} must not be empty",
+                field
+            )));
+        }
+        if let LLMProviderConfig::Ollama {
+            base_url: Some(base_url),
+            ..
+        } = &provider
+        {
+            validate_base_url("Ollama", base_url)?;
+        }
+
+        self.llm.provider = provider;
+        self.save(app_handle)
+    }
+
+    /// Points the Rune provider at a self-hosted or staging engine, or back
+    /// at the production API with `None`.
+    pub fn update_llm_base_url(
+        &mut self,
+        app_handle: &AppHandle,
+        base_url: Option<String>,
+    ) -> Result<(), ConfigError> {
+        let base_url = base_url.filter(|url| !url.trim().is_empty());
+        if let Some(url) = &base_url {
+            validate_base_url("Rune API", url)?;
+        }
+
+        self.llm.base_url = base_url;
+        self.save(app_handle)
+    }
+
+    pub fn update_llm_history_turns(
+        &mut self,
+        app_handle: &AppHandle,
+        history_turns: usize,
+    ) -> Result<(), ConfigError> {
+        if history_turns > MAX_LLM_HISTORY_TURNS {
+            return Err(ConfigError::Invalid(format!(
+                "At most {} earlier dictations can be sent as context",
+                MAX_LLM_HISTORY_TURNS
+            )));
+        }
+
+        self.llm.history_turns = history_turns;
+        self.save(app_handle)
+    }
+
+    pub fn update_llm_timeouts(
+        &mut self,
+        app_handle: &AppHandle,
+        timeouts: PromptTimeouts,
+    ) -> Result<(), ConfigError> {
+        let all = [
+            timeouts.action_checker_secs,
+            timeouts.text_generator_secs,
+            timeouts.text_transformer_secs,
+        ];
+        if all
+            .iter()
+            .any(|secs| !(1..=MAX_PROMPT_TIMEOUT_SECS).contains(secs))
+        {
+            return Err(ConfigError::Invalid(format!(
+                "Timeouts must be between 1 and {} seconds",
+                MAX_PROMPT_TIMEOUT_SECS
+            )));
+        }
+
+        self.llm.timeouts = timeouts;
+        self.save(app_handle)
+    }
+
+    pub fn update_llm_cache(
+        &mut self,
+        app_handle: &AppHandle,
+        cache: LlmCacheConfig,
+    ) -> Result<(), ConfigError> {
+        if !(1..=MAX_LLM_CACHE_ENTRIES).contains(&cache.max_entries) {
+            return Err(ConfigError::Invalid(format!(
+                "The cache must hold between 1 and {} entries",
+                MAX_LLM_CACHE_ENTRIES
+            )));
+        }
+        if cache.ttl_secs == 0 {
+            return Err(ConfigError::Invalid(
+                "Cached entries must live at least one second".to_string(),
+            ));
+        }
+
+        self.llm.cache = cache;
+        self.save(app_handle)
+    }
+
+    pub fn update_beam_size(
+        &mut self,
+        app_handle: &AppHandle,
+        beam_size: Option<usize>,
+    ) -> Result<(), ConfigError> {
+        DecodingStrategy::from_beam_size(beam_size)
+            .validate()
+            .map_err(|e| ConfigError::Invalid(e.to_string()))?;
+
+        self.transcription.beam_size = beam_size;
+        self.save(app_handle)
+    }
+
+    pub fn update_reset_timeout(
+        &mut self,
+        app_handle: &AppHandle,
+        reset_timeout_secs: u64,
+    ) -> Result<(), ConfigError> {
+        if !(MIN_RESET_TIMEOUT_SECS..=MAX_RESET_TIMEOUT_SECS).contains(&reset_timeout_secs) {
+            return Err(ConfigError::Invalid(format!(
+                "Reset timeout must be between {} and {} seconds",
+                MIN_RESET_TIMEOUT_SECS, MAX_RESET_TIMEOUT_SECS
+            )));
+        }
+
+        self.transcription.reset_timeout_secs = reset_timeout_secs;
+        self.save(app_handle)
+    }
+
+    pub fn update_min_confidence(
+        &mut self,
+        app_handle: &AppHandle,
+        min_confidence: Option<f32>,
+    ) -> Result<(), ConfigError> {
+        if let Some(min) = min_confidence {
+            if !min.is_finite() || min > 0.0 {
+                return Err(ConfigError::Invalid(
+                    "Minimum confidence must be a log-probability of 0 or below".to_string(),
+                ));
+            }
+        }
+
+        self.transcription.min_confidence = min_confidence;
+        self.save(app_handle)
+    }
+
+    pub fn update_initial_prompt(
+        &mut self,
+        app_handle: &AppHandle,
+        initial_prompt: Option<String>,
+    ) -> Result<(), ConfigError> {
+        let initial_prompt = initial_prompt
+            .map(|prompt| prompt.trim().to_string())
+            .filter(|prompt| !prompt.is_empty());
+
+        if initial_prompt
+            .as_ref()
+            .is_some_and(|prompt| prompt.chars().count() > MAX_INITIAL_PROMPT_CHARS)
+        {
+            return Err(ConfigError::Invalid(format!(
+                "Vocabulary prompt must be at most {} characters",
+                MAX_INITIAL_PROMPT_CHARS
+            )));
+        }
+
+        self.transcription.initial_prompt = initial_prompt;
+        self.save(app_handle)
+    }
+
+    pub fn update_vad_enabled(
+        &mut self,
+        app_handle: &AppHandle,
+        vad_enabled: bool,
+    ) -> Result<(), ConfigError> {
+        self.transcription.vad_enabled = vad_enabled;
+        self.save(app_handle)
+    }
+
+    pub fn update_chunking(
+        &mut self,
+        app_handle: &AppHandle,
+        chunking: ChunkingConfig,
+    ) -> Result<(), ConfigError> {
+        if chunking.llm_chunk_chars < MIN_CHUNK_CHARS
+            || chunking.injection_chunk_chars < MIN_CHUNK_CHARS
+        {
+            return Err(ConfigError::Invalid(format!(
+                "Chunk sizes must be at least {} characters",
+                MIN_CHUNK_CHARS
+            )));
+        }
+
+        self.chunking = chunking;
+        self.save(app_handle)
+    }
+
+    pub fn update_merge(
+        &mut self,
+        app_handle: &AppHandle,
+        merge: MergeConfig,
+    ) -> Result<(), ConfigError> {
+        if merge.grace_window_ms > MAX_MERGE_GRACE_WINDOW_MS {
+            return Err(ConfigError::Invalid(format!(
+                "Grace window must be at most {} ms",
+                MAX_MERGE_GRACE_WINDOW_MS
+            )));
+        }
+
+        self.merge = merge;
+        self.save(app_handle)
+    }
+
+    pub fn update_silence_detection(
+        &mut self,
+        app_handle: &AppHandle,
+        silence_timeout_ms: Option<u64>,
+        silence_threshold: f32,
+    ) -> Result<(), ConfigError> {
+        if silence_timeout_ms.is_some_and(|ms| ms < MIN_SILENCE_TIMEOUT_MS) {
+            return Err(ConfigError::Invalid(format!(
+                "Silence timeout must be at least {} ms",
+                MIN_SILENCE_TIMEOUT_MS
+            )));
+        }
+        if !(0.0..=1.0).contains(&silence_threshold) {
+            return Err(ConfigError::Invalid(
+                "Silence threshold must be between 0.0 and 1.0".to_string(),
+            ));
+        }
+
+        self.audio.silence_timeout_ms = silence_timeout_ms;
+        self.audio.silence_threshold = silence_threshold;
+        self.save(app_handle)
+    }
+
+    pub fn update_min_recording_ms(
+        &mut self,
+        app_handle: &AppHandle,
+        min_recording_ms: u64,
+    ) -> Result<(), ConfigError> {
+        if min_recording_ms > MAX_MIN_RECORDING_MS {
+            return Err(ConfigError::Invalid(format!(
+                "Minimum recording length must be at most {} ms",
+                MAX_MIN_RECORDING_MS
+            )));
+        }
+
+        self.audio.min_recording_ms = min_recording_ms;
+        self.save(app_handle)
+    }
+
+    pub fn update_input_gain(
+        &mut self,
+        app_handle: &AppHandle,
+        input_gain: f32,
+        normalization: NormalizationMode,
+    ) -> Result<(), ConfigError> {
+        if input_gain <= 0.0 || input_gain > MAX_INPUT_GAIN {
+            return Err(ConfigError::Invalid(format!(
+                "Input gain must be above 0 and at most {}",
+                MAX_INPUT_GAIN
+            )));
+        }
+
+        self.audio.input_gain = input_gain;
+        self.audio.normalization = normalization;
+        self.save(app_handle)
+    }
+
+    pub fn update_noise_reduction(
+        &mut self,
+        app_handle: &AppHandle,
+        noise_reduction: NoiseReduction,
+    ) -> Result<(), ConfigError> {
+        self.audio.noise_reduction = noise_reduction;
+        self.save(app_handle)
+    }
+
+    pub fn update_mic_muted(
+        &mut self,
+        app_handle: &AppHandle,
+        muted: bool,
+    ) -> Result<(), ConfigError> {
+        self.audio.muted = muted;
+        self.save(app_handle)
+    }
+
+    pub fn update_output_format(
+        &mut self,
+        app_handle: &AppHandle,
+        output_format: AudioFormat,
+    ) -> Result<(), ConfigError> {
+        if output_format == AudioFormat::Mp3 && !cfg!(feature = "mp3") {
+            return Err(ConfigError::Invalid(
+                "This build doesn't include MP3 support".to_string(),
+            ));
+        }
+
+        self.audio.output_format = output_format;
+        self.save(app_handle)
+    }
+
+    pub fn update_keep_recordings(
+        &mut self,
+        app_handle: &AppHandle,
+        keep_recordings: bool,
+    ) -> Result<(), ConfigError> {
+        self.audio.keep_recordings = keep_recordings;
+        self.save(app_handle)
+    }
+
+    pub fn update_level_mode(
+        &mut self,
+        app_handle: &AppHandle,
+        level_mode: LevelMode,
+    ) -> Result<(), ConfigError> {
+        self.audio.level_mode = level_mode;
+        self.save(app_handle)
+    }
+
+    pub fn update_injection_method(
+        &mut self,
+        app_handle: &AppHandle,
+        injection_method: InjectionMethod,
+    ) -> Result<(), ConfigError> {
+        self.injection_method = injection_method;
+        self.save(app_handle)
+    }
+
+    /// The profile for `app_name`, matched ignoring case.
+    pub fn app_profile(&self, app_name: &str) -> AppProfile {
+        self.app_profiles
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(app_name))
+            .map(|(_, profile)| profile.clone())
+            .unwrap_or(AppProfile {
+                mode: self.processing_mode,
+                system_prompt: None,
+            })
+    }
+
+    pub fn update_history_retention(
+        &mut self,
+        app_handle: &AppHandle,
+        history: HistoryConfig,
+    ) -> Result<(), ConfigError> {
+        if history.retention_days == Some(0) || history.max_entries == Some(0) {
+            return Err(ConfigError::Invalid(
+                "History limits must keep at least one day and one entry".to_string(),
+            ));
+        }
+
+        self.history = history;
+        self.save(app_handle)
+    }
+
+    pub fn update_processing_mode(
+        &mut self,
+        app_handle: &AppHandle,
+        processing_mode: ProcessingMode,
+    ) -> Result<(), ConfigError> {
+        self.processing_mode = processing_mode;
+        self.save(app_handle)
+    }
+
+    pub fn update_app_profile(
+        &mut self,
+        app_handle: &AppHandle,
+        app_name: String,
+        mut profile: AppProfile,
+    ) -> Result<(), ConfigError> {
+        let app_name = app_name.trim().to_string();
+        if app_name.is_empty() {
+            return Err(ConfigError::Invalid(
+                "App name must not be empty".to_string(),
+            ));
+        }
+
+        profile.system_prompt = profile
+            .system_prompt
+            .map(|prompt| prompt.trim().to_string())
+            .filter(|prompt| !prompt.is_empty());
+        if let Some(prompt) = &profile.system_prompt {
+            if prompt.chars().count() > MAX_PROFILE_PROMPT_CHARS {
+                return Err(ConfigError::Invalid(format!(
+                    "System prompt must be at most {} characters",
+                    MAX_PROFILE_PROMPT_CHARS
+                )));
+            }
+        }
+
+        // One profile per app, whatever the casing it was saved with
+        self.app_profiles
+            .retain(|name, _| !name.eq_ignore_ascii_case(&app_name));
+        self.app_profiles.insert(app_name, profile);
+        self.save(app_handle)
+    }
+
+    pub fn remove_app_profile(
+        &mut self,
+        app_handle: &AppHandle,
+        app_name: &str,
+    ) -> Result<(), ConfigError> {
+        self.app_profiles
+            .retain(|name, _| !name.eq_ignore_ascii_case(app_name.trim()));
+        self.save(app_handle)
+    }
+
+    /// Registers a custom model, replacing any existing one with the same name.
+    pub fn add_custom_model(
+        &mut self,
+        app_handle: &AppHandle,
+        model: CustomModel,
+    ) -> Result<(), ConfigError> {
+        let models = &mut self.transcription.custom_models;
+        models.retain(|existing| existing.name != model.name);
+        models.push(model);
+        self.save(app_handle)
+    }
+
+    pub fn update_model_pool_size(
+        &mut self,
+        app_handle: &AppHandle,
+        model_pool_size: usize,
+    ) -> Result<(), ConfigError> {
+        if !(1..=MAX_MODEL_POOL_SIZE).contains(&model_pool_size) {
+            return Err(ConfigError::Invalid(format!(
+                "Model pool size must be between 1 and {}",
+                MAX_MODEL_POOL_SIZE
+            )));
+        }
+
+        self.transcription.model_pool_size = model_pool_size;
+        self.save(app_handle)
+    }
+
+    pub fn update_whisper_model(
+        &mut self,
+        app_handle: &AppHandle,
+        whisper_model: WhichModel,
+    ) -> Result<(), ConfigError> {
+        self.transcription.whisper_model = whisper_model;
+        self.save(app_handle)
+    }
+
+    pub fn update_download_models(
+        &mut self,
+        app_handle: &AppHandle,
+        download_models: bool,
+    ) -> Result<(), ConfigError> {
+        self.transcription.download_models = download_models;
+        self.save(app_handle)
+    }
+
+    pub fn update_active_custom_model(
+        &mut self,
+        app_handle: &AppHandle,
+        name: Option<String>,
+    ) -> Result<(), ConfigError> {
+        if let Some(name) = &name {
+            if !self
+                .transcription
+                .custom_models
+                .iter()
+                .any(|model| &model.name == name)
+            {
+                return Err(ConfigError::Invalid(format!(
+                    "No custom model named '{}'",
+                    name
+                )));
+            }
+        }
+
+        self.transcription.active_custom_model = name;
+        self.save(app_handle)
+    }
+
+    pub fn update_launch_at_login(
+        &mut self,
+        app_handle: &AppHandle,
+        enabled: bool,
+    ) -> Result<(), ConfigError> {
+        self.launch_at_login = enabled;
         self.save(app_handle)
     }
 
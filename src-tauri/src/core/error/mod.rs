@@ -48,6 +48,10 @@ pub enum AudioError {
 
     #[error("Transcription error: {0}")]
     Transcription(String),
+
+    /// The recording is readable but too short or too quiet to hold speech.
+    #[error("No speech detected: {0}")]
+    NoSpeech(String),
 }
 
 #[derive(Error, Debug)]
@@ -1,28 +1,55 @@
-use crossbeam_channel::{unbounded, Receiver, Sender};
+use crate::controllers::audio_pipleine_controller::{AudioPipelineController, PipelineStage};
+use crate::core::system::shortcut_manager::ShortcutManager;
+use crate::services::metrics_service::{MetricsEvent, MetricsService};
+use crossbeam_channel::{bounded, unbounded, Receiver, RecvTimeoutError, Sender};
 use parking_lot::Mutex;
-use std::sync::Arc;
+use serde::Serialize;
+use std::{
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
 use tauri::{AppHandle, Emitter, Manager};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum AppStateType {
     Idle,
     Recording,
+    Paused,
     Transcribing,
     Processing,
     Error,
 }
 
+/// Payload of `state-transition`, emitted whenever the state actually changes.
+#[derive(Debug, Clone, Serialize)]
+pub struct StateTransition {
+    pub from: AppStateType,
+    pub to: AppStateType,
+    /// RFC 3339, like history timestamps.
+    pub at: String,
+}
+
 pub struct StateMachine {
     current_state: Mutex<AppStateType>,
     app_handle: AppHandle,
     command_sender: Sender<AppCommand>,
     command_receiver: Arc<Mutex<Receiver<AppCommand>>>,
+    /// Dropped when processing finishes or is cancelled, which disarms the
+    /// reset of a stuck pipeline.
+    reset_watchdog: Mutex<Option<Sender<()>>>,
 }
 
 #[derive(Debug)]
 pub enum AppCommand {
     StartRecording,
     StopRecording,
+    /// The pipeline finished processing a stopped recording. Returns to Idle
+    /// and purges the pipeline, unless another recording has started since.
+    ProcessingComplete,
+    TogglePause,
+    PauseRecording,
+    ResumeRecording,
     Cancel,
     PurgeResources,
     EmitStatus(String),
@@ -37,6 +64,7 @@ impl StateMachine {
             app_handle,
             command_sender: tx,
             command_receiver: Arc::new(Mutex::new(rx)),
+            reset_watchdog: Mutex::new(None),
         });
 
         Self::start_command_processor(Arc::clone(&machine));
@@ -60,74 +88,98 @@ impl StateMachine {
                             let machine_clone = Arc::clone(&machine);
 
                             std::thread::spawn(move || {
-                                let rt = tokio::runtime::Builder::new_current_thread()
-                                    .enable_all()
-                                    .build()
-                                    .unwrap();
-
-                                rt.block_on(async {
-                                    if let Some(state) = machine_clone
-                                        .app_handle
-                                        .try_state::<Arc<crate::core::app::AppState>>()
-                                    {
-                                        if let Some(pipeline) = &*state.audio_pipeline.lock() {
-                                            let _ = pipeline.start().await;
+                                let started = match machine_clone.pipeline() {
+                                    Some((state, pipeline)) => {
+                                        match state.runtime.block_on(pipeline.start()) {
+                                            Ok(()) => true,
+                                            Err(e) => {
+                                                log::warn!("Recording did not start: {}", e);
+                                                false
+                                            }
                                         }
                                     }
-                                });
+                                    None => true,
+                                };
 
-                                {
-                                    let mut state = machine_clone.current_state.lock();
-                                    *state = AppStateType::Recording;
+                                // A rejected start leaves the state to whoever owns it
+                                if !started {
+                                    return;
                                 }
+
+                                machine_clone.transition(AppStateType::Recording);
                                 machine_clone.emit_status("recording");
                             });
                         }
                     }
                     AppCommand::StopRecording => {
                         let current = *machine.current_state.lock();
-                        if current == AppStateType::Recording {
+                        if matches!(current, AppStateType::Recording | AppStateType::Paused) {
                             let machine_clone = Arc::clone(&machine);
 
                             std::thread::spawn(move || {
-                                let rt = tokio::runtime::Builder::new_current_thread()
-                                    .enable_all()
-                                    .build()
-                                    .unwrap();
-
-                                rt.block_on(async {
-                                    if let Some(state) = machine_clone
-                                        .app_handle
-                                        .try_state::<Arc<crate::core::app::AppState>>()
-                                    {
-                                        if let Some(pipeline) = &*state.audio_pipeline.lock() {
-                                            pipeline.stop().await;
-                                        }
-                                    }
-                                });
-
-                                {
-                                    let mut state = machine_clone.current_state.lock();
-                                    *state = AppStateType::Transcribing;
+                                // Armed before stopping so a pipeline that finishes
+                                // straight away still finds it
+                                let (disarm, disarmed) = bounded::<()>(1);
+                                *machine_clone.reset_watchdog.lock() = Some(disarm);
+                                machine_clone.transition(AppStateType::Transcribing);
+
+                                if let Some((state, pipeline)) = machine_clone.pipeline() {
+                                    state.runtime.block_on(pipeline.stop());
                                 }
-                                machine_clone.emit_status("transcribing");
 
-                                std::thread::sleep(std::time::Duration::from_secs(5));
+                                machine_clone.emit_status("transcribing");
 
-                                if *machine_clone.current_state.lock() != AppStateType::Idle {
-                                    log::info!("Resetting state machine from lingering state");
-                                    *machine_clone.current_state.lock() = AppStateType::Idle;
-                                    machine_clone.emit_status("idle");
-                                    machine_clone.send_command(AppCommand::PurgeResources);
+                                let timeout = machine_clone.reset_timeout();
+                                if disarmed.recv_timeout(timeout) == Err(RecvTimeoutError::Timeout)
+                                    && *machine_clone.current_state.lock() != AppStateType::Idle
+                                {
+                                    machine_clone.recover_stuck_pipeline(timeout);
                                 }
                             });
                         }
                     }
+                    AppCommand::ProcessingComplete => {
+                        // A cancelled run can finish after the next recording started
+                        let current = *machine.current_state.lock();
+                        if matches!(
+                            current,
+                            AppStateType::Transcribing | AppStateType::Processing
+                        ) {
+                            machine.reset_watchdog.lock().take();
+                            machine.transition(AppStateType::Idle);
+                            machine.emit_status("idle");
+                            machine.purge_resources();
+                        }
+                    }
+                    AppCommand::TogglePause => {
+                        let paused = machine.get_state() == AppStateType::Paused;
+                        machine.set_paused(!paused);
+                    }
+                    AppCommand::PauseRecording => {
+                        machine.set_paused(true);
+                    }
+                    AppCommand::ResumeRecording => {
+                        machine.set_paused(false);
+                    }
                     AppCommand::Cancel => {
+                        MetricsService::record(&machine.app_handle, MetricsEvent::Cancelled);
+                        let previous = *machine.current_state.lock();
+                        machine.reset_watchdog.lock().take();
                         machine.perform_cancellation();
                         machine.send_command(AppCommand::PurgeResources);
-                        *machine.current_state.lock() = AppStateType::Idle;
-                        machine.emit_status("cancelled");
+                        machine.transition(AppStateType::Idle);
+
+                        // Later stages are reported by the pipeline, which knows
+                        // whether a partial result exists
+                        if matches!(previous, AppStateType::Recording | AppStateType::Paused) {
+                            AudioPipelineController::emit_cancelled(
+                                &machine.app_handle,
+                                PipelineStage::Recording,
+                                None,
+                            );
+                        } else {
+                            machine.emit_status("cancelled");
+                        }
                     }
                     AppCommand::PurgeResources => {
                         machine.purge_resources();
@@ -142,6 +194,50 @@ impl StateMachine {
         });
     }
 
+    /// Pauses a running recording or resumes a paused one. Only
+    /// Recording -> Paused and Paused -> Recording are allowed; anything else
+    /// is ignored.
+    fn set_paused(&self, paused: bool) {
+        let current = *self.current_state.lock();
+        let (next, status) = match (current, paused) {
+            (AppStateType::Recording, true) => (AppStateType::Paused, "paused"),
+            (AppStateType::Paused, false) => (AppStateType::Recording, "recording"),
+            (other, _) => {
+                log::info!(
+                    "Ignoring {} while {:?}",
+                    if paused { "pause" } else { "resume" },
+                    other
+                );
+                return;
+            }
+        };
+
+        let Some(state) = self
+            .app_handle
+            .try_state::<Arc<crate::core::app::AppState>>()
+        else {
+            return;
+        };
+        let Some(pipeline) = state.audio_pipeline.lock().clone() else {
+            return;
+        };
+
+        let recording_service = pipeline.get_recording_service();
+        let result = if next == AppStateType::Paused {
+            recording_service.pause_recording()
+        } else {
+            recording_service.resume_recording()
+        };
+
+        match result {
+            Ok(()) => {
+                self.transition(next);
+                self.emit_status(status);
+            }
+            Err(e) => log::warn!("Failed to change pause state: {}", e),
+        }
+    }
+
     fn perform_cancellation(&self) {
         log::info!("Performing cancellation");
 
@@ -189,28 +285,116 @@ impl StateMachine {
 
         let app_handle = self.app_handle.clone();
         std::thread::spawn(move || {
-            let rt = tokio::runtime::Builder::new_current_thread()
-                .enable_all()
-                .build()
-                .unwrap();
-
-            rt.block_on(async {
-                if let Some(state) = app_handle.try_state::<Arc<crate::core::app::AppState>>() {
-                    let new_pipeline = Arc::new(
-                        crate::controllers::audio_pipleine_controller::AudioPipelineController::new(
-                            Arc::clone(&state),
-                            app_handle.clone(),
-                        ),
-                    );
-
-                    *state.audio_pipeline.lock() = Some(new_pipeline);
+            if let Some(state) = app_handle.try_state::<Arc<crate::core::app::AppState>>() {
+                if state.safe_mode {
+                    return;
                 }
-            });
+
+                let new_pipeline = Arc::new(AudioPipelineController::new(
+                    Arc::clone(&state),
+                    app_handle.clone(),
+                ));
+
+                *state.audio_pipeline.lock() = Some(new_pipeline);
+            }
 
             log::info!("Resources purged");
         });
     }
 
+    /// The app state and the current pipeline, cloned out so the pipeline
+    /// lock isn't held while it runs.
+    fn pipeline(
+        &self,
+    ) -> Option<(
+        Arc<crate::core::app::AppState>,
+        Arc<AudioPipelineController>,
+    )> {
+        let state = self
+            .app_handle
+            .try_state::<Arc<crate::core::app::AppState>>()?
+            .inner()
+            .clone();
+        let pipeline = state.audio_pipeline.lock().clone()?;
+        Some((state, pipeline))
+    }
+
+    /// Moves to `to`, announcing it on `state-transition` unless the state
+    /// was already `to`.
+    fn transition(&self, to: AppStateType) {
+        let from = std::mem::replace(&mut *self.current_state.lock(), to);
+        if from == to {
+            return;
+        }
+
+        log::info!("State {:?} -> {:?}", from, to);
+        let transition = StateTransition {
+            from,
+            to,
+            at: chrono::Utc::now().to_rfc3339(),
+        };
+        if let Err(e) = self.app_handle.emit("state-transition", transition) {
+            log::warn!("Failed to emit state transition: {}", e);
+        }
+        ShortcutManager::sync_cancel_shortcut(&self.app_handle);
+    }
+
+    /// Called when a stopped recording has not finished within the reset
+    /// timeout. Logs what the pipeline looked like, stops the recorder in case
+    /// it is still capturing and returns to Idle with a fresh pipeline.
+    fn recover_stuck_pipeline(&self, timeout: Duration) {
+        log::error!(
+            "No state change for {:?} after stopping, recovering stuck pipeline",
+            timeout
+        );
+
+        if let Some(state) = self
+            .app_handle
+            .try_state::<Arc<crate::core::app::AppState>>()
+        {
+            log::error!(
+                "Snapshot: state {:?}, last status {:?}",
+                self.get_state(),
+                state.processing_status()
+            );
+
+            if let Some(pipeline) = state.audio_pipeline.lock().clone() {
+                log::error!(
+                    "Snapshot: processing {}, cancelled {}, recording for {:?}",
+                    pipeline.is_processing(),
+                    pipeline.is_cancelled(),
+                    pipeline.recording_elapsed()
+                );
+
+                pipeline.cancellation_token.store(true, Ordering::SeqCst);
+                // The stuck thread may be the one holding the recorder
+                match pipeline
+                    .recording_service
+                    .try_lock_for(Duration::from_secs(1))
+                {
+                    Some(recording_service) => {
+                        if let Err(e) = recording_service.force_stop() {
+                            log::warn!("Failed to force stop recording: {}", e);
+                        }
+                    }
+                    None => log::error!("Recorder is locked, leaving it to the rebuild"),
+                }
+            }
+        }
+
+        self.reset_watchdog.lock().take();
+        self.transition(AppStateType::Idle);
+        self.emit_status("idle");
+        self.send_command(AppCommand::PurgeResources);
+    }
+
+    fn reset_timeout(&self) -> Duration {
+        self.app_handle
+            .try_state::<Arc<crate::core::app::AppState>>()
+            .map(|state| state.settings.read().transcription.reset_timeout())
+            .unwrap_or(Duration::from_secs(300))
+    }
+
     fn emit_status(&self, status: &str) {
         if let Some(window) = self.app_handle.get_webview_window("main") {
             let _ = window.emit_to("main", "audio-processing-status", status);
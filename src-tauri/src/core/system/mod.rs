@@ -1,4 +1,5 @@
 pub mod permission_manager;
+pub mod platform;
 pub mod shortcut_manager;
 pub mod system_tray_manager;
 pub mod window_manager;
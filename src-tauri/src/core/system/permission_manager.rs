@@ -1,26 +1,48 @@
-use crate::core::error::SystemError;
+use crate::core::{error::SystemError, system::platform};
 use cpal::{
     self,
     traits::{DeviceTrait, HostTrait},
 };
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+use tauri::{AppHandle, Emitter};
+
+static WATCHING_ACCESSIBILITY: AtomicBool = AtomicBool::new(false);
 
 pub struct PermissionManager;
 
 impl PermissionManager {
     pub fn check_accessibility_permissions() -> Result<bool, SystemError> {
-        #[cfg(target_os = "macos")]
-        return Ok(macos_accessibility_client::accessibility::application_is_trusted());
-
-        #[cfg(not(target_os = "macos"))]
-        return Ok(true);
+        Ok(platform::current().accessibility_trusted(false))
     }
 
     pub fn request_accessibility_permissions() -> Result<bool, SystemError> {
-        #[cfg(target_os = "macos")]
-        return Ok(macos_accessibility_client::accessibility::application_is_trusted_with_prompt());
+        Ok(platform::current().accessibility_trusted(true))
+    }
+
+    /// Tells the UI that accessibility access is gone, prompts the user to grant
+    /// it again and keeps polling until it comes back.
+    pub fn handle_accessibility_revoked(app_handle: &AppHandle) {
+        if WATCHING_ACCESSIBILITY.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        log::warn!("Accessibility permission revoked, waiting for it to be restored");
+        let _ = app_handle.emit("accessibility-permission-lost", ());
+        let _ = Self::request_accessibility_permissions();
+
+        let app_handle = app_handle.clone();
+        std::thread::spawn(move || {
+            while !Self::check_accessibility_permissions().unwrap_or(false) {
+                std::thread::sleep(Duration::from_secs(2));
+            }
 
-        #[cfg(not(target_os = "macos"))]
-        return Ok(true);
+            log::info!("Accessibility permission restored");
+            WATCHING_ACCESSIBILITY.store(false, Ordering::SeqCst);
+            let _ = app_handle.emit("accessibility-permission-restored", ());
+        });
     }
 
     pub fn check_microphone_permissions() -> Result<bool, SystemError> {
@@ -0,0 +1,114 @@
+use super::{FrontmostApp, PlatformIntegration};
+use std::process::Command;
+
+pub struct MacOsPlatform;
+
+impl MacOsPlatform {
+    fn run_script(script: &str) -> Option<String> {
+        let output = Command::new("osascript")
+            .arg("-e")
+            .arg(script)
+            .output()
+            .ok()?;
+
+        if !output.status.success() {
+            return None;
+        }
+
+        String::from_utf8(output.stdout).ok()
+    }
+
+    fn activate_pid(pid: i32) -> bool {
+        use cocoa::appkit::{NSApplicationActivationOptions, NSRunningApplication};
+        use cocoa::base::{id, nil, YES};
+        use objc::{class, msg_send, sel, sel_impl};
+
+        unsafe {
+            let app: id = msg_send![
+                class!(NSRunningApplication),
+                runningApplicationWithProcessIdentifier: pid
+            ];
+            if app == nil {
+                return false;
+            }
+            app.activateWithOptions_(
+                NSApplicationActivationOptions::NSApplicationActivateIgnoringOtherApps,
+            ) == YES
+        }
+    }
+}
+
+impl PlatformIntegration for MacOsPlatform {
+    fn frontmost_app(&self) -> Option<FrontmostApp> {
+        let stdout = Self::run_script(
+            r#"tell application "System Events"
+    set frontApp to first application process whose frontmost is true
+    return (name of frontApp) & linefeed & (bundle identifier of frontApp) & linefeed & (unix id of frontApp)
+end tell"#,
+        )?;
+
+        let mut lines = stdout.lines().map(str::trim);
+        let name = lines.next().filter(|name| !name.is_empty())?.to_string();
+        // Processes without a bundle report "missing value"
+        let bundle_id = lines
+            .next()
+            .filter(|id| !id.is_empty() && *id != "missing value")
+            .map(str::to_string);
+        let pid = lines.next().and_then(|pid| pid.parse().ok());
+
+        Some(FrontmostApp {
+            name,
+            bundle_id,
+            pid,
+            window_id: None,
+        })
+    }
+
+    fn frontmost_window_title(&self) -> Option<String> {
+        Self::run_script(
+            r#"tell application "System Events" to get name of front window of (first application process whose frontmost is true)"#,
+        )
+        .map(|title| title.trim().to_string())
+    }
+
+    /// Tries the bundle identifier, then the PID, and only then the name,
+    /// which fails for apps whose AppleScript name differs from their
+    /// process name.
+    fn activate_app(&self, app: &FrontmostApp) -> bool {
+        if let Some(bundle_id) = &app.bundle_id {
+            let script = format!(r#"tell application id "{}" to activate"#, bundle_id);
+            if Self::run_script(&script).is_some() {
+                return true;
+            }
+        }
+
+        if let Some(pid) = app.pid {
+            if Self::activate_pid(pid) {
+                return true;
+            }
+        }
+
+        Self::run_script(&format!(r#"tell application "{}" to activate"#, app.name)).is_some()
+    }
+
+    fn focused_text(&self) -> Option<String> {
+        let text = Self::run_script(
+            r#"tell application "System Events"
+    set frontApp to first application process whose frontmost is true
+    set focusedElement to value of attribute "AXFocusedUIElement" of frontApp
+    return value of attribute "AXValue" of focusedElement
+end tell"#,
+        )?;
+
+        // osascript terminates its result with a newline
+        Some(text.strip_suffix('\n').unwrap_or(&text).to_string())
+    }
+
+    fn accessibility_trusted(&self, prompt: bool) -> bool {
+        if prompt {
+            macos_accessibility_client::accessibility::application_is_trusted_with_prompt()
+        } else {
+            macos_accessibility_client::accessibility::application_is_trusted()
+        }
+    }
+}
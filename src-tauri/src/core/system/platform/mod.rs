@@ -0,0 +1,55 @@
+//! OS-specific pieces of capturing, re-focusing and reading the app a
+//! dictation is for. Everything else, including typing and pasting through
+//! enigo, is already cross-platform.
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod linux;
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(target_os = "windows")]
+mod windows;
+
+/// The app that had focus when a recording started, identified well enough
+/// to bring it back even when its display name isn't its AppleScript name,
+/// as with many Electron apps.
+#[derive(Debug, Clone)]
+pub struct FrontmostApp {
+    pub name: String,
+    /// macOS only.
+    pub bundle_id: Option<String>,
+    pub pid: Option<i32>,
+    /// The focused window's native handle: an HWND on Windows, an X11 window
+    /// id on Linux. Unused on macOS, which activates whole apps.
+    pub window_id: Option<u64>,
+}
+
+pub trait PlatformIntegration: Send + Sync {
+    fn frontmost_app(&self) -> Option<FrontmostApp>;
+
+    /// Title of the frontmost window, used to tell apart documents or chats
+    /// within the same app.
+    fn frontmost_window_title(&self) -> Option<String>;
+
+    /// Brings `app` back to the front. Returns whether that worked.
+    fn activate_app(&self, app: &FrontmostApp) -> bool;
+
+    /// Value of the focused text element in the frontmost app. `None` when it
+    /// isn't a text field, can't be read, or the platform doesn't expose it.
+    fn focused_text(&self) -> Option<String>;
+
+    /// Whether we may send keystrokes to other apps, asking the user for it
+    /// when `prompt` is set and the platform gates it.
+    fn accessibility_trusted(&self, prompt: bool) -> bool;
+}
+
+/// The integration for the OS this build targets.
+pub fn current() -> &'static dyn PlatformIntegration {
+    #[cfg(target_os = "macos")]
+    return &self::macos::MacOsPlatform;
+
+    #[cfg(target_os = "windows")]
+    return &self::windows::WindowsPlatform;
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    return &self::linux::LinuxPlatform;
+}
@@ -0,0 +1,98 @@
+use super::{FrontmostApp, PlatformIntegration};
+use std::path::Path;
+use windows::{
+    core::PWSTR,
+    Win32::{
+        Foundation::{CloseHandle, HWND},
+        System::Threading::{
+            OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32,
+            PROCESS_QUERY_LIMITED_INFORMATION,
+        },
+        UI::WindowsAndMessaging::{
+            GetForegroundWindow, GetWindowTextW, GetWindowThreadProcessId, IsIconic,
+            SetForegroundWindow, ShowWindow, SW_RESTORE,
+        },
+    },
+};
+
+pub struct WindowsPlatform;
+
+impl WindowsPlatform {
+    fn foreground_window() -> Option<HWND> {
+        let hwnd = unsafe { GetForegroundWindow() };
+        (!hwnd.0.is_null()).then_some(hwnd)
+    }
+
+    /// File name of the process's executable without its extension, e.g.
+    /// "Code" for VS Code, which is what app profiles are keyed by.
+    fn process_name(pid: u32) -> Option<String> {
+        unsafe {
+            let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+            let mut buffer = [0u16; 1024];
+            let mut len = buffer.len() as u32;
+            let result = QueryFullProcessImageNameW(
+                process,
+                PROCESS_NAME_WIN32,
+                PWSTR(buffer.as_mut_ptr()),
+                &mut len,
+            );
+            let _ = CloseHandle(process);
+            result.ok()?;
+
+            let path = String::from_utf16_lossy(&buffer[..len as usize]);
+            Path::new(&path)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+        }
+    }
+}
+
+impl PlatformIntegration for WindowsPlatform {
+    fn frontmost_app(&self) -> Option<FrontmostApp> {
+        let hwnd = Self::foreground_window()?;
+        let mut pid = 0u32;
+        unsafe { GetWindowThreadProcessId(hwnd, Some(&mut pid)) };
+        if pid == 0 {
+            return None;
+        }
+
+        Some(FrontmostApp {
+            name: Self::process_name(pid)?,
+            bundle_id: None,
+            pid: Some(pid as i32),
+            window_id: Some(hwnd.0 as u64),
+        })
+    }
+
+    fn frontmost_window_title(&self) -> Option<String> {
+        let hwnd = Self::foreground_window()?;
+        let mut buffer = [0u16; 512];
+        let len = unsafe { GetWindowTextW(hwnd, &mut buffer) };
+        (len > 0).then(|| String::from_utf16_lossy(&buffer[..len as usize]))
+    }
+
+    fn activate_app(&self, app: &FrontmostApp) -> bool {
+        let Some(window_id) = app.window_id else {
+            return false;
+        };
+
+        let hwnd = HWND(window_id as *mut _);
+        unsafe {
+            if IsIconic(hwnd).as_bool() {
+                let _ = ShowWindow(hwnd, SW_RESTORE);
+            }
+            SetForegroundWindow(hwnd).as_bool()
+        }
+    }
+
+    /// Would need UI Automation; injection verification is skipped instead.
+    fn focused_text(&self) -> Option<String> {
+        None
+    }
+
+    /// Windows lets any desktop app send input to others of the same
+    /// integrity level.
+    fn accessibility_trusted(&self, _prompt: bool) -> bool {
+        true
+    }
+}
@@ -1,10 +1,90 @@
-use crate::core::{app::AppState, error::AppError, state_machine::AppCommand};
-use std::{str::FromStr, sync::Arc};
-use tauri::AppHandle;
+use crate::core::{
+    app::AppState,
+    config::{RecordingMode, ShortcutConfig},
+    error::AppError,
+    state_machine::{AppCommand, AppStateType, StateMachine},
+};
+use parking_lot::Mutex;
+use std::{
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tauri::{AppHandle, Manager, Wry};
 use tauri_plugin_global_shortcut::{
-    Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutEvent, ShortcutState,
+    Code, GlobalShortcut, GlobalShortcutExt, Modifiers, Shortcut, ShortcutEvent, ShortcutState,
 };
 
+/// Tracks one press of the record shortcut. In `TapOrHold` mode a tap
+/// (released before the threshold) leaves recording on until the next press;
+/// a hold records only while the key is down. Exactly at the threshold counts
+/// as a hold. The other modes always behave as one or the other.
+#[derive(Default)]
+struct RecordGesture {
+    pressed_at: Option<Instant>,
+    toggled_on: bool,
+    /// Set when a press stops a toggled recording, so its release is ignored.
+    ending_toggle: bool,
+}
+
+impl RecordGesture {
+    fn on_pressed(&mut self, machine: &StateMachine, mode: RecordingMode) {
+        // Key repeat delivers extra presses while the shortcut is held
+        if self.pressed_at.is_some() || self.ending_toggle {
+            return;
+        }
+
+        let recording = matches!(
+            machine.get_state(),
+            AppStateType::Recording | AppStateType::Paused
+        );
+        if mode == RecordingMode::Toggle {
+            self.pressed_at = Some(Instant::now());
+            machine.send_command(if recording {
+                AppCommand::StopRecording
+            } else {
+                AppCommand::StartRecording
+            });
+            return;
+        }
+
+        if self.toggled_on && recording {
+            self.toggled_on = false;
+            self.ending_toggle = true;
+            machine.send_command(AppCommand::StopRecording);
+            return;
+        }
+
+        // A toggled recording may have been cancelled from the UI meanwhile
+        self.toggled_on = false;
+        self.pressed_at = Some(Instant::now());
+        machine.send_command(AppCommand::StartRecording);
+    }
+
+    fn on_released(&mut self, machine: &StateMachine, mode: RecordingMode, threshold: Duration) {
+        if self.ending_toggle {
+            self.ending_toggle = false;
+            return;
+        }
+
+        let Some(pressed_at) = self.pressed_at.take() else {
+            return;
+        };
+
+        // In Toggle mode the press already did the work
+        if mode == RecordingMode::Toggle {
+            return;
+        }
+
+        if mode == RecordingMode::TapOrHold && pressed_at.elapsed() < threshold {
+            log::info!("Record shortcut tapped, recording until next press");
+            self.toggled_on = true;
+        } else {
+            machine.send_command(AppCommand::StopRecording);
+        }
+    }
+}
+
 pub struct ShortcutManager {
     app_state: Arc<AppState>,
 }
@@ -19,46 +99,42 @@ impl ShortcutManager {
         let settings = self.app_state.settings.read().clone();
 
         log::info!("{:?}", settings);
-        let record_shortcut = {
-            let modifier = settings
-                .shortcuts
-                .record_modifier
-                .as_ref()
-                .ok_or_else(|| AppError::Generic("Record modifier not set".to_string()))?;
-
-            let parsed_modifier = Modifiers::from_name(modifier).ok_or_else(|| {
-                AppError::Generic(format!("Failed to parse shortcut modifier '{}'", modifier))
-            })?;
-
-            let key = settings
-                .shortcuts
-                .record_key
-                .as_ref()
-                .ok_or_else(|| AppError::Generic("Record key not set".to_string()))?;
-
-            log::info!("modifier: {:?}, key: {:?}", modifier, key);
-
-            let parsed_key = Code::from_str(key).map_err(|e| {
-                AppError::Generic(format!("Failed to parse shortcut key '{}': {}", key, e))
-            })?;
-
-            Shortcut::new(Some(parsed_modifier), parsed_key)
-        };
+        let record_shortcut = Self::record_shortcut(&settings.shortcuts)?;
+        log::info!("Record shortcut: {:?}", record_shortcut);
 
         let app_state = Arc::clone(&self.app_state);
+        let gesture = Mutex::new(RecordGesture::default());
 
         handle.plugin(
             tauri_plugin_global_shortcut::Builder::new()
                 .with_handler(
                     move |_app_handle: &AppHandle, shortcut: &Shortcut, event: ShortcutEvent| {
-                        if shortcut == &record_shortcut {
+                        let cancel = Self::cancel_shortcut(&app_state.settings.read().shortcuts);
+                        if cancel.is_ok_and(|cancel| cancel.as_ref() == Some(shortcut)) {
+                            if event.state == ShortcutState::Pressed {
+                                log::info!("Cancel shortcut pressed");
+                                app_state.cancel_current_operation();
+                            }
+                            return;
+                        }
+
+                        // Compared against the settings so a re-registered shortcut
+                        // takes over without rebuilding the plugin
+                        let current = Self::record_shortcut(&app_state.settings.read().shortcuts);
+                        if current.is_ok_and(|current| shortcut == &current) {
                             if let Some(machine) = &*app_state.state_machine.lock() {
+                                let (mode, threshold) = {
+                                    let settings = app_state.settings.read();
+                                    (
+                                        settings.shortcuts.recording_mode,
+                                        Duration::from_millis(settings.shortcuts.tap_threshold_ms),
+                                    )
+                                };
+                                let mut gesture = gesture.lock();
                                 match event.state {
-                                    ShortcutState::Pressed => {
-                                        machine.send_command(AppCommand::StartRecording);
-                                    }
+                                    ShortcutState::Pressed => gesture.on_pressed(machine, mode),
                                     ShortcutState::Released => {
-                                        machine.send_command(AppCommand::StopRecording);
+                                        gesture.on_released(machine, mode, threshold)
                                     }
                                 }
                             }
@@ -77,4 +153,125 @@ impl ShortcutManager {
 
         Ok(())
     }
+
+    /// Swaps the registered record shortcut for the one in `shortcuts`.
+    pub fn reregister_shortcuts(
+        app_handle: &AppHandle,
+        shortcuts: &ShortcutConfig,
+    ) -> Result<(), AppError> {
+        let record_shortcut = Self::record_shortcut(shortcuts)?;
+
+        let global_shortcut = app_handle.global_shortcut();
+        global_shortcut
+            .unregister_all()
+            .map_err(|e| AppError::Generic(format!("Failed to unregister shortcuts: {}", e)))?;
+        global_shortcut.register(record_shortcut).map_err(|e| {
+            log::error!("Failed to register record shortcut: {}", e);
+            AppError::Generic("Failed to register record shortcut".to_string())
+        })?;
+
+        if let Some(state) = app_handle.try_state::<Arc<AppState>>() {
+            Self::set_cancel_registered(app_handle, shortcuts, Self::dictation_active(&state));
+        }
+        Ok(())
+    }
+
+    /// Registers the cancel shortcut while a dictation is under way and
+    /// removes it otherwise, so the cancel key (Escape by default) keeps
+    /// working in other apps the rest of the time.
+    pub fn sync_cancel_shortcut(app_handle: &AppHandle) {
+        let Some(state) = app_handle.try_state::<Arc<AppState>>() else {
+            return;
+        };
+        let shortcuts = state.settings.read().shortcuts.clone();
+        Self::set_cancel_registered(app_handle, &shortcuts, Self::dictation_active(&state));
+    }
+
+    /// Swaps a cancel shortcut that was just changed in settings, replacing
+    /// `previous` if it is registered.
+    pub fn replace_cancel_shortcut(app_handle: &AppHandle, previous: &ShortcutConfig) {
+        Self::set_cancel_registered(app_handle, previous, false);
+        Self::sync_cancel_shortcut(app_handle);
+    }
+
+    fn dictation_active(state: &AppState) -> bool {
+        state.state_machine.lock().as_ref().is_some_and(|machine| {
+            !matches!(
+                machine.get_state(),
+                AppStateType::Idle | AppStateType::Error
+            )
+        })
+    }
+
+    fn set_cancel_registered(app_handle: &AppHandle, shortcuts: &ShortcutConfig, registered: bool) {
+        let cancel = match Self::cancel_shortcut(shortcuts) {
+            Ok(Some(cancel)) => cancel,
+            Ok(None) => return,
+            Err(e) => {
+                log::warn!("Skipping cancel shortcut: {}", e);
+                return;
+            }
+        };
+
+        // Not installed in safe mode
+        if app_handle.try_state::<GlobalShortcut<Wry>>().is_none() {
+            return;
+        }
+
+        let global_shortcut = app_handle.global_shortcut();
+        if global_shortcut.is_registered(cancel) == registered {
+            return;
+        }
+
+        let result = if registered {
+            global_shortcut.register(cancel)
+        } else {
+            global_shortcut.unregister(cancel)
+        };
+        if let Err(e) = result {
+            log::warn!("Failed to update cancel shortcut registration: {}", e);
+        }
+    }
+
+    /// `None` when no cancel key is set.
+    fn cancel_shortcut(shortcuts: &ShortcutConfig) -> Result<Option<Shortcut>, AppError> {
+        let Some(key) = shortcuts.cancel_key.as_ref() else {
+            return Ok(None);
+        };
+
+        let parsed_modifier = match shortcuts.cancel_modifier.as_ref() {
+            Some(modifier) => Some(Modifiers::from_name(modifier).ok_or_else(|| {
+                AppError::Generic(format!("Failed to parse shortcut modifier '{}'", modifier))
+            })?),
+            None => None,
+        };
+
+        let parsed_key = Code::from_str(key).map_err(|e| {
+            AppError::Generic(format!("Failed to parse shortcut key '{}': {}", key, e))
+        })?;
+
+        Ok(Some(Shortcut::new(parsed_modifier, parsed_key)))
+    }
+
+    fn record_shortcut(shortcuts: &ShortcutConfig) -> Result<Shortcut, AppError> {
+        let modifier = shortcuts
+            .record_modifier
+            .as_ref()
+            .ok_or_else(|| AppError::Generic("Record modifier not set".to_string()))?;
+
+        let parsed_modifier = Modifiers::from_name(modifier).ok_or_else(|| {
+            AppError::Generic(format!("Failed to parse shortcut modifier '{}'", modifier))
+        })?;
+
+        let key = shortcuts
+            .record_key
+            .as_ref()
+            .ok_or_else(|| AppError::Generic("Record key not set".to_string()))?;
+
+        let parsed_key = Code::from_str(key).map_err(|e| {
+            AppError::Generic(format!("Failed to parse shortcut key '{}': {}", key, e))
+        })?;
+
+        Ok(Shortcut::new(Some(parsed_modifier), parsed_key))
+    }
 }
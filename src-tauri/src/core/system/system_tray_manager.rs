@@ -1,18 +1,33 @@
-use crate::core::{
-    app::AppState,
-    error::{AppError, SystemError},
-    state_machine::AppCommand,
-    utils::updater::check_for_updates,
+use crate::{
+    core::{
+        app::AppState,
+        error::{AppError, SystemError},
+        state_machine::{AppCommand, AppStateType},
+        utils::updater::check_for_updates,
+    },
+    services::{
+        text_processing_service::TextProcessingService,
+        text_transcript_history_service::TextTranscriptHistoryService,
+    },
+};
+use std::{
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
 };
-use std::sync::Arc;
 use tauri::{
     image::Image,
-    menu::{Menu, MenuItem, PredefinedMenuItem},
+    menu::{CheckMenuItem, IsMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu},
     tray::TrayIconBuilder,
-    AppHandle, Manager,
+    AppHandle, Listener, Manager,
 };
 use tauri_plugin_notification::NotificationExt;
 
+const TOOLTIP_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+/// Menu ids of recent transcriptions are this prefix plus the history id.
+const RECENT_ID_PREFIX: &str = "recent:";
+const RECENT_COUNT: usize = 5;
+const RECENT_LABEL_CHARS: usize = 40;
+
 pub struct SystemTrayManager {
     app_handle: AppHandle,
     app_state: Arc<AppState>,
@@ -45,9 +60,94 @@ impl SystemTrayManager {
             log::warn!("Tray not found");
         }
 
+        self.watch_recording_duration();
+
+        // Keeps the Recent submenu and Start Recording's availability current
+        for event in [
+            "transcription-added",
+            "refresh-history",
+            "audio-device-changed",
+        ] {
+            let app_handle = self.app_handle.clone();
+            let app_state = Arc::clone(&self.app_state);
+            self.app_handle.listen(event, move |_| {
+                Self::refresh_menu(&app_handle, &app_state);
+            });
+        }
+
         Ok(())
     }
 
+    /// Rebuilds the menu with the recording items enabled to match the
+    /// current recording, the same way the tray's own handlers set them.
+    pub fn refresh_menu(app: &AppHandle, app_state: &AppState) {
+        let busy = app_state
+            .state_machine
+            .lock()
+            .as_ref()
+            .is_some_and(|machine| {
+                matches!(
+                    machine.get_state(),
+                    AppStateType::Recording | AppStateType::Paused
+                )
+            });
+
+        if let Some(tray) = app.tray_by_id("tray") {
+            match Self::build_tray_menu_static(app, !busy, busy) {
+                Ok(menu) => {
+                    let _ = tray.set_menu(Some(menu));
+                }
+                Err(e) => log::warn!("Failed to rebuild tray menu: {}", e),
+            }
+        }
+    }
+
+    /// Keeps the tray tooltip showing how long the current recording has run,
+    /// whether it was started from the tray or the shortcut.
+    fn watch_recording_duration(&self) {
+        let app_handle = self.app_handle.clone();
+        let app_state = Arc::clone(&self.app_state);
+
+        std::thread::spawn(move || {
+            let mut shown: Option<String> = None;
+            loop {
+                std::thread::sleep(TOOLTIP_REFRESH_INTERVAL);
+
+                let tooltip = Self::recording_tooltip(&app_state);
+                if tooltip == shown {
+                    continue;
+                }
+                if let Some(tray) = app_handle.tray_by_id("tray") {
+                    if let Err(e) = tray.set_tooltip(tooltip.as_deref()) {
+                        log::warn!("Failed to update tray tooltip: {}", e);
+                    }
+                }
+                shown = tooltip;
+            }
+        });
+    }
+
+    fn recording_tooltip(app_state: &AppState) -> Option<String> {
+        let elapsed = app_state
+            .audio_pipeline
+            .lock()
+            .as_ref()?
+            .recording_elapsed()?;
+        let paused = app_state
+            .state_machine
+            .lock()
+            .as_ref()
+            .is_some_and(|machine| machine.get_state() == AppStateType::Paused);
+
+        let secs = elapsed.as_secs();
+        let label = if paused {
+            "Recording paused"
+        } else {
+            "Recording"
+        };
+        Some(format!("{} {:02}:{:02}", label, secs / 60, secs % 60))
+    }
+
     pub fn handle_tray_menu_event(app: &AppHandle, event_id: &str, app_state: &Arc<AppState>) {
         match event_id {
             "start_recording" => {
@@ -73,6 +173,12 @@ impl SystemTrayManager {
                     }
                 }
             }
+            "toggle_pause" => {
+                log::info!("Pause/resume requested from tray");
+                if let Some(machine) = &*app_state.state_machine.lock() {
+                    machine.send_command(AppCommand::TogglePause);
+                }
+            }
             "cancel_recording" => {
                 log::info!("Cancel recording requested from tray");
                 app_state.cancel_current_operation();
@@ -83,6 +189,14 @@ impl SystemTrayManager {
                     }
                 }
             }
+            "mute_microphone" => {
+                let muted = !app_state.settings.read().audio.muted;
+                if let Err(e) = app_state.set_mic_muted(app, muted) {
+                    log::error!("Failed to toggle microphone mute: {}", e);
+                }
+                // The check mark flips on click; rebuild so it matches what was stored
+                Self::refresh_menu(app, app_state);
+            }
             "settings" => {
                 if let Some(settings_window) = app.get_webview_window("settings") {
                     if let Err(e) = settings_window.show() {
@@ -134,10 +248,37 @@ impl SystemTrayManager {
 
                 app.exit(0);
             }
-            _ => {}
+            id => {
+                if let Some(entry_id) = id.strip_prefix(RECENT_ID_PREFIX) {
+                    Self::reinject_recent(app, entry_id);
+                }
+            }
         }
     }
 
+    /// Injects a recent transcription again into the frontmost app.
+    fn reinject_recent(app: &AppHandle, entry_id: &str) {
+        let text = entry_id.parse::<u32>().ok().and_then(|id| {
+            TextTranscriptHistoryService::get_transcription_history(app)
+                .ok()?
+                .into_iter()
+                .find(|entry| entry.id == id)
+                .map(|entry| entry.text)
+        });
+        let Some(text) = text else {
+            log::warn!("Recent transcription {} no longer exists", entry_id);
+            return;
+        };
+
+        log::info!("Re-injecting a recent transcription from tray");
+        let app = app.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = TextProcessingService::inject_text(&app, &text) {
+                log::error!("Failed to re-inject transcription: {}", e);
+            }
+        });
+    }
+
     pub fn update_menu(&self, start_enabled: bool, stop_enabled: bool) -> Result<(), AppError> {
         if let Some(tray) = self.app_handle.tray_by_id("tray") {
             let new_menu = self.build_tray_menu(start_enabled, stop_enabled)?;
@@ -176,12 +317,34 @@ impl SystemTrayManager {
         start_enabled: bool,
         stop_enabled: bool,
     ) -> Result<Menu<tauri::Wry>, AppError> {
-        let start_recording_item =
-            Self::create_menu_item(app, "start_recording", "Start Recording", start_enabled)?;
+        let input_available = app
+            .try_state::<Arc<AppState>>()
+            .is_none_or(|state| state.input_available.load(Ordering::SeqCst));
+        let start_recording_item = Self::create_menu_item(
+            app,
+            "start_recording",
+            "Start Recording",
+            start_enabled && input_available,
+        )?;
         let stop_recording_item =
             Self::create_menu_item(app, "stop_recording", "Stop Recording", stop_enabled)?;
+        let pause_recording_item =
+            Self::create_menu_item(app, "toggle_pause", "Pause/Resume Recording", stop_enabled)?;
         let cancel_recording_item =
             Self::create_menu_item(app, "cancel_recording", "Cancel Recording", stop_enabled)?;
+        let muted = app
+            .try_state::<Arc<AppState>>()
+            .is_some_and(|state| state.settings.read().audio.muted);
+        let mute_item = CheckMenuItem::with_id(
+            app,
+            "mute_microphone",
+            "Mute Microphone",
+            true,
+            muted,
+            None::<&str>,
+        )
+        .map_err(|e| AppError::Config(format!("Failed to create mute item: {}", e).into()))?;
+        let recent_menu = Self::build_recent_submenu(app)?;
         let history_item = Self::create_menu_item(app, "history", "History", true)?;
         let separator = PredefinedMenuItem::separator(app)
             .map_err(|e| AppError::Config(format!("Failed to create separator: {}", e).into()))?;
@@ -199,8 +362,11 @@ impl SystemTrayManager {
             &[
                 &start_recording_item,
                 &stop_recording_item,
+                &pause_recording_item,
                 &cancel_recording_item,
+                &mute_item,
                 &separator,
+                &recent_menu,
                 &history_item,
                 &settings_item,
                 &separator,
@@ -213,6 +379,57 @@ impl SystemTrayManager {
         .map_err(|e| AppError::Config(format!("Failed to create menu: {}", e).into()))
     }
 
+    fn build_recent_submenu(app: &AppHandle) -> Result<Submenu<tauri::Wry>, AppError> {
+        let history =
+            TextTranscriptHistoryService::get_transcription_history(app).unwrap_or_else(|e| {
+                log::warn!("Failed to load recent transcriptions: {}", e);
+                Vec::new()
+            });
+
+        let mut items = history
+            .iter()
+            .rev()
+            // Nothing worth copying is left in these
+            .filter(|entry| !entry.redacted && !entry.text.trim().is_empty())
+            .take(RECENT_COUNT)
+            .map(|entry| {
+                Self::create_menu_item(
+                    app,
+                    &format!("{}{}", RECENT_ID_PREFIX, entry.id),
+                    &Self::recent_label(&entry.text),
+                    true,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if items.is_empty() {
+            items.push(Self::create_menu_item(
+                app,
+                "recent_empty",
+                "No transcriptions yet",
+                false,
+            )?);
+        }
+
+        let items: Vec<&dyn IsMenuItem<tauri::Wry>> = items
+            .iter()
+            .map(|item| item as &dyn IsMenuItem<tauri::Wry>)
+            .collect();
+        Submenu::with_items(app, "Recent", true, &items)
+            .map_err(|e| AppError::Config(format!("Failed to create submenu: {}", e).into()))
+    }
+
+    /// One line of at most `RECENT_LABEL_CHARS` characters.
+    fn recent_label(text: &str) -> String {
+        let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if line.chars().count() <= RECENT_LABEL_CHARS {
+            return line;
+        }
+
+        let mut label: String = line.chars().take(RECENT_LABEL_CHARS - 1).collect();
+        label.push('…');
+        label
+    }
+
     fn create_menu_item(
         app: &AppHandle,
         id: &str,
@@ -1,11 +1,39 @@
+#[cfg(target_os = "macos")]
 use cocoa::appkit::NSWindowTitleVisibility;
-use tauri::WebviewWindow;
+use tauri::{LogicalPosition, Monitor, WebviewWindow};
 
 use crate::core::error::SystemError;
 
+const MAIN_WINDOW_WIDTH: f64 = 150.0;
+const MAIN_WINDOW_BOTTOM_OFFSET: f64 = 40.0 + 80.0;
+
 pub struct WindowManager {}
 
 impl WindowManager {
+    /// Places the recording indicator at the bottom center of the monitor the
+    /// cursor is on, falling back to the primary monitor.
+    pub fn position_on_active_monitor(window: &WebviewWindow) -> Result<(), SystemError> {
+        let monitor = Self::cursor_monitor(window)
+            .or_else(|| window.primary_monitor().ok().flatten())
+            .ok_or_else(|| SystemError::Window("No monitor available".to_string()))?;
+
+        let scale_factor = monitor.scale_factor();
+        let origin = monitor.position().to_logical::<f64>(scale_factor);
+        let size = monitor.size().to_logical::<f64>(scale_factor);
+
+        let x_pos = origin.x + (size.width / 2.0) - (MAIN_WINDOW_WIDTH / 2.0);
+        let y_pos = origin.y + size.height - MAIN_WINDOW_BOTTOM_OFFSET;
+
+        window
+            .set_position(LogicalPosition::new(x_pos, y_pos))
+            .map_err(|e| SystemError::Window(format!("Failed to position window: {}", e)))
+    }
+
+    fn cursor_monitor(window: &WebviewWindow) -> Option<Monitor> {
+        let cursor = window.cursor_position().ok()?;
+        window.monitor_from_point(cursor.x, cursor.y).ok().flatten()
+    }
+
     pub fn remove_titlebar_and_traffic_lights(window: WebviewWindow) -> Result<(), SystemError> {
         #[cfg(target_os = "macos")]
         {
@@ -32,6 +60,8 @@ impl WindowManager {
                 ns_window.setTitlebarAppearsTransparent_(cocoa::base::YES);
             }
         }
+        #[cfg(not(target_os = "macos"))]
+        let _ = window;
         Ok(())
     }
 
@@ -58,6 +88,8 @@ impl WindowManager {
                 ns_window.setTitlebarAppearsTransparent_(cocoa::base::YES);
             }
         }
+        #[cfg(not(target_os = "macos"))]
+        let _ = window;
         Ok(())
     }
 }
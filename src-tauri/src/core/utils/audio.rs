@@ -1,7 +1,16 @@
-use std::fs;
-use std::path::PathBuf;
+#[cfg(feature = "mp3")]
+use mp3lame_encoder::{FlushNoGap, MonoPcm};
+use std::fs::{self, File};
+#[cfg(feature = "mp3")]
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tauri::{AppHandle, Manager};
 
+use super::flac;
+use crate::core::{config::AudioFormat, error::AudioError};
+
 const RECORDINGS_PATH_TYPE: &str = "app_data";
 
 pub fn get_recordings_path(app_handle: &AppHandle) -> PathBuf {
@@ -25,3 +34,167 @@ pub fn get_recordings_path(app_handle: &AppHandle) -> PathBuf {
 
     recordings_path
 }
+
+/// What `validate_wav` found in a recording.
+#[derive(Debug, Clone)]
+pub struct WavInfo {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub duration: Duration,
+    /// Highest absolute sample level, 0.0–1.0.
+    pub peak: f32,
+}
+
+/// Checks that `path` is a readable WAV holding at least `min_duration` of
+/// audio that peaks above `silence_threshold`. Short or silent recordings
+/// fail with `AudioError::NoSpeech`, so callers can skip transcription
+/// instead of feeding the model noise it will hallucinate text for.
+pub fn validate_wav(
+    path: &Path,
+    min_duration: Duration,
+    silence_threshold: f32,
+) -> Result<WavInfo, AudioError> {
+    let mut reader = hound::WavReader::open(path)
+        .map_err(|e| AudioError::Recording(format!("Invalid recording: {}", e)))?;
+    let spec = reader.spec();
+    if spec.channels == 0 || spec.sample_rate == 0 {
+        return Err(AudioError::Recording(format!(
+            "Invalid recording: {} channels at {} Hz",
+            spec.channels, spec.sample_rate
+        )));
+    }
+
+    let read_error = |e: hound::Error| AudioError::Recording(format!("Invalid recording: {}", e));
+    let peak = match spec.sample_format {
+        hound::SampleFormat::Float => reader
+            .samples::<f32>()
+            .try_fold(0f32, |max, s| s.map(|s| max.max(s.abs())))
+            .map_err(read_error)?,
+        hound::SampleFormat::Int => {
+            let full_scale = (1i64 << (spec.bits_per_sample.clamp(1, 32) - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .try_fold(0f32, |max, s| s.map(|s| max.max(s.unsigned_abs() as f32)))
+                .map_err(read_error)?
+                / full_scale
+        }
+    };
+
+    let frames = reader.duration();
+    let info = WavInfo {
+        sample_rate: spec.sample_rate,
+        channels: spec.channels,
+        duration: Duration::from_secs_f64(frames as f64 / spec.sample_rate as f64),
+        peak: peak.min(1.0),
+    };
+
+    if info.duration < min_duration {
+        return Err(AudioError::NoSpeech(format!(
+            "recording is only {} ms long",
+            info.duration.as_millis()
+        )));
+    }
+    if info.peak < silence_threshold {
+        return Err(AudioError::NoSpeech(format!(
+            "recording peaks at {:.3}, below the silence threshold",
+            info.peak
+        )));
+    }
+
+    Ok(info)
+}
+
+/// Stores the WAV recording at `wav_path` as `dest` in `format`, replacing the
+/// extension of `dest` with the format's. Returns the path written.
+pub fn save_recording(
+    wav_path: &Path,
+    dest: &Path,
+    format: AudioFormat,
+) -> Result<PathBuf, AudioError> {
+    let dest = dest.with_extension(format.extension());
+
+    if format == AudioFormat::Wav {
+        fs::copy(wav_path, &dest)
+            .map_err(|e| AudioError::Recording(format!("Failed to copy recording: {}", e)))?;
+        return Ok(dest);
+    }
+
+    let mut reader = hound::WavReader::open(wav_path)
+        .map_err(|e| AudioError::Recording(format!("Failed to read recording: {}", e)))?;
+    let spec = reader.spec();
+    if spec.channels != 1 || spec.bits_per_sample != 16 {
+        return Err(AudioError::Recording(format!(
+            "Expected 16-bit mono audio, got {} channels at {} bits",
+            spec.channels, spec.bits_per_sample
+        )));
+    }
+    let samples = reader
+        .samples::<i16>()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AudioError::Recording(format!("Failed to read recording: {}", e)))?;
+
+    let file = File::create(&dest)
+        .map_err(|e| AudioError::Recording(format!("Failed to create {:?}: {}", dest, e)))?;
+    let result = match format {
+        AudioFormat::Flac => write_flac(file, &samples, spec.sample_rate),
+        AudioFormat::Mp3 => write_mp3(file, &samples, spec.sample_rate),
+        AudioFormat::Wav => unreachable!(),
+    };
+
+    if result.is_err() {
+        let _ = fs::remove_file(&dest);
+    }
+    result.map(|_| dest)
+}
+
+fn write_flac(mut file: File, samples: &[i16], sample_rate: u32) -> Result<(), AudioError> {
+    file.write_all(&flac::encode_mono_16(samples, sample_rate))
+        .and_then(|_| file.flush())
+        .map_err(|e| AudioError::Recording(format!("Failed to write FLAC: {}", e)))
+}
+
+#[cfg(not(feature = "mp3"))]
+fn write_mp3(_file: File, _samples: &[i16], _sample_rate: u32) -> Result<(), AudioError> {
+    Err(AudioError::Recording(
+        "This build doesn't include MP3 support".to_string(),
+    ))
+}
+
+#[cfg(feature = "mp3")]
+fn write_mp3(file: File, samples: &[i16], sample_rate: u32) -> Result<(), AudioError> {
+    let encode_error =
+        |e: &dyn std::fmt::Debug| AudioError::Recording(format!("Failed to encode MP3: {:?}", e));
+
+    let mut builder = mp3lame_encoder::Builder::new()
+        .ok_or_else(|| AudioError::Recording("Failed to create MP3 encoder".to_string()))?;
+    builder.set_num_channels(1).map_err(|e| encode_error(&e))?;
+    builder
+        .set_sample_rate(sample_rate)
+        .map_err(|e| encode_error(&e))?;
+    // Plenty for speech at 16 kHz
+    builder
+        .set_brate(mp3lame_encoder::Bitrate::Kbps64)
+        .map_err(|e| encode_error(&e))?;
+    builder
+        .set_quality(mp3lame_encoder::Quality::Good)
+        .map_err(|e| encode_error(&e))?;
+    let mut encoder = builder.build().map_err(|e| encode_error(&e))?;
+
+    let mut mp3 = Vec::with_capacity(mp3lame_encoder::max_required_buffer_size(samples.len()));
+    let written = encoder
+        .encode(MonoPcm(samples), mp3.spare_capacity_mut())
+        .map_err(|e| encode_error(&e))?;
+    // SAFETY: the encoder initialized `written` bytes of the spare capacity
+    unsafe { mp3.set_len(mp3.len() + written) };
+    let written = encoder
+        .flush::<FlushNoGap>(mp3.spare_capacity_mut())
+        .map_err(|e| encode_error(&e))?;
+    // SAFETY: as above, for the flushed tail
+    unsafe { mp3.set_len(mp3.len() + written) };
+
+    let mut writer = BufWriter::new(file);
+    writer
+        .write_all(&mp3)
+        .and_then(|_| writer.flush())
+        .map_err(|e| AudioError::Recording(format!("Failed to write MP3: {}", e)))
+}
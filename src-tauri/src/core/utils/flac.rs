@@ -0,0 +1,396 @@
+//! Minimal FLAC encoder for 16-bit mono recordings. Each block uses the best
+//! fixed predictor (order 0–4) with partitioned Rice residuals, which gets
+//! most of libFLAC's compression on speech without a native dependency.
+
+/// Samples per frame. 4096 is libFLAC's default at 16 kHz.
+const BLOCK_SIZE: usize = 4096;
+const MAX_FIXED_ORDER: usize = 4;
+const MAX_PARTITION_ORDER: u32 = 6;
+/// Largest parameter the 4-bit Rice coding method can signal (15 means escape).
+const MAX_RICE_PARAM: u32 = 14;
+const BITS_PER_SAMPLE: u32 = 16;
+
+/// Encodes `samples` as a complete FLAC file.
+pub fn encode_mono_16(samples: &[i16], sample_rate: u32) -> Vec<u8> {
+    let mut out = BitWriter::default();
+    out.bytes(b"fLaC");
+
+    // STREAMINFO, the only (and so last) metadata block
+    out.bits(1, 1);
+    out.bits(0, 7);
+    out.bits(34, 24);
+    out.bits(BLOCK_SIZE as u64, 16); // min block size, the last may be shorter
+    out.bits(BLOCK_SIZE as u64, 16);
+    out.bits(0, 24); // min frame size, unknown
+    out.bits(0, 24); // max frame size, unknown
+    out.bits(sample_rate as u64, 20);
+    out.bits(0, 3); // channels - 1
+    out.bits((BITS_PER_SAMPLE - 1) as u64, 5);
+    out.bits(samples.len() as u64, 36);
+    out.bytes(&[0; 16]); // MD5 of the audio, zero meaning not computed
+
+    let mut bytes = out.finish();
+    for (index, block) in samples.chunks(BLOCK_SIZE).enumerate() {
+        bytes.extend(encode_frame(index as u64, block));
+    }
+    bytes
+}
+
+fn encode_frame(index: u64, block: &[i16]) -> Vec<u8> {
+    let mut out = BitWriter::default();
+    out.bits(0b11_1111_1111_1110, 14); // sync code
+    out.bits(0, 1);
+    out.bits(0, 1); // fixed block size
+    let partial = block.len() != BLOCK_SIZE;
+    out.bits(if partial { 0b0111 } else { 0b1100 }, 4);
+    out.bits(0, 4); // sample rate from STREAMINFO
+    out.bits(0, 4); // mono
+    out.bits(0b100, 3); // 16 bits per sample
+    out.bits(0, 1);
+    out.utf8(index);
+    if partial {
+        out.bits(block.len() as u64 - 1, 16);
+    }
+    let crc = crc8(out.aligned_bytes());
+    out.bits(crc as u64, 8);
+
+    let samples: Vec<i32> = block.iter().map(|&s| s as i32).collect();
+    encode_subframe(&mut out, &samples);
+
+    let mut bytes = out.finish();
+    let crc = crc16(&bytes);
+    bytes.extend(crc.to_be_bytes());
+    bytes
+}
+
+fn encode_subframe(out: &mut BitWriter, samples: &[i32]) {
+    out.bits(0, 1);
+    if samples.iter().all(|&s| s == samples[0]) {
+        out.bits(0b000000, 6);
+        out.bits(0, 1);
+        out.signed(samples[0], BITS_PER_SAMPLE);
+        return;
+    }
+
+    let verbatim_bits = samples.len() as u64 * BITS_PER_SAMPLE as u64;
+    let fixed = (0..=MAX_FIXED_ORDER.min(samples.len() - 1))
+        .map(|order| {
+            let residuals = fixed_residuals(samples, order);
+            let partitions = best_partitions(&residuals, samples.len(), order);
+            let bits = order as u64 * BITS_PER_SAMPLE as u64 + partitions.bits;
+            (order, residuals, partitions, bits)
+        })
+        .min_by_key(|(_, _, _, bits)| *bits);
+
+    match fixed {
+        Some((order, residuals, partitions, bits)) if bits < verbatim_bits => {
+            out.bits(0b001000 | order as u64, 6);
+            out.bits(0, 1);
+            for &sample in &samples[..order] {
+                out.signed(sample, BITS_PER_SAMPLE);
+            }
+            write_residuals(out, &residuals, order, &partitions);
+        }
+        _ => {
+            out.bits(0b000001, 6);
+            out.bits(0, 1);
+            for &sample in samples {
+                out.signed(sample, BITS_PER_SAMPLE);
+            }
+        }
+    }
+}
+
+/// Residuals of the fixed predictor of `order`, skipping the warm-up samples.
+fn fixed_residuals(samples: &[i32], order: usize) -> Vec<i32> {
+    (order..samples.len())
+        .map(|n| {
+            let s = |k: usize| samples[n - k];
+            match order {
+                0 => s(0),
+                1 => s(0) - s(1),
+                2 => s(0) - 2 * s(1) + s(2),
+                3 => s(0) - 3 * s(1) + 3 * s(2) - s(3),
+                _ => s(0) - 4 * s(1) + 6 * s(2) - 4 * s(3) + s(4),
+            }
+        })
+        .collect()
+}
+
+struct Partitions {
+    order: u32,
+    params: Vec<u32>,
+    bits: u64,
+}
+
+/// Picks the partition order and per-partition Rice parameters that code
+/// `residuals` in the fewest bits.
+fn best_partitions(residuals: &[i32], block_len: usize, predictor_order: usize) -> Partitions {
+    let folded: Vec<u32> = residuals.iter().map(|&r| zigzag(r)).collect();
+
+    let mut best: Option<Partitions> = None;
+    for order in 0..=MAX_PARTITION_ORDER {
+        let partition_len = block_len >> order;
+        if !block_len.is_multiple_of(1 << order) || partition_len <= predictor_order {
+            break;
+        }
+
+        let mut params = Vec::with_capacity(1 << order);
+        let mut bits = 2 + 4; // coding method and partition order
+        let mut start = 0;
+        for partition in 0..1usize << order {
+            let len = if partition == 0 {
+                partition_len - predictor_order
+            } else {
+                partition_len
+            };
+            let (param, param_bits) = best_rice_param(&folded[start..start + len]);
+            params.push(param);
+            bits += 4 + param_bits;
+            start += len;
+        }
+
+        if best.as_ref().is_none_or(|best| bits < best.bits) {
+            best = Some(Partitions {
+                order,
+                params,
+                bits,
+            });
+        }
+    }
+
+    best.expect("partition order 0 always fits")
+}
+
+/// The optimal parameter is within one of log2 of the mean folded residual,
+/// so only those candidates are costed.
+fn best_rice_param(folded: &[u32]) -> (u32, u64) {
+    let mean = folded.iter().map(|&u| u as u64).sum::<u64>() / folded.len().max(1) as u64;
+    let estimate = mean.checked_ilog2().unwrap_or(0).min(MAX_RICE_PARAM);
+    (estimate.saturating_sub(1)..=(estimate + 1).min(MAX_RICE_PARAM))
+        .map(|k| {
+            let bits = folded
+                .iter()
+                .map(|&u| (u >> k) as u64 + 1 + k as u64)
+                .sum::<u64>();
+            (k, bits)
+        })
+        .min_by_key(|(_, bits)| *bits)
+        .unwrap_or((0, 0))
+}
+
+fn write_residuals(
+    out: &mut BitWriter,
+    residuals: &[i32],
+    predictor_order: usize,
+    partitions: &Partitions,
+) {
+    out.bits(0b00, 2); // Rice coding with 4-bit parameters
+    out.bits(partitions.order as u64, 4);
+
+    let partition_len = (residuals.len() + predictor_order) >> partitions.order;
+    let mut start = 0;
+    for (index, &param) in partitions.params.iter().enumerate() {
+        // The warm-up samples count towards the first partition
+        let len = if index == 0 {
+            partition_len - predictor_order
+        } else {
+            partition_len
+        };
+        out.bits(param as u64, 4);
+        for &residual in &residuals[start..start + len] {
+            let u = zigzag(residual);
+            out.unary(u >> param);
+            out.bits((u & ((1 << param) - 1)) as u64, param);
+        }
+        start += len;
+    }
+}
+
+fn zigzag(r: i32) -> u32 {
+    ((r << 1) ^ (r >> 31)) as u32
+}
+
+fn crc8(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |crc, &byte| {
+        (0..8).fold(crc ^ byte, |crc, _| {
+            if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
+
+fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0u16, |crc, &byte| {
+        (0..8).fold(crc ^ ((byte as u16) << 8), |crc, _| {
+            if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x8005
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    acc: u64,
+    len: u32,
+}
+
+impl BitWriter {
+    /// Writes the low `count` bits of `value`, most significant first.
+    fn bits(&mut self, value: u64, count: u32) {
+        for shift in (0..count).rev() {
+            self.acc = (self.acc << 1) | ((value >> shift) & 1);
+            self.len += 1;
+            if self.len == 8 {
+                self.bytes.push(self.acc as u8);
+                self.acc = 0;
+                self.len = 0;
+            }
+        }
+    }
+
+    fn signed(&mut self, value: i32, count: u32) {
+        self.bits(value as u32 as u64, count);
+    }
+
+    fn unary(&mut self, zeros: u32) {
+        for _ in 0..zeros {
+            self.bits(0, 1);
+        }
+        self.bits(1, 1);
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.bits(byte as u64, 8);
+        }
+    }
+
+    /// FLAC's extended UTF-8 coding of frame numbers.
+    fn utf8(&mut self, value: u64) {
+        if value < 0x80 {
+            self.bits(value, 8);
+            return;
+        }
+        let continuation = match value {
+            v if v < 0x800 => 1,
+            v if v < 0x1_0000 => 2,
+            v if v < 0x20_0000 => 3,
+            v if v < 0x400_0000 => 4,
+            v if v < 0x8000_0000 => 5,
+            _ => 6,
+        };
+        let marker = (0xFF00u64 >> (continuation + 1)) & 0xFF;
+        self.bits(marker | (value >> (6 * continuation)), 8);
+        for i in (0..continuation).rev() {
+            self.bits(0x80 | ((value >> (6 * i)) & 0x3F), 8);
+        }
+    }
+
+    /// Bytes written so far; only meaningful on a byte boundary.
+    fn aligned_bytes(&self) -> &[u8] {
+        debug_assert_eq!(self.len, 0);
+        &self.bytes
+    }
+
+    /// Pads with zero bits to the next byte boundary.
+    fn finish(mut self) -> Vec<u8> {
+        if self.len > 0 {
+            self.bits(0, 8 - self.len);
+        }
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use symphonia::core::{
+        audio::SampleBuffer, codecs::DecoderOptions, errors::Error, formats::FormatOptions,
+        io::MediaSourceStream, meta::MetadataOptions, probe::Hint,
+    };
+
+    fn decode(bytes: Vec<u8>) -> (Vec<i16>, u32) {
+        let mss = MediaSourceStream::new(Box::new(std::io::Cursor::new(bytes)), Default::default());
+        let mut hint = Hint::new();
+        hint.with_extension("flac");
+        let mut format = symphonia::default::get_probe()
+            .format(
+                &hint,
+                mss,
+                &FormatOptions::default(),
+                &MetadataOptions::default(),
+            )
+            .expect("encoder output is recognised as FLAC")
+            .format;
+        let track = format.default_track().expect("one track");
+        let sample_rate = track.codec_params.sample_rate.expect("sample rate");
+        let mut decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions { verify: true })
+            .expect("FLAC decoder");
+
+        let mut samples = Vec::new();
+        loop {
+            let packet = match format.next_packet() {
+                Ok(packet) => packet,
+                Err(Error::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                Err(e) => panic!("failed to read frame: {}", e),
+            };
+            let decoded = decoder.decode(&packet).expect("frame decodes");
+            let mut buffer = SampleBuffer::<i16>::new(decoded.capacity() as u64, *decoded.spec());
+            buffer.copy_interleaved_ref(decoded);
+            samples.extend_from_slice(buffer.samples());
+        }
+        (samples, sample_rate)
+    }
+
+    /// Speech-like tones with noise, a silent stretch that takes the constant
+    /// subframe, clipped peaks, and a final partial block.
+    fn test_signal() -> Vec<i16> {
+        let mut noise = 0x2545_f491_u32;
+        (0..3 * BLOCK_SIZE + 1234)
+            .map(|n| {
+                noise ^= noise << 13;
+                noise ^= noise >> 17;
+                noise ^= noise << 5;
+                let t = n as f32 / 16_000.0;
+                match n / BLOCK_SIZE {
+                    1 => 0,
+                    2 if n % 97 == 0 => i16::MIN,
+                    _ => {
+                        let tone = (t * 220.0 * std::f32::consts::TAU).sin() * 9000.0
+                            + (t * 1870.0 * std::f32::consts::TAU).sin() * 3000.0;
+                        tone as i16 + (noise % 512) as i16 - 256
+                    }
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn round_trips_through_a_decoder() {
+        let samples = test_signal();
+        let (decoded, sample_rate) = decode(encode_mono_16(&samples, 16_000));
+
+        assert_eq!(sample_rate, 16_000);
+        assert_eq!(decoded, samples);
+    }
+
+    #[test]
+    fn round_trips_extremes_and_short_input() {
+        let extremes: Vec<i16> = (0..BLOCK_SIZE)
+            .map(|n| if n % 2 == 0 { i16::MAX } else { i16::MIN })
+            .collect();
+        for samples in [extremes, vec![1234], vec![-5, 7, 0, 3, -2]] {
+            let (decoded, _) = decode(encode_mono_16(&samples, 44_100));
+            assert_eq!(decoded, samples);
+        }
+    }
+}
@@ -1,2 +1,5 @@
 pub mod audio;
+pub mod flac;
+pub mod noise_gate;
+pub mod text;
 pub mod updater;
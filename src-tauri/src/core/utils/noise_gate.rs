@@ -0,0 +1,116 @@
+use crate::core::{config::NoiseReduction, error::AudioError};
+
+/// Length of the frames the gate measures, in milliseconds.
+const FRAME_MS: usize = 10;
+/// How quickly the noise floor estimate follows louder frames. Kept slow so
+/// speech doesn't drag the floor up with it.
+const FLOOR_RISE: f32 = 0.002;
+/// How quickly the noise floor estimate follows quieter frames.
+const FLOOR_FALL: f32 = 0.3;
+/// Gain smoothing per frame when opening (fast, so word onsets survive) and
+/// closing (slower, so word endings aren't cut).
+const ATTACK: f32 = 0.6;
+const RELEASE: f32 = 0.08;
+/// Frames quieter than this are never treated as speech.
+const MIN_THRESHOLD: f32 = 0.002;
+
+/// A downward expander: frames that stay close to the recording's noise
+/// floor are attenuated, louder frames pass unchanged. The noise floor is
+/// tracked as the audio streams through, so spilled recordings need no
+/// second pass.
+pub struct NoiseGate {
+    frame_len: usize,
+    /// Frames this many times louder (in RMS) than the floor pass untouched.
+    threshold_ratio: f32,
+    /// Gain applied to frames at or below the floor.
+    min_gain: f32,
+    noise_floor: Option<f32>,
+    gain: f32,
+    pending: Vec<f32>,
+}
+
+impl NoiseGate {
+    /// Returns `None` for `NoiseReduction::Off`.
+    pub fn new(mode: NoiseReduction, sample_rate: u32) -> Option<Self> {
+        let (threshold_ratio, min_gain) = match mode {
+            NoiseReduction::Off => return None,
+            NoiseReduction::Light => (2.0, 0.3),
+            NoiseReduction::Aggressive => (4.0, 0.05),
+        };
+        let frame_len = (sample_rate as usize * FRAME_MS / 1000).max(1);
+
+        Some(Self {
+            frame_len,
+            threshold_ratio,
+            min_gain,
+            noise_floor: None,
+            gain: 1.0,
+            pending: Vec::with_capacity(frame_len * 2),
+        })
+    }
+
+    pub fn push<F>(&mut self, samples: &[f32], out: &mut F) -> Result<(), AudioError>
+    where
+        F: FnMut(&[f32]) -> Result<(), AudioError>,
+    {
+        self.pending.extend_from_slice(samples);
+
+        let whole = self.pending.len() - self.pending.len() % self.frame_len;
+        if whole == 0 {
+            return Ok(());
+        }
+
+        let mut frames = std::mem::take(&mut self.pending);
+        for frame in frames[..whole].chunks_mut(self.frame_len) {
+            self.apply(frame);
+        }
+        self.pending = frames.split_off(whole);
+
+        out(&frames)
+    }
+
+    /// Gates the last partial frame.
+    pub fn finish<F>(mut self, out: &mut F) -> Result<(), AudioError>
+    where
+        F: FnMut(&[f32]) -> Result<(), AudioError>,
+    {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+
+        let mut frame = std::mem::take(&mut self.pending);
+        self.apply(&mut frame);
+        out(&frame)
+    }
+
+    fn apply(&mut self, frame: &mut [f32]) {
+        let rms = (frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32).sqrt();
+
+        let floor = match self.noise_floor {
+            None => rms,
+            Some(floor) if rms < floor => floor + (rms - floor) * FLOOR_FALL,
+            Some(floor) => floor + (rms - floor) * FLOOR_RISE,
+        };
+        self.noise_floor = Some(floor);
+
+        let threshold = (floor * self.threshold_ratio).max(MIN_THRESHOLD);
+        let target = if rms >= threshold {
+            1.0
+        } else {
+            // Fades in from `min_gain` at the floor to unity at the threshold
+            let position = ((rms - floor) / (threshold - floor).max(f32::EPSILON)).clamp(0.0, 1.0);
+            self.min_gain + (1.0 - self.min_gain) * position
+        };
+
+        let smoothing = if target > self.gain { ATTACK } else { RELEASE };
+        let start = self.gain;
+        let end = start + (target - start) * smoothing;
+        self.gain = end;
+
+        // Ramp across the frame so gain changes don't click
+        let step = (end - start) / frame.len() as f32;
+        for (i, sample) in frame.iter_mut().enumerate() {
+            *sample *= start + step * (i + 1) as f32;
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Number of Backspace presses that remove `text`: one per user-perceived
+/// character, so an emoji or an accented letter built from several code
+/// points counts once.
+pub fn grapheme_count(text: &str) -> usize {
+    text.graphemes(true).count()
+}
+
+/// Splits `text` into chunks of at most `max_chars` characters, preferring
+/// paragraph breaks, then sentence ends, then whitespace. Every separator stays
+/// attached to the chunk before it, so concatenating the chunks reproduces the
+/// input exactly. A single word longer than `max_chars` is split mid-word.
+pub fn split_into_chunks(text: &str, max_chars: usize) -> Vec<String> {
+    let max_chars = max_chars.max(1);
+    let mut chunks = Vec::new();
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        if rest.chars().count() <= max_chars {
+            chunks.push(rest.to_string());
+            break;
+        }
+
+        // Byte offset just past the last character that still fits
+        let limit = rest
+            .char_indices()
+            .nth(max_chars)
+            .map(|(i, _)| i)
+            .unwrap_or(rest.len());
+        let window = &rest[..limit];
+
+        let split = last_paragraph_break(window)
+            .or_else(|| last_sentence_end(window))
+            .or_else(|| last_whitespace(window))
+            .unwrap_or(limit);
+
+        let (chunk, remainder) = rest.split_at(split);
+        chunks.push(chunk.to_string());
+        rest = remainder;
+    }
+
+    chunks
+}
+
+/// Separates a chunk into its content and the trailing whitespace that joined
+/// it to the next one, so the content can be rewritten and the layout restored.
+pub fn split_trailing_whitespace(chunk: &str) -> (&str, &str) {
+    let content = chunk.trim_end();
+    (content, &chunk[content.len()..])
+}
+
+fn last_paragraph_break(window: &str) -> Option<usize> {
+    window
+        .rfind("\n\n")
+        .map(|i| i + window[i..].len() - window[i..].trim_start().len())
+        .filter(|&i| i > 0)
+}
+
+fn last_sentence_end(window: &str) -> Option<usize> {
+    window
+        .char_indices()
+        .zip(window.chars().skip(1))
+        .filter(|((_, c), next)| matches!(c, '.' | '!' | '?' | '\n') && next.is_whitespace())
+        .map(|((i, c), _)| i + c.len_utf8())
+        .last()
+        .map(|end| end + window[end..].len() - window[end..].trim_start().len())
+        .filter(|&i| i > 0)
+}
+
+fn last_whitespace(window: &str) -> Option<usize> {
+    window
+        .char_indices()
+        .filter(|(_, c)| c.is_whitespace())
+        .map(|(i, c)| i + c.len_utf8())
+        .last()
+        .filter(|&i| i > 0)
+}
@@ -1,3 +1,14 @@
 pub mod text_generator_prompt;
 pub mod text_intent_detector_prompt;
 pub mod text_transformer_prompt;
+
+/// Appends the user's own instructions for `app_name`, from its app profile.
+pub fn with_app_instructions(prompt: String, app_name: &str, instructions: Option<&str>) -> String {
+    match instructions {
+        Some(instructions) => format!(
+            "{}\n\nAdditional instructions for {}, which take precedence over the above:\n{}",
+            prompt, app_name, instructions
+        ),
+        None => prompt,
+    }
+}
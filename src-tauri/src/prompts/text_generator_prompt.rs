@@ -1,3 +1,4 @@
+use super::with_app_instructions;
 use rune_llm::ToolDefinition;
 use serde_json::json;
 
@@ -26,8 +27,8 @@ impl TextGeneratorPrompt {
         }
     }
 
-    pub fn get_prompt(app_name: &str, text: &str) -> String {
-        format!(
+    pub fn get_prompt(app_name: &str, text: &str, instructions: Option<&str>) -> String {
+        let prompt = format!(
             r#"You are a helpful assistant that generates content based on voice input.
             The following is a voice input recorded in {}:
 
@@ -47,6 +48,7 @@ Instructions:
 Generate the content without any explanations or meta-commentary using tool "generate_content".
 Ensure the output is complete and ready for use in {}."#,
             app_name, text, app_name, app_name, app_name
-        )
+        );
+        with_app_instructions(prompt, app_name, instructions)
     }
 }
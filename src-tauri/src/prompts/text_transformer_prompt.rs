@@ -1,3 +1,4 @@
+use super::with_app_instructions;
 use rune_llm::ToolDefinition;
 use serde_json::json;
 
@@ -22,8 +23,8 @@ impl TextTransformerPrompt {
         }
     }
 
-    pub fn get_prompt(app_name: &str, text: &str) -> String {
-        format!(
+    pub fn get_prompt(app_name: &str, text: &str, instructions: Option<&str>) -> String {
+        let prompt = format!(
             r#"You are a helpful assistant that processes voice input for {}.
             The following is a voice input recorded in {}:
 
@@ -42,6 +43,7 @@ Instructions:
 
 Provide only the corrected and contextually formatted text without any explanations or meta-commentary using tool call."#,
             app_name, app_name, text, app_name
-        )
+        );
+        with_app_instructions(prompt, app_name, instructions)
     }
 }
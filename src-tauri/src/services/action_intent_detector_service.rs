@@ -1,14 +1,22 @@
 use crate::prompts::text_intent_detector_prompt::TextIntentDetectorPrompt;
-use rune_llm::LLMClient;
+use rune_llm::{LLMClient, PromptOptions};
+use std::time::Duration;
 
 pub struct ActionIntentDetectorService;
 
 impl ActionIntentDetectorService {
-    pub async fn detect_intent(llm_client: &LLMClient, text: &str) -> Result<bool, anyhow::Error> {
+    pub async fn detect_intent(
+        llm_client: &LLMClient,
+        text: &str,
+        timeout: Duration,
+    ) -> Result<bool, anyhow::Error> {
         let prompt = TextIntentDetectorPrompt::get_prompt(text);
         let tool = TextIntentDetectorPrompt::get_tool();
 
-        let response = llm_client.execute_prompt(&prompt, vec![tool]).await?;
+        let options = PromptOptions::default().with_timeout(timeout);
+        let response = llm_client
+            .execute_prompt_with_options(&prompt, vec![tool], &options)
+            .await?;
 
         for tool_call in &response.tool_calls {
             if tool_call.name == "detect_action_intent" {
@@ -1,12 +1,41 @@
 use cpal::traits::{DeviceTrait, HostTrait};
 use serde::{Deserialize, Serialize};
+use std::{
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
+use tauri::{AppHandle, Emitter, Manager};
 
-use crate::core::error::AudioError;
+use crate::core::{app::AppState, error::AudioError};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// cpal has no device notifications, so the device list is polled.
+const DEVICE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Common rates checked against each device's supported ranges. Whisper
+/// wants 16kHz; anything else is resampled after recording.
+const STANDARD_SAMPLE_RATES: [u32; 9] =
+    [8000, 11025, 16000, 22050, 32000, 44100, 48000, 88200, 96000];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct AudioDevice {
     pub name: String,
     pub id: String,
+    /// The system's default input device.
+    #[serde(default)]
+    pub default: bool,
+    /// Standard rates, in Hz, the device can record at.
+    #[serde(default)]
+    pub sample_rates: Vec<u32>,
+    #[serde(default)]
+    pub channels: Vec<u16>,
+}
+
+/// Payload of `audio-device-changed`.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceChange {
+    pub devices: Vec<AudioDevice>,
+    pub added: Vec<AudioDevice>,
+    pub removed: Vec<AudioDevice>,
 }
 
 pub struct AudioDeviceService;
@@ -18,26 +47,73 @@ impl AudioDeviceService {
 
     pub fn list_devices(&self) -> Result<Vec<AudioDevice>, AudioError> {
         let host = cpal::default_host();
+        let default_name = host
+            .default_input_device()
+            .and_then(|device| device.name().ok());
+
         Ok(host
             .input_devices()
             .map_err(|e| AudioError::Device(e.to_string()))?
             .filter_map(|device| {
-                device.name().ok().map(|name| AudioDevice {
-                    name: name.clone(),
-                    id: name,
-                })
+                let name = device.name().ok()?;
+                let default = default_name.as_ref() == Some(&name);
+                Some(Self::describe(&device, name, default))
             })
             .collect())
     }
 
+    /// Just the names, which is all the device watcher compares. Querying
+    /// every device's configurations each poll is slow on some hosts.
+    fn list_device_names(&self) -> Result<Vec<String>, AudioError> {
+        let host = cpal::default_host();
+        Ok(host
+            .input_devices()
+            .map_err(|e| AudioError::Device(e.to_string()))?
+            .filter_map(|device| device.name().ok())
+            .collect())
+    }
+
+    fn describe(device: &cpal::Device, name: String, default: bool) -> AudioDevice {
+        let configs: Vec<_> = match device.supported_input_configs() {
+            Ok(configs) => configs.collect(),
+            Err(e) => {
+                log::warn!("Failed to get configs for {}: {}", name, e);
+                Vec::new()
+            }
+        };
+
+        let sample_rates = STANDARD_SAMPLE_RATES
+            .into_iter()
+            .filter(|rate| {
+                configs.iter().any(|config| {
+                    (config.min_sample_rate().0..=config.max_sample_rate().0).contains(rate)
+                })
+            })
+            .collect();
+
+        let mut channels: Vec<u16> = configs.iter().map(|config| config.channels()).collect();
+        channels.sort_unstable();
+        channels.dedup();
+
+        AudioDevice {
+            id: name.clone(),
+            name,
+            default,
+            sample_rates,
+            channels,
+        }
+    }
+
+    pub fn has_input_device(&self) -> bool {
+        self.list_device_names()
+            .is_ok_and(|devices| !devices.is_empty())
+    }
+
     pub fn get_default_device(&self) -> Result<Option<AudioDevice>, AudioError> {
         let host = cpal::default_host();
         match host.default_input_device() {
             Some(device) => match device.name() {
-                Ok(name) => Ok(Some(AudioDevice {
-                    name: name.clone(),
-                    id: name,
-                })),
+                Ok(name) => Ok(Some(Self::describe(&device, name, true))),
                 Err(e) => Err(AudioError::Device(format!(
                     "Failed to get device name: {}",
                     e
@@ -47,6 +123,83 @@ impl AudioDeviceService {
         }
     }
 
+    /// Watches for input devices coming and going for the lifetime of the
+    /// app. Each change emits `audio-device-changed`, and a recording whose
+    /// device disappeared is stopped.
+    pub fn watch_devices(app_handle: AppHandle) {
+        std::thread::spawn(move || {
+            let service = Self::new();
+            let mut known = service.list_devices().unwrap_or_default();
+            let mut known_names: Vec<String> =
+                known.iter().map(|device| device.name.clone()).collect();
+
+            loop {
+                std::thread::sleep(DEVICE_POLL_INTERVAL);
+
+                match service.list_device_names() {
+                    Ok(names) if names == known_names => continue,
+                    Ok(names) => known_names = names,
+                    Err(e) => {
+                        log::warn!("Failed to list audio devices: {}", e);
+                        continue;
+                    }
+                }
+
+                let devices = match service.list_devices() {
+                    Ok(devices) => devices,
+                    Err(e) => {
+                        log::warn!("Failed to list audio devices: {}", e);
+                        continue;
+                    }
+                };
+
+                let change = DeviceChange {
+                    added: devices
+                        .iter()
+                        .filter(|device| !known.iter().any(|known| known.id == device.id))
+                        .cloned()
+                        .collect(),
+                    removed: known
+                        .iter()
+                        .filter(|device| !devices.iter().any(|current| current.id == device.id))
+                        .cloned()
+                        .collect(),
+                    devices: devices.clone(),
+                };
+                known = devices;
+
+                log::info!(
+                    "Audio devices changed, added: {:?}, removed: {:?}",
+                    change.added,
+                    change.removed
+                );
+                Self::stop_recordings_from(&app_handle, &change.removed);
+                if let Some(state) = app_handle.try_state::<Arc<AppState>>() {
+                    state
+                        .input_available
+                        .store(!change.devices.is_empty(), Ordering::SeqCst);
+                }
+                if let Err(e) = app_handle.emit("audio-device-changed", change) {
+                    log::warn!("Failed to emit device change: {}", e);
+                }
+            }
+        });
+    }
+
+    fn stop_recordings_from(app_handle: &AppHandle, removed: &[AudioDevice]) {
+        let Some(state) = app_handle.try_state::<Arc<AppState>>() else {
+            return;
+        };
+        let Some(pipeline) = state.audio_pipeline.lock().clone() else {
+            return;
+        };
+
+        let recording_service = pipeline.get_recording_service();
+        for device in removed {
+            recording_service.handle_device_removed(&device.name);
+        }
+    }
+
     pub fn find_device_by_id(&self, id: &str) -> Result<Option<AudioDevice>, AudioError> {
         let devices = self.list_devices()?;
         Ok(devices.into_iter().find(|device| device.id == id))
@@ -1,29 +1,331 @@
-use crate::core::error::AudioError;
+use crate::core::{
+    app::AppState,
+    config::{AudioConfig, BufferOverflowPolicy, LevelMode, NoiseReduction, NormalizationMode},
+    error::AudioError,
+    state_machine::AppCommand,
+    utils::noise_gate::NoiseGate,
+};
 use cpal::{
     traits::{DeviceTrait, HostTrait, StreamTrait},
     Device, Stream,
 };
-use crossbeam_channel::{bounded, Sender};
+use crossbeam_channel::{bounded, Sender, TrySendError};
 use parking_lot::Mutex;
+use rubato::{FftFixedIn, Resampler};
+use serde::Serialize;
 use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write},
     path::PathBuf,
     sync::Arc,
+    thread::JoinHandle,
     time::{Duration, Instant},
 };
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Manager};
 
 const TARGET_SAMPLE_RATE: u32 = 16000;
-const LEVEL_UPDATE_INTERVAL: Duration = Duration::from_millis(50);
+pub(crate) const LEVEL_UPDATE_INTERVAL: Duration = Duration::from_millis(50);
 const DEFAULT_CHUNK_SIZE: usize = 4096;
 const MIN_CHUNK_SIZE: usize = 1024;
 const PRE_BUFFER_SIZE: usize = 1024;
+const RESAMPLE_CHUNK_SIZE: usize = 1024;
+/// Level the loudest sample is scaled to by peak normalization.
+const NORMALIZATION_TARGET: f32 = 0.95;
+/// Keeps normalization from blowing a silent recording's noise up to full scale.
+const MAX_NORMALIZATION_GAIN: f32 = 20.0;
+
+/// Spilling starts once this fraction of the buffer cap is held in memory,
+/// leaving headroom while the writer thread catches up.
+const SPILL_START_RATIO: f32 = 0.5;
+
+/// Samples moved out of memory under the spill-to-disk policy, stored as raw
+/// little-endian f32 in a temp file. Buffers are handed to a writer thread,
+/// so disk I/O never stalls the thread taking samples off the input stream.
+/// The queue to the writer holds at most `capacity` buffers, so a slow disk
+/// can't grow memory past the cap.
+struct SpillFile {
+    path: PathBuf,
+    sender: Sender<Vec<f32>>,
+    writer: JoinHandle<std::io::Result<File>>,
+}
+
+impl SpillFile {
+    /// `app_handle` is used to stop the recording if writing fails.
+    fn create(app_handle: Option<AppHandle>, capacity: usize) -> std::io::Result<Self> {
+        let path = std::env::temp_dir().join(format!(
+            "rune_recording_{}_{}.spill",
+            std::process::id(),
+            chrono::Utc::now().timestamp_millis()
+        ));
+        let file = File::options()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+
+        let (sender, receiver) = bounded::<Vec<f32>>(capacity);
+        let writer = std::thread::spawn(move || {
+            let mut writer = BufWriter::new(file);
+            let result = receiver.iter().try_for_each(|buffer| {
+                buffer
+                    .iter()
+                    .try_for_each(|sample| writer.write_all(&sample.to_le_bytes()))
+            });
+            if let Err(e) = result {
+                log::error!("Failed to spill audio buffers: {}", e);
+                if let Some(handle) = app_handle.as_ref() {
+                    request_auto_stop(handle, "buffer_limit");
+                }
+                return Err(e);
+            }
+            writer.into_inner().map_err(|e| e.into_error())
+        });
+
+        Ok(Self {
+            path,
+            sender,
+            writer,
+        })
+    }
+
+    /// Queues `buffer` for the writer thread without blocking. Hands it back
+    /// when the queue is full because the writer can't keep up.
+    fn push(&self, buffer: Vec<f32>) -> Result<(), Vec<f32>> {
+        match self.sender.try_send(buffer) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(buffer)) => Err(buffer),
+            // The writer has given up, which already stops the recording
+            Err(TrySendError::Disconnected(_)) => Ok(()),
+        }
+    }
+
+    /// Waits for the writer to catch up, streams the spilled samples back in
+    /// chunks, then deletes the file.
+    fn drain<F>(self, mut on_chunk: F) -> Result<(), AudioError>
+    where
+        F: FnMut(&[f32]) -> Result<(), AudioError>,
+    {
+        let path = self.path.clone();
+        drop(self.sender);
+        let result: Result<(), AudioError> = (|| {
+            let mut file = self
+                .writer
+                .join()
+                .map_err(|_| AudioError::Recording("Spill writer panicked".to_string()))?
+                .map_err(|e| AudioError::Recording(format!("Failed to spill audio: {}", e)))?;
+            file.seek(SeekFrom::Start(0))
+                .map_err(|e| AudioError::Recording(format!("Failed to read spill file: {}", e)))?;
+
+            let mut reader = BufReader::new(file);
+            let mut bytes = vec![0u8; DEFAULT_CHUNK_SIZE * 4];
+            loop {
+                let read = read_full(&mut reader, &mut bytes).map_err(|e| {
+                    AudioError::Recording(format!("Failed to read spill file: {}", e))
+                })?;
+                if read == 0 {
+                    break;
+                }
+                let samples: Vec<f32> = bytes[..read - read % 4]
+                    .chunks_exact(4)
+                    .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                    .collect();
+                on_chunk(&samples)?;
+            }
+            Ok(())
+        })();
+
+        let _ = std::fs::remove_file(&path);
+        result
+    }
+
+    /// Deletes the file once the writer has finished, without waiting for it.
+    fn discard(self) {
+        drop(self.sender);
+        let writer = self.writer;
+        let path = self.path;
+        std::thread::spawn(move || {
+            let _ = writer.join();
+            let _ = std::fs::remove_file(&path);
+        });
+    }
+}
+
+/// Feeds audio through an FFT resampler in fixed-size blocks, so spilled and
+/// in-memory samples can be converted chunk by chunk as they are written.
+struct ChunkResampler {
+    /// `None` when the device already records at the target rate.
+    resampler: Option<FftFixedIn<f32>>,
+    pending: Vec<f32>,
+    output: ResampledOutput,
+}
+
+/// Trims resampled audio to what the input actually covers.
+struct ResampledOutput {
+    from_rate: u32,
+    to_rate: u32,
+    /// Output still to drop to compensate for the filter delay.
+    skip: usize,
+    input_len: usize,
+    output_len: usize,
+}
+
+impl ChunkResampler {
+    fn new(from_rate: u32, to_rate: u32) -> Result<Self, AudioError> {
+        let resampler = if from_rate == to_rate {
+            None
+        } else {
+            let resampler = FftFixedIn::<f32>::new(
+                from_rate as usize,
+                to_rate as usize,
+                RESAMPLE_CHUNK_SIZE,
+                1,
+                1,
+            )
+            .map_err(|e| {
+                AudioError::Recording(format!(
+                    "Cannot resample from {} Hz to {} Hz: {}",
+                    from_rate, to_rate, e
+                ))
+            })?;
+            Some(resampler)
+        };
+
+        Ok(Self {
+            output: ResampledOutput {
+                from_rate,
+                to_rate,
+                skip: resampler.as_ref().map_or(0, |r| r.output_delay()),
+                input_len: 0,
+                output_len: 0,
+            },
+            resampler,
+            pending: Vec::with_capacity(RESAMPLE_CHUNK_SIZE * 2),
+        })
+    }
+
+    fn push<F>(&mut self, samples: &[f32], out: &mut F) -> Result<(), AudioError>
+    where
+        F: FnMut(&[f32]) -> Result<(), AudioError>,
+    {
+        let Some(resampler) = self.resampler.as_mut() else {
+            return out(samples);
+        };
+
+        self.output.input_len += samples.len();
+        self.pending.extend_from_slice(samples);
+
+        let mut offset = 0;
+        while self.pending.len() - offset >= RESAMPLE_CHUNK_SIZE {
+            let block = &self.pending[offset..offset + RESAMPLE_CHUNK_SIZE];
+            let resampled = resampler
+                .process(&[block], None)
+                .map_err(|e| AudioError::Recording(format!("Failed to resample: {}", e)))?;
+            offset += RESAMPLE_CHUNK_SIZE;
+            self.output.emit(&resampled[0], out)?;
+        }
+        self.pending.drain(..offset);
+
+        Ok(())
+    }
+
+    /// Resamples what is left and flushes the filter so the tail of the
+    /// recording isn't lost.
+    fn finish<F>(mut self, out: &mut F) -> Result<(), AudioError>
+    where
+        F: FnMut(&[f32]) -> Result<(), AudioError>,
+    {
+        let Some(resampler) = self.resampler.as_mut() else {
+            return Ok(());
+        };
+
+        if !self.pending.is_empty() {
+            let resampled = resampler
+                .process_partial(Some(&[&self.pending[..]]), None)
+                .map_err(|e| AudioError::Recording(format!("Failed to resample: {}", e)))?;
+            self.output.emit(&resampled[0], out)?;
+        }
+
+        while self.output.output_len < self.output.expected_len() {
+            let resampled = resampler
+                .process_partial::<&[f32]>(None, None)
+                .map_err(|e| AudioError::Recording(format!("Failed to resample: {}", e)))?;
+            if resampled[0].is_empty() {
+                break;
+            }
+            self.output.emit(&resampled[0], out)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ResampledOutput {
+    fn expected_len(&self) -> usize {
+        (self.input_len as u64 * self.to_rate as u64 / self.from_rate as u64) as usize
+    }
 
-#[derive(Default, Clone)]
+    /// Passes resampled audio on, minus the filter delay at the start and any
+    /// padding past the expected length.
+    fn emit<F>(&mut self, samples: &[f32], out: &mut F) -> Result<(), AudioError>
+    where
+        F: FnMut(&[f32]) -> Result<(), AudioError>,
+    {
+        let skip = self.skip.min(samples.len());
+        self.skip -= skip;
+
+        let remaining = self.expected_len().saturating_sub(self.output_len);
+        let samples = &samples[skip..];
+        let samples = &samples[..samples.len().min(remaining)];
+        if samples.is_empty() {
+            return Ok(());
+        }
+
+        self.output_len += samples.len();
+        out(samples)
+    }
+}
+
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut total = 0;
+    while total < buf.len() {
+        match reader.read(&mut buf[total..])? {
+            0 => break,
+            n => total += n,
+        }
+    }
+    Ok(total)
+}
+
+#[derive(Default)]
 struct AudioData {
     recording: bool,
+    /// The stream keeps running while paused, but its samples are dropped.
+    paused: bool,
+    /// Kept across recordings: no recording starts and no samples are kept.
+    muted: bool,
     app_handle: Option<AppHandle>,
     buffers: Vec<Vec<f32>>,
     current_chunk: Vec<f32>,
+    buffered_bytes: usize,
+    max_buffer_bytes: Option<usize>,
+    overflow_policy: BufferOverflowPolicy,
+    limit_reached: bool,
+    spill: Option<SpillFile>,
+    silence_timeout: Option<Duration>,
+    silence_threshold: f32,
+    /// Start of the current run of chunks below `silence_threshold`.
+    silent_since: Option<Instant>,
+    silence_stop_requested: bool,
+    level_mode: LevelMode,
+    input_gain: f32,
+    normalization: NormalizationMode,
+    noise_reduction: NoiseReduction,
+    /// Loudest stored sample, for peak normalization.
+    peak: f32,
+    device_name: Option<String>,
+    /// The input device went away mid-recording; what was captured has a gap
+    /// and is discarded.
+    device_lost: bool,
 }
 
 impl AudioData {
@@ -33,23 +335,132 @@ impl AudioData {
             app_handle: None,
             buffers: Vec::new(),
             current_chunk: Vec::with_capacity(DEFAULT_CHUNK_SIZE),
+            input_gain: AudioConfig::default().input_gain,
+            ..Default::default()
         }
     }
 
-    fn store_samples(&mut self, samples: &[f32]) {
+    /// Stores samples and enforces the memory cap. Returns `true` when the
+    /// recording should stop: the first time the cap is hit under the
+    /// auto-stop policy, or when spilling couldn't start or keep up.
+    fn store_samples(&mut self, samples: &[f32]) -> bool {
+        if self.limit_reached {
+            return false;
+        }
+
         self.current_chunk.extend_from_slice(samples);
+        self.peak = samples.iter().fold(self.peak, |max, &s| max.max(s.abs()));
 
         if self.current_chunk.len() >= DEFAULT_CHUNK_SIZE {
             let full_buffer = std::mem::replace(
                 &mut self.current_chunk,
                 Vec::with_capacity(DEFAULT_CHUNK_SIZE),
             );
+            if let Some(spill) = self.spill.as_ref() {
+                let Err(full_buffer) = spill.push(full_buffer) else {
+                    return false;
+                };
+                // Kept in memory, where it follows the spilled samples
+                log::warn!("Spill writer can't keep up with the input, stopping recording");
+                self.buffered_bytes += full_buffer.len() * std::mem::size_of::<f32>();
+                self.buffers.push(full_buffer);
+                self.limit_reached = true;
+                return true;
+            }
+
+            self.buffered_bytes += full_buffer.len() * std::mem::size_of::<f32>();
             self.buffers.push(full_buffer);
             log::info!(
                 "Chunk complete - Size: {}, Total chunks: {}",
                 DEFAULT_CHUNK_SIZE,
                 self.buffers.len()
             );
+
+            if let Some(max_bytes) = self.max_buffer_bytes {
+                return self.handle_buffer_limit(max_bytes);
+            }
+        }
+
+        false
+    }
+
+    fn handle_buffer_limit(&mut self, max_bytes: usize) -> bool {
+        match self.overflow_policy {
+            BufferOverflowPolicy::SpillToDisk
+                if self.buffered_bytes as f32 > max_bytes as f32 * SPILL_START_RATIO =>
+            {
+                // Once spilling, queued buffers are all that is held in memory,
+                // so the queue gets the whole cap
+                let capacity = (max_bytes / (DEFAULT_CHUNK_SIZE * std::mem::size_of::<f32>()))
+                    .max(self.buffers.len())
+                    .max(1);
+                match SpillFile::create(self.app_handle.clone(), capacity) {
+                    Ok(spill) => {
+                        log::info!("Buffer cap approaching, spilling audio to {:?}", spill.path);
+                        for buffer in std::mem::take(&mut self.buffers) {
+                            // The queue has room for every buffer held so far
+                            let _ = spill.push(buffer);
+                        }
+                        self.buffered_bytes = 0;
+                        self.spill = Some(spill);
+                        false
+                    }
+                    Err(e) => {
+                        log::error!("Failed to create spill file, stopping recording: {}", e);
+                        self.limit_reached = true;
+                        self.overflow_policy = BufferOverflowPolicy::AutoStop;
+                        true
+                    }
+                }
+            }
+            BufferOverflowPolicy::AutoStop if self.buffered_bytes > max_bytes => {
+                log::warn!(
+                    "Buffer cap exceeded ({} bytes), stopping recording",
+                    self.buffered_bytes
+                );
+                self.limit_reached = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Tracks how long the input has stayed below the silence threshold.
+    /// Returns how long the current silence has lasted, if there is one.
+    fn track_silence(&mut self, peak: f32) -> Option<Duration> {
+        self.silence_timeout?;
+
+        if peak >= self.silence_threshold {
+            self.silent_since = None;
+            return None;
+        }
+
+        Some(self.silent_since.get_or_insert_with(Instant::now).elapsed())
+    }
+
+    /// Gain applied when the recording is written out.
+    fn output_gain(&self) -> f32 {
+        match self.normalization {
+            NormalizationMode::Off => self.input_gain,
+            NormalizationMode::Peak if self.peak > 0.0 => {
+                (NORMALIZATION_TARGET / self.peak).min(MAX_NORMALIZATION_GAIN)
+            }
+            NormalizationMode::Peak => self.input_gain,
+        }
+    }
+
+    fn mark_device_lost(&mut self) {
+        if !self.recording || self.device_lost {
+            return;
+        }
+
+        log::warn!(
+            "Input device {:?} disconnected while recording",
+            self.device_name
+        );
+        self.device_lost = true;
+        if let Some(handle) = self.app_handle.as_ref() {
+            request_auto_stop(handle, "device_disconnected");
         }
     }
 
@@ -59,6 +470,89 @@ impl AudioData {
             log::info!("Final chunk added - Total chunks: {}", self.buffers.len());
         }
     }
+
+    fn reset(&mut self) {
+        self.buffers.clear();
+        self.current_chunk.clear();
+        self.buffered_bytes = 0;
+        self.limit_reached = false;
+        self.paused = false;
+        self.peak = 0.0;
+        self.device_lost = false;
+        self.silent_since = None;
+        self.silence_stop_requested = false;
+        if let Some(spill) = self.spill.take() {
+            spill.discard();
+        }
+    }
+}
+
+/// Splits `samples` into 8 bands and measures each for the visualizer.
+pub(crate) fn band_levels(samples: &[f32], mode: LevelMode) -> Vec<f32> {
+    if samples.is_empty() {
+        return vec![0.0; 8];
+    }
+
+    let chunk_size = samples.len() / 8;
+    (0..8)
+        .map(|i| {
+            let chunk = &samples[i * chunk_size..(i + 1) * chunk_size];
+            match mode {
+                LevelMode::Peak => chunk.iter().map(|&s| s.abs()).fold(0f32, f32::max),
+                LevelMode::Rms if chunk.is_empty() => 0.0,
+                LevelMode::Rms => {
+                    (chunk.iter().map(|&s| s * s).sum::<f32>() / chunk.len() as f32).sqrt()
+                }
+            }
+        })
+        .collect()
+}
+
+/// Payload of `audio-levels`: the eight meter bands and the loudest sample
+/// of the block they were taken from, both in 0.0..=1.0.
+#[derive(Debug, Serialize, Clone)]
+pub struct AudioLevels {
+    pub bands: Vec<f32>,
+    pub peak: f32,
+}
+
+impl AudioLevels {
+    pub(crate) fn silent() -> Self {
+        Self {
+            bands: vec![0.0; 8],
+            peak: 0.0,
+        }
+    }
+}
+
+pub(crate) fn emit_audio_levels(handle: &AppHandle, levels: AudioLevels) {
+    if let Err(e) = handle.emit("audio-levels", levels) {
+        log::info!("Failed to emit audio levels: {}", e);
+    }
+}
+
+/// Payload of `audio-silence-detected`, so the frontend can count down to the
+/// automatic stop.
+#[derive(Serialize, Clone)]
+struct SilenceDetected {
+    silent_ms: u64,
+    timeout_ms: u64,
+}
+
+/// Asks the state machine to stop the current recording as if the user had
+/// released the shortcut, so the captured audio is still processed.
+fn request_auto_stop(app_handle: &AppHandle, reason: &str) {
+    log::info!("Requesting automatic stop: {}", reason);
+
+    if let Err(e) = app_handle.emit("recording-auto-stopped", reason) {
+        log::info!("Failed to emit auto-stop event: {}", e);
+    }
+
+    if let Some(state) = app_handle.try_state::<Arc<AppState>>() {
+        if let Some(machine) = &*state.state_machine.lock() {
+            machine.send_command(AppCommand::StopRecording);
+        }
+    }
 }
 
 #[derive(Default)]
@@ -262,6 +756,54 @@ impl AudioRecordingService {
         state.audio_data.lock().app_handle = Some(handle);
     }
 
+    pub fn set_buffer_limit(&self, max_bytes: Option<usize>, policy: BufferOverflowPolicy) {
+        log::info!("Setting buffer limit: {:?} bytes ({:?})", max_bytes, policy);
+        let state = self.state.lock();
+        let mut audio_data = state.audio_data.lock();
+        audio_data.max_buffer_bytes = max_bytes;
+        audio_data.overflow_policy = policy;
+    }
+
+    pub fn set_silence_detection(&self, timeout: Option<Duration>, threshold: f32) {
+        log::info!(
+            "Setting silence auto-stop: {:?} below {}",
+            timeout,
+            threshold
+        );
+        let state = self.state.lock();
+        let mut audio_data = state.audio_data.lock();
+        audio_data.silence_timeout = timeout;
+        audio_data.silence_threshold = threshold;
+    }
+
+    pub fn set_gain(&self, input_gain: f32, normalization: NormalizationMode) {
+        log::info!("Setting input gain: {} ({:?})", input_gain, normalization);
+        let state = self.state.lock();
+        let mut audio_data = state.audio_data.lock();
+        audio_data.input_gain = input_gain;
+        audio_data.normalization = normalization;
+    }
+
+    pub fn set_noise_reduction(&self, noise_reduction: NoiseReduction) {
+        let state = self.state.lock();
+        state.audio_data.lock().noise_reduction = noise_reduction;
+    }
+
+    /// Input device of the current or most recent recording.
+    pub fn device_name(&self) -> Option<String> {
+        self.state.lock().audio_data.lock().device_name.clone()
+    }
+
+    pub fn set_muted(&self, muted: bool) {
+        let state = self.state.lock();
+        state.audio_data.lock().muted = muted;
+    }
+
+    pub fn set_level_mode(&self, mode: LevelMode) {
+        let state = self.state.lock();
+        state.audio_data.lock().level_mode = mode;
+    }
+
     pub fn set_device_id(&self, device_id: Option<String>) {
         log::info!("Setting device ID: {:?}", device_id);
         self.state.lock().device_id = device_id;
@@ -320,7 +862,7 @@ impl AudioRecordingService {
             }
 
             if let Some(device) = found_devices.first() {
-                log::info!(
+                log::warn!(
                     "Device '{}' not found exactly, using first available input device: {}",
                     device_id,
                     device.name().unwrap_or_default()
@@ -343,6 +885,10 @@ impl AudioRecordingService {
         log::info!("=== Starting Recording Process ===");
         log::info!("Audio system: {}", cpal::default_host().id().name());
 
+        if self.state.lock().audio_data.lock().muted {
+            return Err(AudioError::Device("Microphone is muted".to_string()));
+        }
+
         {
             let mut state = self.state.lock();
             if let Some(stream) = state.stream.take() {
@@ -380,13 +926,16 @@ impl AudioRecordingService {
                 return Err(AudioError::Recording("Already recording".to_string()));
             }
             audio_data.recording = true;
-            audio_data.buffers.clear();
-            audio_data.current_chunk.clear();
+            audio_data.reset();
             log::info!("Recording state initialized");
         }
         drop(state);
 
         let device = self.get_input_device().await?;
+        {
+            let state = self.state.lock();
+            state.audio_data.lock().device_name = device.name().ok();
+        }
 
         let supported_configs = device
             .supported_input_configs()
@@ -430,6 +979,7 @@ impl AudioRecordingService {
         state.audio_data.lock().buffers.clear();
         state.audio_data.lock().current_chunk = Vec::with_capacity(chunk_size);
         let audio_data = state.audio_data.clone();
+        let stream_audio_data = state.audio_data.clone();
         drop(state);
 
         let last_level_update_arc = Arc::clone(&self.last_level_update);
@@ -448,6 +998,9 @@ impl AudioRecordingService {
                             log::info!("Recording stopped");
                             continue;
                         }
+                        if audio_data.paused || audio_data.muted {
+                            continue;
+                        }
 
                         let mono_samples: Vec<f32> = if pcm.len() % 2 == 0 && num_channels == 2 {
                             pcm.chunks(2)
@@ -461,24 +1014,16 @@ impl AudioRecordingService {
                             pcm
                         };
 
-                        let levels: Vec<f32> = if !mono_samples.is_empty() {
-                            let chunk_size = mono_samples.len() / 8;
-                            (0..8)
-                                .map(|i| {
-                                    let start = i * chunk_size;
-                                    let end = start + chunk_size;
-                                    let chunk = &mono_samples[start..end];
-                                    chunk
-                                        .iter()
-                                        .map(|&s| s.abs())
-                                        .fold(0f32, |max, val| max.max(val))
-                                })
-                                .collect()
-                        } else {
-                            vec![0.0; 8]
-                        };
+                        let bands = band_levels(&mono_samples, audio_data.level_mode);
 
-                        audio_data.store_samples(&mono_samples);
+                        // Requested once the lock is released
+                        let mut auto_stop = None;
+                        if audio_data.store_samples(&mono_samples) {
+                            auto_stop = Some("buffer_limit");
+                        }
+
+                        let peak = mono_samples.iter().fold(0f32, |max, &s| max.max(s.abs()));
+                        let silence = audio_data.track_silence(peak);
 
                         if let Some(handle) = audio_data.app_handle.as_ref() {
                             let now = Instant::now();
@@ -486,13 +1031,43 @@ impl AudioRecordingService {
                             if now.duration_since(*last_update) >= LEVEL_UPDATE_INTERVAL
                                 || audio_data.buffers.len() < 5
                             {
-                                log::info!("Audio levels: {:?}", levels);
-                                if let Err(e) = handle.emit("audio-levels", levels) {
-                                    log::info!("Failed to emit audio levels: {}", e);
+                                log::info!("Audio levels: {:?}", bands);
+                                emit_audio_levels(
+                                    handle,
+                                    AudioLevels {
+                                        bands,
+                                        peak: peak.min(1.0),
+                                    },
+                                );
+                                if let (Some(silent), Some(timeout)) =
+                                    (silence, audio_data.silence_timeout)
+                                {
+                                    let payload = SilenceDetected {
+                                        silent_ms: silent.as_millis() as u64,
+                                        timeout_ms: timeout.as_millis() as u64,
+                                    };
+                                    if let Err(e) = handle.emit("audio-silence-detected", payload) {
+                                        log::info!("Failed to emit silence event: {}", e);
+                                    }
                                 }
                                 *last_update = now;
                             }
                         }
+
+                        let timed_out = matches!(
+                            (silence, audio_data.silence_timeout),
+                            (Some(silent), Some(timeout)) if silent >= timeout
+                        );
+                        if timed_out && !audio_data.silence_stop_requested {
+                            audio_data.silence_stop_requested = true;
+                            auto_stop = auto_stop.or(Some("silence"));
+                        }
+
+                        let handle = audio_data.app_handle.clone();
+                        drop(audio_data);
+                        if let (Some(reason), Some(handle)) = (auto_stop, handle) {
+                            request_auto_stop(&handle, reason);
+                        }
                     }
                     Err(crossbeam_channel::RecvTimeoutError::Timeout) => continue,
                     Err(crossbeam_channel::RecvTimeoutError::Disconnected) => {
@@ -544,6 +1119,9 @@ impl AudioRecordingService {
 
         let error_callback = move |err| {
             log::info!("Audio input error: {}", err);
+            if let cpal::StreamError::DeviceNotAvailable = err {
+                stream_audio_data.lock().mark_device_lost();
+            }
         };
 
         let stream = device
@@ -579,6 +1157,7 @@ impl AudioRecordingService {
                 return Err(AudioError::Recording("Not currently recording".to_string()));
             }
             audio_data.recording = false;
+            audio_data.paused = false;
         }
 
         let native_sample_rate = {
@@ -604,19 +1183,30 @@ impl AudioRecordingService {
 
         let state = self.state.lock();
         let mut audio_data = state.audio_data.lock();
+        if audio_data.device_lost {
+            audio_data.reset();
+            return Err(AudioError::Device(
+                "Input device was disconnected during recording".to_string(),
+            ));
+        }
         audio_data.finalize();
 
         let buffers = std::mem::take(&mut audio_data.buffers);
+        let spill = audio_data.spill.take();
+        let gain = audio_data.output_gain();
+        let noise_reduction = audio_data.noise_reduction;
         drop(audio_data);
         drop(state);
 
         log::info!(
-            "Stop recording - Buffers: {}, Sample rate: {}",
+            "Stop recording - Buffers: {}, Spilled: {}, Sample rate: {}, Gain: {}",
             buffers.len(),
-            native_sample_rate
+            spill.is_some(),
+            native_sample_rate,
+            gain
         );
 
-        if buffers.is_empty() {
+        if buffers.is_empty() && spill.is_none() {
             return Err(AudioError::Recording("No audio data recorded".to_string()));
         }
 
@@ -626,7 +1216,7 @@ impl AudioRecordingService {
         }
 
         let total_samples = buffers.iter().map(|b| b.len()).sum::<usize>();
-        log::info!("Total samples recorded: {}", total_samples);
+        log::info!("Total in-memory samples recorded: {}", total_samples);
 
         let spec = hound::WavSpec {
             channels: 1,
@@ -639,36 +1229,50 @@ impl AudioRecordingService {
         let mut writer = hound::WavWriter::create(&output_path, spec)
             .map_err(|e| AudioError::Recording(format!("Failed to create WAV file: {}", e)))?;
 
-        let ratio = TARGET_SAMPLE_RATE as f32 / native_sample_rate as f32;
-        log::info!("Using simple resampling with ratio: {}", ratio);
+        log::info!(
+            "Resampling from {} Hz to {} Hz",
+            native_sample_rate,
+            TARGET_SAMPLE_RATE
+        );
+        let mut resampler = ChunkResampler::new(native_sample_rate, TARGET_SAMPLE_RATE)?;
 
         let mut total_written = 0;
 
-        for buffer in buffers.iter() {
-            if buffer.is_empty() {
-                continue;
-            }
+        let mut write_samples = |samples: &[f32]| -> Result<(), AudioError> {
+            for &sample in samples {
+                let normalized = sample * gain;
+                let sample_i16 = (normalized * i16::MAX as f32).clamp(-32768.0, 32767.0) as i16;
 
-            let samples_to_use = buffer;
+                writer
+                    .write_sample(sample_i16)
+                    .map_err(|e| AudioError::Recording(format!("Failed to write sample: {}", e)))?;
+                total_written += 1;
+            }
+            Ok(())
+        };
 
-            let step = 1.0 / ratio;
-            let mut idx = 0.0;
+        let mut gate = NoiseGate::new(noise_reduction, TARGET_SAMPLE_RATE);
+        let mut write_gated = |samples: &[f32]| -> Result<(), AudioError> {
+            match gate.as_mut() {
+                Some(gate) => gate.push(samples, &mut write_samples),
+                None => write_samples(samples),
+            }
+        };
 
-            while idx < samples_to_use.len() as f32 {
-                let i = idx as usize;
-                if i < samples_to_use.len() {
-                    let sample = samples_to_use[i];
-                    let gain = 0.9;
-                    let normalized = sample * gain;
-                    let sample_i16 = (normalized * i16::MAX as f32).clamp(-32768.0, 32767.0) as i16;
+        // Spilled samples precede whatever is still held in memory
+        if let Some(spill) = spill {
+            spill.drain(|chunk| resampler.push(chunk, &mut write_gated))?;
+        }
 
-                    writer.write_sample(sample_i16).map_err(|e| {
-                        AudioError::Recording(format!("Failed to write sample: {}", e))
-                    })?;
-                    total_written += 1;
-                }
-                idx += step;
+        for buffer in buffers.iter() {
+            if buffer.is_empty() {
+                continue;
             }
+            resampler.push(buffer, &mut write_gated)?;
+        }
+        resampler.finish(&mut write_gated)?;
+        if let Some(gate) = gate {
+            gate.finish(&mut write_samples)?;
         }
 
         if total_written == 0 {
@@ -703,6 +1307,57 @@ impl AudioRecordingService {
         Ok(())
     }
 
+    /// Stops keeping samples without tearing down the stream, so resuming
+    /// is instant. Levels stop too, after one flat update for the visualizer.
+    pub fn pause_recording(&self) -> Result<(), AudioError> {
+        let state = self.state.lock();
+        let mut audio_data = state.audio_data.lock();
+        if !audio_data.recording {
+            return Err(AudioError::Recording("Not currently recording".to_string()));
+        }
+        if audio_data.paused {
+            return Ok(());
+        }
+
+        log::info!("Pausing recording");
+        audio_data.paused = true;
+        if let Some(handle) = audio_data.app_handle.as_ref() {
+            emit_audio_levels(handle, AudioLevels::silent());
+        }
+
+        Ok(())
+    }
+
+    pub fn resume_recording(&self) -> Result<(), AudioError> {
+        let state = self.state.lock();
+        let mut audio_data = state.audio_data.lock();
+        if !audio_data.recording {
+            return Err(AudioError::Recording("Not currently recording".to_string()));
+        }
+
+        log::info!("Resuming recording");
+        audio_data.paused = false;
+        // Time spent paused doesn't count towards the silence auto-stop
+        audio_data.silent_since = None;
+        Ok(())
+    }
+
+    /// Stops a recording from `device` after it has been unplugged. The
+    /// recording is reported as failed rather than saved with a gap.
+    pub fn handle_device_removed(&self, device: &str) {
+        let state = self.state.lock();
+        let mut audio_data = state.audio_data.lock();
+        if audio_data.device_name.as_deref() == Some(device) {
+            audio_data.mark_device_lost();
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        let state = self.state.lock();
+        let audio_data = state.audio_data.lock();
+        audio_data.recording && audio_data.paused
+    }
+
     pub async fn stop_recording_without_save(&self) -> Result<(), AudioError> {
         let log_tag = "=== Stopping Recording (No Save) ===";
         log::info!("{}", log_tag);
@@ -718,6 +1373,7 @@ impl AudioRecordingService {
                 return Ok(());
             }
             audio_data.recording = false;
+            audio_data.reset();
             log::info!("Marked recording as stopped");
         }
 
@@ -758,8 +1414,7 @@ impl AudioRecordingService {
             }
             audio_data.recording = false;
 
-            audio_data.buffers.clear();
-            audio_data.current_chunk.clear();
+            audio_data.reset();
         }
 
         {
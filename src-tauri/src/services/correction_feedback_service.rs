@@ -0,0 +1,151 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::{sync::Arc, time::Duration};
+
+use crate::{core::app::AppState, services::text_injector_service::TextInjectorService};
+
+const MAX_FEEDBACK_LOG_ENTRIES: usize = 20;
+
+/// The complete payload sent to the feedback endpoint. It carries only the
+/// injected span and the user's edit of it, so the surrounding document never
+/// leaves the machine. The app is named only if the user allowed it.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CorrectionFeedback {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app_name: Option<String>,
+    pub original: String,
+    pub corrected: String,
+    pub timestamp: String,
+}
+
+pub struct CorrectionFeedbackService;
+
+impl CorrectionFeedbackService {
+    /// Watches the focused text field after an injection and reports the user's
+    /// correction of the injected span if one happens within the configured window.
+    /// Does nothing unless the user has opted in.
+    pub fn watch_injection(state: &Arc<AppState>, app_name: &str, injected: &str) {
+        let config = state.settings.read().feedback.clone();
+        if !config.enabled || injected.trim().is_empty() {
+            return;
+        }
+
+        let state = Arc::clone(state);
+        let app_name = config.include_app_name.then(|| app_name.to_string());
+        let injected = injected.to_string();
+
+        std::thread::spawn(move || {
+            // Give the target app a moment to apply the injected keystrokes
+            std::thread::sleep(Duration::from_millis(500));
+
+            let Some(before) = TextInjectorService::focused_text() else {
+                log::info!("Focused element is not readable, skipping correction tracking");
+                return;
+            };
+
+            std::thread::sleep(Duration::from_secs(config.correction_window_secs));
+
+            // Consent may have been withdrawn while we were waiting
+            if !state.settings.read().feedback.enabled {
+                return;
+            }
+
+            let Some(after) = TextInjectorService::focused_text() else {
+                return;
+            };
+
+            let Some(corrected) = Self::extract_correction(&before, &after, &injected) else {
+                return;
+            };
+
+            let feedback = CorrectionFeedback {
+                app_name,
+                original: injected,
+                corrected,
+                timestamp: Utc::now().to_rfc3339(),
+            };
+
+            Self::submit(&state, config.endpoint, feedback);
+        });
+    }
+
+    /// Returns the payloads reported during this session so users can inspect
+    /// exactly what was sent.
+    pub fn sent_payloads(state: &Arc<AppState>) -> Vec<CorrectionFeedback> {
+        state.feedback_log.lock().iter().cloned().collect()
+    }
+
+    fn submit(state: &Arc<AppState>, endpoint: String, feedback: CorrectionFeedback) {
+        // The text itself stays out of the logs; `sent_payloads` shows it on request
+        log::info!(
+            "Reporting correction feedback: {} chars corrected to {} chars",
+            feedback.original.chars().count(),
+            feedback.corrected.chars().count()
+        );
+
+        {
+            let mut log = state.feedback_log.lock();
+            if log.len() >= MAX_FEEDBACK_LOG_ENTRIES {
+                log.pop_front();
+            }
+            log.push_back(feedback.clone());
+        }
+
+        tauri::async_runtime::spawn(async move {
+            let result = reqwest::Client::new()
+                .post(&endpoint)
+                .json(&feedback)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+
+            if let Err(e) = result {
+                log::warn!("Failed to send correction feedback: {}", e);
+            }
+        });
+    }
+
+    /// Given the field contents right after injection and after the correction
+    /// window, returns the edited version of the injected span. Returns `None` if
+    /// nothing changed, the span can't be located, or the edit touched text
+    /// outside of the injected span.
+    fn extract_correction(before: &str, after: &str, injected: &str) -> Option<String> {
+        if before == after {
+            return None;
+        }
+
+        let span_start = before.rfind(injected)?;
+        let span_tail = before.len() - (span_start + injected.len());
+
+        let prefix = common_prefix_len(before, after);
+        let suffix = common_suffix_len(before, after).min(before.len().min(after.len()) - prefix);
+
+        if prefix < span_start || suffix < span_tail {
+            return None;
+        }
+
+        let corrected = after.get(span_start..after.len() - span_tail)?;
+        if corrected == injected {
+            return None;
+        }
+
+        Some(corrected.to_string())
+    }
+}
+
+fn common_prefix_len(a: &str, b: &str) -> usize {
+    a.char_indices()
+        .zip(b.chars())
+        .find(|((_, x), y)| x != y)
+        .map(|((i, _), _)| i)
+        .unwrap_or_else(|| a.len().min(b.len()))
+}
+
+fn common_suffix_len(a: &str, b: &str) -> usize {
+    a.char_indices()
+        .rev()
+        .zip(b.chars().rev())
+        .find(|((_, x), y)| x != y)
+        .map(|((i, x), _)| a.len() - i - x.len_utf8())
+        .unwrap_or_else(|| a.len().min(b.len()))
+}
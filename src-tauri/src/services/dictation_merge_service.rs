@@ -0,0 +1,77 @@
+use parking_lot::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::core::config::MergeConfig;
+
+/// Where a dictation was injected. Merging only happens while this stays the
+/// same between dictations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeTarget {
+    pub app_name: String,
+    pub window_title: Option<String>,
+}
+
+/// The running paragraph a new dictation is about to join.
+#[derive(Debug, Clone)]
+pub struct MergeContext {
+    pub previous_raw: String,
+    pub previous_injected: String,
+}
+
+struct RunningParagraph {
+    target: MergeTarget,
+    raw: String,
+    injected: String,
+    last_injected_at: Instant,
+}
+
+/// Joins dictations made in quick succession into the same target into one
+/// running paragraph. Lives on `AppState` because the pipeline is rebuilt
+/// after every dictation.
+#[derive(Default)]
+pub struct DictationMergeService {
+    last: Mutex<Option<RunningParagraph>>,
+}
+
+impl DictationMergeService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the running paragraph when a dictation into `target` should
+    /// continue it, and drops it otherwise.
+    pub fn continuation(&self, target: &MergeTarget, config: &MergeConfig) -> Option<MergeContext> {
+        let mut last = self.last.lock();
+        let grace_window = Duration::from_millis(config.grace_window_ms);
+
+        match &*last {
+            Some(paragraph)
+                if config.enabled
+                    && paragraph.target == *target
+                    && paragraph.last_injected_at.elapsed() <= grace_window =>
+            {
+                Some(MergeContext {
+                    previous_raw: paragraph.raw.clone(),
+                    previous_injected: paragraph.injected.clone(),
+                })
+            }
+            _ => {
+                *last = None;
+                None
+            }
+        }
+    }
+
+    pub fn record(&self, target: MergeTarget, raw: String, injected: String) {
+        *self.last.lock() = Some(RunningParagraph {
+            target,
+            raw,
+            injected,
+            last_injected_at: Instant::now(),
+        });
+    }
+
+    pub fn reset(&self) {
+        *self.last.lock() = None;
+    }
+}
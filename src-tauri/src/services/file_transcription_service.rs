@@ -0,0 +1,229 @@
+use serde::Serialize;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tauri::{AppHandle, Emitter};
+
+use crate::{
+    controllers::audio_pipleine_controller::AudioPipelineController,
+    core::{
+        app::AppState,
+        error::{AppError, AudioError},
+    },
+    services::{
+        text_injector_service::TextInjectorService,
+        text_processing_service::{InjectionOutcome, TextProcessingService, ACTIVATE_SETTLE_MS},
+        text_transcript_history_service::TextTranscriptHistoryService,
+    },
+};
+
+/// App profile used for files whose text only goes to the clipboard.
+const CLIPBOARD_APP: &str = "Clipboard";
+
+#[derive(Serialize, Debug, Clone)]
+pub struct FileTranscriptionResult {
+    pub path: PathBuf,
+    pub text: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ExternalTranscription {
+    pub text: String,
+    /// Whether the text was typed into the focused app rather than left on
+    /// the clipboard.
+    pub injected: bool,
+}
+
+#[derive(Serialize, Debug, Clone)]
+struct FileTranscriptionProgress<'a> {
+    path: &'a Path,
+    index: usize,
+    total: usize,
+    status: &'a str,
+}
+
+/// Transcribes audio files the user hands us (drag-drop or file picker). There
+/// is no target app to inject into, so results go to history and the clipboard.
+pub struct FileTranscriptionService;
+
+impl FileTranscriptionService {
+    pub fn transcribe_files(
+        state: &Arc<AppState>,
+        app_handle: &AppHandle,
+        paths: Vec<PathBuf>,
+        transform: bool,
+    ) -> Vec<FileTranscriptionResult> {
+        let total = paths.len();
+        let mut results = Vec::with_capacity(total);
+
+        for (index, path) in paths.into_iter().enumerate() {
+            let result = match Self::transcribe_file(
+                state,
+                app_handle,
+                &path,
+                index,
+                total,
+                transform.then_some(CLIPBOARD_APP),
+            ) {
+                Ok(text) => {
+                    Self::emit_progress(app_handle, &path, index, total, "completed");
+                    FileTranscriptionResult {
+                        path,
+                        text: Some(text),
+                        error: None,
+                    }
+                }
+                Err(e) => {
+                    log::error!("Failed to transcribe {:?}: {}", path, e);
+                    Self::emit_progress(app_handle, &path, index, total, "error");
+                    FileTranscriptionResult {
+                        path,
+                        text: None,
+                        error: Some(e.to_string()),
+                    }
+                }
+            };
+            results.push(result);
+        }
+
+        let combined = results
+            .iter()
+            .filter_map(|r| r.text.as_deref())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        if !combined.is_empty() {
+            if let Err(e) = TextInjectorService::copy_to_clipboard(&combined) {
+                log::error!("Failed to copy transcription to clipboard: {}", e);
+            }
+        }
+
+        results
+    }
+
+    /// Transcribes a single file in any format the decoder reads and, with
+    /// `inject`, types the text into the app that had focus. When our own
+    /// window had focus, or injecting fails, the text is left on the clipboard
+    /// instead.
+    pub fn transcribe_external_file(
+        state: &Arc<AppState>,
+        app_handle: &AppHandle,
+        path: &Path,
+        transform: bool,
+        inject: bool,
+    ) -> Result<ExternalTranscription, AppError> {
+        let own_pid = std::process::id() as i32;
+        let target = inject
+            .then(AudioPipelineController::get_frontmost_app)
+            .flatten()
+            .filter(|app| app.pid != Some(own_pid));
+
+        let app_name = target
+            .as_ref()
+            .map_or(CLIPBOARD_APP, |app| app.name.as_str());
+        let text =
+            Self::transcribe_file(state, app_handle, path, 0, 1, transform.then_some(app_name))
+                .inspect_err(|_| Self::emit_progress(app_handle, path, 0, 1, "error"))?;
+        Self::emit_progress(app_handle, path, 0, 1, "completed");
+
+        if text.is_empty() {
+            return Ok(ExternalTranscription {
+                text,
+                injected: false,
+            });
+        }
+
+        if let Some(app) = &target {
+            AudioPipelineController::activate_app(app);
+            std::thread::sleep(std::time::Duration::from_millis(ACTIVATE_SETTLE_MS));
+
+            match TextProcessingService::inject_text(app_handle, &text) {
+                Ok(outcome) => {
+                    return Ok(ExternalTranscription {
+                        injected: outcome == InjectionOutcome::Injected,
+                        text,
+                    })
+                }
+                Err(e) => log::error!("Failed to inject transcription of {:?}: {}", path, e),
+            }
+        }
+
+        TextInjectorService::copy_to_clipboard(&text)?;
+        Ok(ExternalTranscription {
+            text,
+            injected: false,
+        })
+    }
+
+    /// `process_as` names the app profile to run text processing with;
+    /// `None` keeps the raw transcription.
+    fn transcribe_file(
+        state: &Arc<AppState>,
+        app_handle: &AppHandle,
+        path: &Path,
+        index: usize,
+        total: usize,
+        process_as: Option<&str>,
+    ) -> Result<String, AppError> {
+        if !path.is_file() {
+            return Err(
+                AudioError::Transcription(format!("File not found: {}", path.display())).into(),
+            );
+        }
+
+        Self::emit_progress(app_handle, path, index, total, "transcribing");
+
+        let pipeline =
+            state.audio_pipeline.lock().clone().ok_or_else(|| {
+                AudioError::Transcription("Audio pipeline not initialized".into())
+            })?;
+
+        let segments = pipeline
+            .transcription_service
+            .transcribe(path.to_path_buf())?;
+        let mut text = segments
+            .iter()
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        if let Some(app_name) = process_as.filter(|_| !text.is_empty()) {
+            Self::emit_progress(app_handle, path, index, total, "processing");
+
+            match state.runtime.block_on(TextProcessingService::process_text(
+                state, app_name, &text, true,
+            )) {
+                Ok(processed) => text = processed,
+                Err(e) => log::warn!("Text processing failed, keeping raw transcription: {}", e),
+            }
+        }
+
+        if !text.is_empty() {
+            TextTranscriptHistoryService::save_processed_text(app_handle, &text, None, None)?;
+        }
+
+        Ok(text)
+    }
+
+    fn emit_progress(
+        app_handle: &AppHandle,
+        path: &Path,
+        index: usize,
+        total: usize,
+        status: &str,
+    ) {
+        let progress = FileTranscriptionProgress {
+            path,
+            index,
+            total,
+            status,
+        };
+
+        if let Err(e) = app_handle.emit("file-transcription-progress", progress) {
+            log::warn!("Failed to emit file transcription progress: {}", e);
+        }
+    }
+}
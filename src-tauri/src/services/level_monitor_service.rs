@@ -0,0 +1,156 @@
+use cpal::{
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+    Device, SampleFormat, Stream,
+};
+use crossbeam_channel::{bounded, Sender};
+use parking_lot::Mutex;
+use std::{sync::Arc, time::Instant};
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    core::{app::AppState, error::AudioError},
+    services::audio_recording_service::{
+        band_levels, emit_audio_levels, AudioLevels, LEVEL_UPDATE_INTERVAL,
+    },
+};
+
+/// Opens the selected microphone only to emit `audio-levels`, so the user can
+/// check it and set the gain before recording. Nothing is buffered or written
+/// to disk.
+///
+/// cpal streams aren't `Send` on every platform, so the stream lives on its
+/// own thread for as long as the monitor runs.
+#[derive(Default)]
+pub struct LevelMonitorService {
+    stop: Mutex<Option<Sender<()>>>,
+}
+
+impl LevelMonitorService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Does nothing if the monitor is already running.
+    pub fn start(&self, app_handle: AppHandle) -> Result<(), AudioError> {
+        let mut stop = self.stop.lock();
+        if stop.is_some() {
+            return Ok(());
+        }
+
+        let (stop_tx, stop_rx) = bounded::<()>(1);
+        let (ready_tx, ready_rx) = bounded::<Result<(), AudioError>>(1);
+
+        std::thread::spawn(move || {
+            let stream = match Self::open_stream(&app_handle) {
+                Ok(stream) => stream,
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e));
+                    return;
+                }
+            };
+            let _ = ready_tx.send(Ok(()));
+
+            // Returns once `stop` sends or the service is dropped
+            let _ = stop_rx.recv();
+            drop(stream);
+            emit_audio_levels(&app_handle, AudioLevels::silent());
+            log::info!("Level monitoring stopped");
+        });
+
+        ready_rx
+            .recv()
+            .map_err(|_| AudioError::Device("Level monitor exited unexpectedly".to_string()))??;
+
+        *stop = Some(stop_tx);
+        log::info!("Level monitoring started");
+        Ok(())
+    }
+
+    pub fn stop(&self) {
+        if let Some(stop) = self.stop.lock().take() {
+            let _ = stop.send(());
+        }
+    }
+
+    /// The microphone recordings would use: the one chosen in settings if
+    /// it's connected, otherwise the system default.
+    fn input_device(state: &AppState) -> Result<Device, AudioError> {
+        let host = cpal::default_host();
+        let device_id = state.settings.read().audio.default_device.clone();
+
+        if let Some(device_id) = device_id {
+            let selected = host
+                .input_devices()
+                .map_err(|e| AudioError::Device(e.to_string()))?
+                .find(|device| device.name().is_ok_and(|name| name == device_id));
+            if let Some(device) = selected {
+                return Ok(device);
+            }
+            log::warn!("{} not found, monitoring the default input", device_id);
+        }
+
+        host.default_input_device()
+            .ok_or_else(|| AudioError::Device("No default input device available".to_string()))
+    }
+
+    fn open_stream(app_handle: &AppHandle) -> Result<Stream, AudioError> {
+        let state = Arc::clone(app_handle.state::<Arc<AppState>>().inner());
+        let device = Self::input_device(&state)?;
+
+        let config = device
+            .supported_input_configs()
+            .map_err(|e| AudioError::Device(format!("Error getting supported configs: {}", e)))?
+            .find(|config| config.sample_format() == SampleFormat::F32)
+            .ok_or_else(|| {
+                AudioError::Device("No suitable audio configuration found".to_string())
+            })?;
+        let config = config.with_sample_rate(config.min_sample_rate());
+        let num_channels = config.channels().max(1) as usize;
+
+        let handle = app_handle.clone();
+        let mut block = Vec::new();
+        let mut last_update = Instant::now();
+        let data_callback = move |data: &[f32], _: &cpal::InputCallbackInfo| {
+            block.extend(
+                data.chunks(num_channels)
+                    .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32),
+            );
+            if last_update.elapsed() < LEVEL_UPDATE_INTERVAL {
+                return;
+            }
+
+            // Read on every update so gain changes show up while monitoring
+            let (gain, level_mode) = {
+                let settings = state.settings.read();
+                (settings.audio.input_gain, settings.audio.level_mode)
+            };
+            let samples: Vec<f32> = block
+                .drain(..)
+                .map(|s| (s * gain).clamp(-1.0, 1.0))
+                .collect();
+            let peak = samples.iter().fold(0f32, |max, &s| max.max(s.abs()));
+
+            emit_audio_levels(
+                &handle,
+                AudioLevels {
+                    bands: band_levels(&samples, level_mode),
+                    peak,
+                },
+            );
+            last_update = Instant::now();
+        };
+
+        let error_callback = |err| {
+            log::info!("Level monitor input error: {}", err);
+        };
+
+        let stream = device
+            .build_input_stream(&config.into(), data_callback, error_callback, None)
+            .map_err(|e| AudioError::Recording(format!("Failed to build input stream: {}", e)))?;
+        stream
+            .play()
+            .map_err(|e| AudioError::Recording(format!("Failed to start stream: {}", e)))?;
+
+        Ok(stream)
+    }
+}
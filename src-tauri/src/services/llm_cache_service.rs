@@ -0,0 +1,118 @@
+use parking_lot::Mutex;
+use std::{
+    collections::VecDeque,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Instant,
+};
+
+use crate::core::config::{LlmCacheConfig, ProcessingMode};
+
+/// What a processed dictation depends on besides the model itself. Changing
+/// the provider clears the cache instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LlmCacheKey {
+    app_name: String,
+    mode: ProcessingMode,
+    instructions: Option<String>,
+    transcript: String,
+}
+
+impl LlmCacheKey {
+    /// Dictating the same phrase rarely transcribes with exactly the same
+    /// spacing, so that is ignored. Case is kept, as it changes the output.
+    pub fn new(
+        app_name: &str,
+        mode: ProcessingMode,
+        instructions: Option<&str>,
+        transcript: &str,
+    ) -> Self {
+        Self {
+            app_name: app_name.to_lowercase(),
+            mode,
+            instructions: instructions.map(str::to_string),
+            transcript: normalize_whitespace(transcript),
+        }
+    }
+}
+
+fn normalize_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+struct CachedResponse {
+    key: LlmCacheKey,
+    text: String,
+    stored_at: Instant,
+}
+
+/// Processed text of recent dictations, least recently used first. Lives on
+/// `AppState` so it outlasts the pipeline, which is rebuilt after every
+/// dictation.
+#[derive(Default)]
+pub struct LlmCacheService {
+    entries: Mutex<VecDeque<CachedResponse>>,
+    /// Set for a one-off command, so the next dictation neither reads nor
+    /// fills the cache.
+    bypass_next: AtomicBool,
+}
+
+impl LlmCacheService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, key: &LlmCacheKey, config: &LlmCacheConfig) -> Option<String> {
+        if !config.enabled {
+            return None;
+        }
+
+        let mut entries = self.entries.lock();
+        entries.retain(|entry| entry.stored_at.elapsed() <= config.ttl());
+
+        let position = entries.iter().position(|entry| entry.key == *key)?;
+        let entry = entries.remove(position)?;
+        let text = entry.text.clone();
+        entries.push_back(entry);
+        Some(text)
+    }
+
+    pub fn insert(&self, key: LlmCacheKey, text: &str, config: &LlmCacheConfig) {
+        if !config.enabled {
+            return;
+        }
+
+        let mut entries = self.entries.lock();
+        entries.retain(|entry| entry.key != key);
+        entries.push_back(CachedResponse {
+            key,
+            text: text.to_string(),
+            stored_at: Instant::now(),
+        });
+        while entries.len() > config.max_entries {
+            entries.pop_front();
+        }
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().clear();
+    }
+
+    /// Drops entries that produced or were produced from `text`, for history
+    /// that was deleted or redacted.
+    pub fn forget(&self, text: &str) {
+        let transcript = normalize_whitespace(text);
+        self.entries
+            .lock()
+            .retain(|entry| entry.text != text && entry.key.transcript != transcript);
+    }
+
+    pub fn bypass_next(&self) {
+        self.bypass_next.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether the dictation being processed should skip the cache. Clears
+    /// the request, so only one dictation is affected.
+    pub fn take_bypass(&self) -> bool {
+        self.bypass_next.swap(false, Ordering::SeqCst)
+    }
+}
@@ -0,0 +1,248 @@
+use serde::{Deserialize, Serialize};
+use std::{fmt::Write, sync::Arc, time::Duration};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_store::StoreExt;
+
+use crate::core::{app::AppState, error::SystemError};
+
+const METRICS_FILE: &str = "metrics.json";
+/// How often recorded events are written out. Whatever is left is written
+/// when the app exits.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Aggregate usage counters. Only counts and timings are kept, never any
+/// dictated content, so they are safe to show and export.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct UsageMetrics {
+    pub dictations_started: u64,
+    pub dictations_completed: u64,
+    pub dictations_failed: u64,
+    pub dictations_cancelled: u64,
+    /// Recordings dropped without transcription because they held no speech.
+    #[serde(default)]
+    pub dictations_no_speech: u64,
+    pub transcription_count: u64,
+    pub transcription_ms_total: u64,
+    pub llm_count: u64,
+    pub llm_ms_total: u64,
+    /// A dictation has started and has no outcome yet. Keeps a single
+    /// dictation from being counted twice when several paths report on it.
+    #[serde(skip)]
+    in_flight: bool,
+    /// Changed since they were last written to disk.
+    #[serde(skip)]
+    unsaved: bool,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct MetricsSummary {
+    #[serde(flatten)]
+    pub totals: UsageMetrics,
+    pub avg_transcription_ms: Option<f64>,
+    pub avg_llm_ms: Option<f64>,
+}
+
+/// Where the time of one dictation went, in milliseconds. Stages that didn't
+/// run (such as the LLM in raw mode) are `None`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
+pub struct PipelineTimings {
+    /// From the start of the recording to its stop, pauses included.
+    pub record_ms: Option<u64>,
+    pub transcription_ms: Option<u64>,
+    pub llm_ms: Option<u64>,
+    pub injection_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum MetricsEvent {
+    Started,
+    Completed,
+    Failed,
+    Cancelled,
+    /// The recording was silent, so nothing was transcribed.
+    NoSpeech,
+    Transcribed(Duration),
+    LlmProcessed(Duration),
+}
+
+impl UsageMetrics {
+    fn apply(&mut self, event: MetricsEvent) -> bool {
+        match event {
+            MetricsEvent::Started => {
+                self.dictations_started += 1;
+                self.in_flight = true;
+            }
+            MetricsEvent::Completed
+            | MetricsEvent::Failed
+            | MetricsEvent::Cancelled
+            | MetricsEvent::NoSpeech => {
+                if !std::mem::take(&mut self.in_flight) {
+                    return false;
+                }
+                match event {
+                    MetricsEvent::Completed => self.dictations_completed += 1,
+                    MetricsEvent::Failed => self.dictations_failed += 1,
+                    MetricsEvent::NoSpeech => self.dictations_no_speech += 1,
+                    _ => self.dictations_cancelled += 1,
+                }
+            }
+            MetricsEvent::Transcribed(elapsed) => {
+                self.transcription_count += 1;
+                self.transcription_ms_total += elapsed.as_millis() as u64;
+            }
+            MetricsEvent::LlmProcessed(elapsed) => {
+                self.llm_count += 1;
+                self.llm_ms_total += elapsed.as_millis() as u64;
+            }
+        }
+        true
+    }
+
+    pub fn summary(&self) -> MetricsSummary {
+        let average = |total: u64, count: u64| {
+            if count == 0 {
+                None
+            } else {
+                Some(total as f64 / count as f64)
+            }
+        };
+
+        MetricsSummary {
+            totals: self.clone(),
+            avg_transcription_ms: average(self.transcription_ms_total, self.transcription_count),
+            avg_llm_ms: average(self.llm_ms_total, self.llm_count),
+        }
+    }
+
+    /// Renders the counters in the Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+
+        let _ = writeln!(out, "# HELP rune_dictations_total Dictations by outcome.");
+        let _ = writeln!(out, "# TYPE rune_dictations_total counter");
+        for (outcome, value) in [
+            ("started", self.dictations_started),
+            ("completed", self.dictations_completed),
+            ("failed", self.dictations_failed),
+            ("cancelled", self.dictations_cancelled),
+            ("no_speech", self.dictations_no_speech),
+        ] {
+            let _ = writeln!(
+                out,
+                "rune_dictations_total{{outcome=\"{}\"}} {}",
+                outcome, value
+            );
+        }
+
+        for (name, help, count, total_ms) in [
+            (
+                "rune_transcription_seconds",
+                "Time spent transcribing recordings.",
+                self.transcription_count,
+                self.transcription_ms_total,
+            ),
+            (
+                "rune_llm_seconds",
+                "Time spent in LLM processing.",
+                self.llm_count,
+                self.llm_ms_total,
+            ),
+        ] {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} summary", name);
+            let _ = writeln!(out, "{}_sum {}", name, total_ms as f64 / 1000.0);
+            let _ = writeln!(out, "{}_count {}", name, count);
+        }
+
+        out
+    }
+}
+
+pub struct MetricsService;
+
+impl MetricsService {
+    pub fn load(app_handle: &AppHandle) -> UsageMetrics {
+        app_handle
+            .store(METRICS_FILE)
+            .ok()
+            .and_then(|store| store.get("metrics"))
+            .and_then(|value| serde_json::from_value(value).ok())
+            .unwrap_or_default()
+    }
+
+    /// Updates the counters in memory. They reach the disk with the next
+    /// `flush`, so recording never waits on I/O.
+    pub fn record(app_handle: &AppHandle, event: MetricsEvent) {
+        let Some(state) = app_handle.try_state::<Arc<AppState>>() else {
+            return;
+        };
+
+        let mut metrics = state.metrics.lock();
+        if metrics.apply(event) {
+            metrics.unsaved = true;
+        }
+    }
+
+    /// Writes the counters out if they changed since the last write. Failures
+    /// are only logged and retried with the next flush; metrics must never
+    /// get in the way of a dictation.
+    pub fn flush(app_handle: &AppHandle) {
+        let Some(state) = app_handle.try_state::<Arc<AppState>>() else {
+            return;
+        };
+
+        let snapshot = {
+            let mut metrics = state.metrics.lock();
+            if !std::mem::take(&mut metrics.unsaved) {
+                return;
+            }
+            metrics.clone()
+        };
+
+        if let Err(e) = Self::save(app_handle, &snapshot) {
+            log::warn!("Failed to persist metrics: {}", e);
+            state.metrics.lock().unsaved = true;
+        }
+    }
+
+    /// Flushes the counters every `FLUSH_INTERVAL`.
+    pub fn watch_flush(app_handle: AppHandle) {
+        std::thread::spawn(move || loop {
+            std::thread::sleep(FLUSH_INTERVAL);
+            Self::flush(&app_handle);
+        });
+    }
+
+    pub fn reset(app_handle: &AppHandle, state: &AppState) -> Result<(), SystemError> {
+        let snapshot = {
+            let mut metrics = state.metrics.lock();
+            // Saved below, so nothing is left for the next flush
+            *metrics = UsageMetrics {
+                in_flight: metrics.in_flight,
+                ..Default::default()
+            };
+            metrics.clone()
+        };
+
+        Self::save(app_handle, &snapshot)
+    }
+
+    /// Reports the timings of a finished dictation as `pipeline-metrics`.
+    pub fn emit_timings(app_handle: &AppHandle, timings: &PipelineTimings) {
+        log::info!("Dictation timings: {:?}", timings);
+        if let Err(e) = app_handle.emit("pipeline-metrics", timings) {
+            log::warn!("Failed to emit pipeline metrics: {}", e);
+        }
+    }
+
+    fn save(app_handle: &AppHandle, metrics: &UsageMetrics) -> Result<(), SystemError> {
+        let store = app_handle
+            .store(METRICS_FILE)
+            .map_err(|e| SystemError::General(format!("Failed to access store: {}", e)))?;
+
+        store.set("metrics", serde_json::json!(metrics));
+        store
+            .save()
+            .map_err(|e| SystemError::General(format!("Failed to save metrics: {}", e)))
+    }
+}
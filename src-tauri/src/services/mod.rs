@@ -1,6 +1,14 @@
 pub mod action_intent_detector_service;
 pub mod audio_device_service;
 pub mod audio_recording_service;
+pub mod correction_feedback_service;
+pub mod dictation_merge_service;
+pub mod file_transcription_service;
+pub mod level_monitor_service;
+pub mod llm_cache_service;
+pub mod metrics_service;
+pub mod model_comparison_service;
+pub mod model_download_service;
 pub mod text_generator_service;
 pub mod text_injector_service;
 pub mod text_processing_service;
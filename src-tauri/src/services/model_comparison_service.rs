@@ -0,0 +1,176 @@
+use parking_lot::Mutex;
+use rune_whisper_local::model::WhichModel;
+use serde::Serialize;
+use std::{
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tauri::AppHandle;
+
+use crate::{
+    core::error::AudioError, services::text_transcription_service::TextTranscriptionService,
+};
+
+const MAX_CACHED_MODELS: usize = 2;
+const CACHE_TTL: Duration = Duration::from_secs(300);
+/// How often idle models are checked for expiry.
+const EXPIRY_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ComparisonTranscription {
+    pub model: WhichModel,
+    pub text: Vec<String>,
+    pub load_ms: u64,
+    pub transcribe_ms: u64,
+}
+
+struct CachedModel {
+    model: WhichModel,
+    service: Arc<TextTranscriptionService>,
+    last_used: Instant,
+}
+
+/// Keeps a small, short-lived set of whisper models loaded for side-by-side
+/// comparisons, separate from the model used by the recording pipeline. The
+/// lock only guards the list, so comparisons with different models load and
+/// decode in parallel.
+#[derive(Default)]
+pub struct ModelComparisonService {
+    cache: Mutex<Vec<CachedModel>>,
+}
+
+impl ModelComparisonService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn transcribe(
+        &self,
+        app_handle: &AppHandle,
+        audio_path: PathBuf,
+        model: WhichModel,
+    ) -> Result<ComparisonTranscription, AudioError> {
+        if !audio_path.exists() {
+            return Err(AudioError::Transcription(format!(
+                "Audio file not found: {}",
+                audio_path.display()
+            )));
+        }
+
+        let load_start = Instant::now();
+        let service = match self.cached(model) {
+            Some(service) => service,
+            None => {
+                let service = Arc::new(Self::load_model(app_handle, model)?);
+                self.insert(model, service)
+            }
+        };
+        let load_ms = load_start.elapsed().as_millis() as u64;
+
+        let transcribe_start = Instant::now();
+        let text = service.transcribe(audio_path)?;
+        let transcribe_ms = transcribe_start.elapsed().as_millis() as u64;
+
+        log::info!(
+            "Comparison transcription with {:?}: load {}ms, transcribe {}ms",
+            model,
+            load_ms,
+            transcribe_ms
+        );
+
+        Ok(ComparisonTranscription {
+            model,
+            text,
+            load_ms,
+            transcribe_ms,
+        })
+    }
+
+    /// Frees models that haven't been used within the TTL.
+    pub fn expire(&self) {
+        self.cache.lock().retain(|entry| {
+            let keep = entry.last_used.elapsed() < CACHE_TTL;
+            if !keep {
+                log::info!("Unloading idle comparison model {:?}", entry.model);
+            }
+            keep
+        });
+    }
+
+    /// Expires idle models every minute, so they don't stay loaded until the
+    /// next comparison.
+    pub fn watch_expiry(self: Arc<Self>) {
+        std::thread::spawn(move || loop {
+            std::thread::sleep(EXPIRY_INTERVAL);
+            self.expire();
+        });
+    }
+
+    /// The loaded `model`, if any. Makes room for it otherwise, so loading it
+    /// doesn't briefly take the cache over its limit.
+    fn cached(&self, model: WhichModel) -> Option<Arc<TextTranscriptionService>> {
+        self.expire();
+
+        let mut cache = self.cache.lock();
+        if let Some(entry) = cache.iter_mut().find(|entry| entry.model == model) {
+            entry.last_used = Instant::now();
+            return Some(Arc::clone(&entry.service));
+        }
+
+        Self::evict_to(&mut cache, MAX_CACHED_MODELS - 1);
+        None
+    }
+
+    /// Adds a freshly loaded model, or returns the copy a concurrent
+    /// comparison loaded in the meantime.
+    fn insert(
+        &self,
+        model: WhichModel,
+        service: Arc<TextTranscriptionService>,
+    ) -> Arc<TextTranscriptionService> {
+        let mut cache = self.cache.lock();
+        if let Some(entry) = cache.iter_mut().find(|entry| entry.model == model) {
+            entry.last_used = Instant::now();
+            return Arc::clone(&entry.service);
+        }
+
+        Self::evict_to(&mut cache, MAX_CACHED_MODELS - 1);
+        cache.push(CachedModel {
+            model,
+            service: Arc::clone(&service),
+            last_used: Instant::now(),
+        });
+        service
+    }
+
+    /// Drops the least recently used models until at most `len` remain. A
+    /// model still decoding is freed once that comparison finishes.
+    fn evict_to(cache: &mut Vec<CachedModel>, len: usize) {
+        while cache.len() > len {
+            let Some(oldest) = cache
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(i, _)| i)
+            else {
+                break;
+            };
+            log::info!("Evicting comparison model {:?}", cache[oldest].model);
+            cache.remove(oldest);
+        }
+    }
+
+    fn load_model(
+        app_handle: &AppHandle,
+        model: WhichModel,
+    ) -> Result<TextTranscriptionService, AudioError> {
+        let model_dir =
+            TextTranscriptionService::find_model_dir(app_handle, model).ok_or_else(|| {
+                AudioError::Transcription(format!("Model files for {:?} not found", model))
+            })?;
+
+        log::info!("Loading comparison model {:?} from {:?}", model, model_dir);
+        TextTranscriptionService::new(Some(model_dir), Some(app_handle.clone()))
+    }
+}
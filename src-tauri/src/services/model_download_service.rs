@@ -0,0 +1,133 @@
+use parking_lot::Mutex;
+use rune_whisper_local::{
+    download::{download_model, DownloadProgress},
+    model::WhichModel,
+};
+use serde::Serialize;
+use std::{
+    collections::HashSet,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::core::{app::AppState, state_machine::AppCommand};
+
+/// Byte counts arrive for every chunk; the UI only needs a few updates a second.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Payload of `model-download-progress`.
+#[derive(Serialize, Debug, Clone)]
+struct ModelDownloadProgress<'a> {
+    model: WhichModel,
+    #[serde(flatten)]
+    progress: &'a DownloadProgress,
+}
+
+/// Payload of `model-download-complete`.
+#[derive(Serialize, Debug, Clone)]
+struct ModelDownloadComplete {
+    model: WhichModel,
+    path: PathBuf,
+}
+
+/// Payload of `model-download-failed`.
+#[derive(Serialize, Debug, Clone)]
+struct ModelDownloadFailed {
+    model: WhichModel,
+    error: String,
+}
+
+/// Fetches stock models that aren't bundled or already downloaded into the
+/// app data directory, so the installer doesn't have to carry the weights.
+#[derive(Default)]
+pub struct ModelDownloadService {
+    active: Mutex<HashSet<WhichModel>>,
+}
+
+impl ModelDownloadService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Where downloaded models go; `TextTranscriptionService::find_model_dir`
+    /// looks here too.
+    pub fn models_dir() -> Option<PathBuf> {
+        dirs::data_dir().map(|p| p.join("rune/models"))
+    }
+
+    /// Downloads `model` on a background thread, unless it is already being
+    /// downloaded, and reloads the pipeline once it arrives if it is the
+    /// model in use. Returns whether a download was started.
+    pub fn start(self: &Arc<Self>, app_handle: AppHandle, model: WhichModel) -> bool {
+        let Some(models_dir) = Self::models_dir() else {
+            log::error!("No data directory to download {:?} into", model);
+            return false;
+        };
+        if !self.active.lock().insert(model) {
+            return false;
+        }
+
+        log::info!("Downloading {:?} into {:?}", model, models_dir);
+        let service = Arc::clone(self);
+        std::thread::spawn(move || {
+            let mut last_emit: Option<(usize, Instant)> = None;
+            let result = download_model(model, &models_dir, |progress| {
+                let due = last_emit.is_none_or(|(file_index, at)| {
+                    file_index != progress.file_index || at.elapsed() >= PROGRESS_INTERVAL
+                });
+                if !due {
+                    return;
+                }
+                last_emit = Some((progress.file_index, Instant::now()));
+
+                let payload = ModelDownloadProgress { model, progress };
+                if let Err(e) = app_handle.emit("model-download-progress", payload) {
+                    log::warn!("Failed to emit model download progress: {}", e);
+                }
+            });
+            service.active.lock().remove(&model);
+
+            match result {
+                Ok(path) => {
+                    log::info!("Downloaded {:?} to {:?}", model, path);
+                    let complete = ModelDownloadComplete { model, path };
+                    if let Err(e) = app_handle.emit("model-download-complete", complete) {
+                        log::warn!("Failed to emit model download completion: {}", e);
+                    }
+                    Self::reload_if_selected(&app_handle, model);
+                }
+                Err(e) => {
+                    log::error!("Failed to download {:?}: {:#}", model, e);
+                    let failed = ModelDownloadFailed {
+                        model,
+                        error: format!("{:#}", e),
+                    };
+                    if let Err(e) = app_handle.emit("model-download-failed", failed) {
+                        log::warn!("Failed to emit model download failure: {}", e);
+                    }
+                }
+            }
+        });
+
+        true
+    }
+
+    fn reload_if_selected(app_handle: &AppHandle, model: WhichModel) {
+        let Some(state) = app_handle.try_state::<Arc<AppState>>() else {
+            return;
+        };
+
+        let selected = {
+            let settings = state.settings.read();
+            settings.transcription.active_custom_model.is_none()
+                && settings.transcription.whisper_model == model
+        };
+        if selected {
+            if let Some(machine) = &*state.state_machine.lock() {
+                machine.send_command(AppCommand::PurgeResources);
+            }
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use crate::prompts::text_generator_prompt::TextGeneratorPrompt;
-use rune_llm::LLMClient;
+use rune_llm::{LLMClient, PromptOptions};
 
 pub struct TextGeneratorService;
 
@@ -8,11 +8,15 @@ impl TextGeneratorService {
         llm_client: &LLMClient,
         app_name: &str,
         text: &str,
+        instructions: Option<&str>,
+        options: &PromptOptions,
     ) -> Result<String, anyhow::Error> {
-        let prompt = TextGeneratorPrompt::get_prompt(app_name, text);
+        let prompt = TextGeneratorPrompt::get_prompt(app_name, text, instructions);
         let tool = TextGeneratorPrompt::get_tool();
 
-        let response = llm_client.execute_prompt(&prompt, vec![tool]).await?;
+        let response = llm_client
+            .execute_prompt_with_options(&prompt, vec![tool], options)
+            .await?;
 
         for tool_call in &response.tool_calls {
             if tool_call.name == "generate_text" {
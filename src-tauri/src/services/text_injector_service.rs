@@ -1,5 +1,61 @@
-use crate::core::error::{AppError, SystemError};
-use enigo::{Enigo, Keyboard, Settings};
+use crate::core::{
+    error::{AppError, SystemError},
+    system::platform,
+    utils::text::split_into_chunks,
+};
+use clipboard::{ClipboardContext, ClipboardProvider};
+use enigo::{Direction, Enigo, Key, Keyboard, Settings};
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// Outcome of a test injection. Verification fields are `None` when the target
+/// app doesn't expose its text through accessibility.
+#[derive(Serialize, Debug, Clone)]
+pub struct InjectionReport {
+    pub app_name: Option<String>,
+    pub sample_chars: usize,
+    pub elapsed_ms: u64,
+    pub chars_per_sec: f64,
+    pub verified: Option<bool>,
+    pub dropped_chars: Option<usize>,
+}
+
+/// How long the target app gets to read the clipboard after the paste
+/// shortcut before the previous contents are put back.
+const PASTE_SETTLE_MS: u64 = 150;
+
+/// Snapshots the clipboard text and puts it back when dropped, so it is
+/// restored however injection ends, panics included. Only text is kept; the
+/// clipboard crate can't read images.
+pub struct ClipboardGuard {
+    ctx: Option<ClipboardContext>,
+    previous: Option<String>,
+}
+
+impl ClipboardGuard {
+    pub fn snapshot() -> Self {
+        let mut ctx: Option<ClipboardContext> = match ClipboardProvider::new() {
+            Ok(ctx) => Some(ctx),
+            Err(e) => {
+                log::warn!("Clipboard unavailable, it won't be restored: {}", e);
+                None
+            }
+        };
+        let previous = ctx.as_mut().and_then(|ctx| ctx.get_contents().ok());
+
+        Self { ctx, previous }
+    }
+}
+
+impl Drop for ClipboardGuard {
+    fn drop(&mut self) {
+        if let (Some(ctx), Some(previous)) = (self.ctx.as_mut(), self.previous.take()) {
+            if let Err(e) = ctx.set_contents(previous) {
+                log::warn!("Failed to restore clipboard: {}", e);
+            }
+        }
+    }
+}
 
 pub struct TextInjectorService;
 
@@ -10,4 +66,136 @@ impl TextInjectorService {
             .text(text)
             .map_err(|e| AppError::System(SystemError::General(e.to_string())))
     }
+
+    /// Types long text in chunks with a short pause in between; some apps drop
+    /// keystrokes when a large burst arrives at once.
+    pub fn inject_text_chunked(
+        text: &str,
+        max_chars: usize,
+        delay_ms: u64,
+    ) -> Result<(), AppError> {
+        let chunks = split_into_chunks(text, max_chars);
+        if chunks.len() > 1 {
+            log::info!(
+                "Injecting {} chars in {} chunks",
+                text.chars().count(),
+                chunks.len()
+            );
+        }
+
+        for (i, chunk) in chunks.iter().enumerate() {
+            if i > 0 {
+                std::thread::sleep(Duration::from_millis(delay_ms));
+            }
+            Self::inject_text(chunk)?;
+        }
+
+        Ok(())
+    }
+
+    /// Pastes `text` through the clipboard, which is faster than typing and
+    /// keeps characters that keystrokes can't produce. Leaves `text` on the
+    /// clipboard; hold a `ClipboardGuard` to put the user's contents back.
+    pub fn paste_text(text: &str) -> Result<(), AppError> {
+        Self::copy_to_clipboard(text)?;
+        Self::send_command_shortcut('v')?;
+
+        std::thread::sleep(Duration::from_millis(PASTE_SETTLE_MS));
+        Ok(())
+    }
+
+    /// Sends the platform undo shortcut to the focused app.
+    pub fn undo() -> Result<(), AppError> {
+        Self::send_command_shortcut('z')
+    }
+
+    /// Presses `key` with Cmd on macOS and Ctrl elsewhere.
+    fn send_command_shortcut(key: char) -> Result<(), AppError> {
+        let mut enigo = Enigo::new(&Settings::default())
+            .map_err(|e| AppError::System(SystemError::General(e.to_string())))?;
+
+        let modifier = if cfg!(target_os = "macos") {
+            Key::Meta
+        } else {
+            Key::Control
+        };
+
+        enigo
+            .key(modifier, Direction::Press)
+            .map_err(|e| AppError::System(SystemError::General(e.to_string())))?;
+        let result = enigo
+            .key(Key::Unicode(key), Direction::Click)
+            .map_err(|e| AppError::System(SystemError::General(e.to_string())));
+        // Always let go of the modifier, or it stays stuck for the user
+        enigo
+            .key(modifier, Direction::Release)
+            .map_err(|e| AppError::System(SystemError::General(e.to_string())))?;
+
+        result
+    }
+
+    /// Presses Backspace `count` times. Each press removes a whole grapheme,
+    /// so size `count` with `grapheme_count` rather than `chars().count()`.
+    pub fn delete_backward(count: usize) -> Result<(), AppError> {
+        let mut enigo = Enigo::new(&Settings::default())
+            .map_err(|e| AppError::System(SystemError::General(e.to_string())))?;
+
+        for _ in 0..count {
+            enigo
+                .key(Key::Backspace, Direction::Click)
+                .map_err(|e| AppError::System(SystemError::General(e.to_string())))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn copy_to_clipboard(text: &str) -> Result<(), AppError> {
+        let mut ctx: ClipboardContext = ClipboardProvider::new()
+            .map_err(|e| AppError::System(SystemError::General(e.to_string())))?;
+        ctx.set_contents(text.to_string())
+            .map_err(|e| AppError::System(SystemError::General(e.to_string())))
+    }
+
+    /// Reads the value of the focused text element in the frontmost app.
+    /// Returns `None` when the element is not a text field or cannot be read.
+    pub fn focused_text() -> Option<String> {
+        platform::current().focused_text()
+    }
+
+    /// Types `sample` into whatever is currently focused and measures how it went.
+    pub fn test_injection(
+        app_name: Option<String>,
+        sample: &str,
+    ) -> Result<InjectionReport, AppError> {
+        let before = Self::focused_text();
+
+        let start = Instant::now();
+        Self::inject_text(sample)?;
+        let elapsed = start.elapsed();
+
+        // Let the target app finish processing the keystrokes before reading back
+        std::thread::sleep(Duration::from_millis(300));
+        let after = Self::focused_text();
+
+        let sample_chars = sample.chars().count();
+        let (verified, dropped_chars) = match (before, after) {
+            (Some(before), Some(after)) => {
+                let inserted = after.chars().count().saturating_sub(before.chars().count());
+                (
+                    Some(after.contains(sample)),
+                    Some(sample_chars.saturating_sub(inserted)),
+                )
+            }
+            _ => (None, None),
+        };
+
+        Ok(InjectionReport {
+            app_name,
+            sample_chars,
+            elapsed_ms: elapsed.as_millis() as u64,
+            chars_per_sec: sample_chars as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+            verified,
+            dropped_chars,
+        })
+    }
 }
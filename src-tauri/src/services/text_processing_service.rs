@@ -1,12 +1,40 @@
+use crate::controllers::audio_pipleine_controller::AudioPipelineController;
 use crate::core::app::AppState;
+use crate::core::config::{ChunkingConfig, InjectionMethod, ProcessingMode};
+use crate::core::system::{permission_manager::PermissionManager, platform::FrontmostApp};
+use crate::core::utils::text::{grapheme_count, split_into_chunks};
 use crate::services::{
-    text_generator_service::TextGeneratorService, text_injector_service::TextInjectorService,
+    llm_cache_service::LlmCacheKey,
+    text_generator_service::TextGeneratorService,
+    text_injector_service::{ClipboardGuard, TextInjectorService},
     text_transformation_service::TextTransformationService,
 };
-use std::sync::Arc;
+use anyhow::anyhow;
+use rune_llm::Message;
+use std::{sync::Arc, time::Duration};
+use tauri::{AppHandle, Manager};
 
 use super::action_intent_detector_service::ActionIntentDetectorService;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InjectionOutcome {
+    Injected,
+    /// Accessibility access was missing, so the text was left on the clipboard.
+    CopiedToClipboard,
+}
+
+/// Enough about the last injection to take it back out again.
+#[derive(Debug, Clone)]
+pub struct LastInjection {
+    pub app: Option<FrontmostApp>,
+    /// Backspaces needed to remove typed text, see `grapheme_count`.
+    pub graphemes: usize,
+    pub pasted: bool,
+}
+
+/// Time for the target app to come to the front before keys are sent to it.
+pub(crate) const ACTIVATE_SETTLE_MS: u64 = 200;
+
 pub struct TextProcessingService;
 
 impl TextProcessingService {
@@ -14,35 +42,206 @@ impl TextProcessingService {
         state: &Arc<AppState>,
         app_name: &str,
         text: &str,
+        use_cache: bool,
     ) -> Result<String, anyhow::Error> {
+        let (options, timeouts, max_chars, history_turns, cache_config, profile) = {
+            let settings = state.settings.read();
+            (
+                settings.llm.prompt_options(),
+                settings.llm.timeouts.clone(),
+                settings.chunking.llm_chunk_chars,
+                settings.llm.history_turns,
+                settings.llm.cache.clone(),
+                settings.app_profile(app_name),
+            )
+        };
+        let instructions = profile.system_prompt.as_deref();
+
+        if profile.mode == ProcessingMode::Raw {
+            log::info!("Raw mode for {}, skipping text processing", app_name);
+            return Ok(text.to_string());
+        }
+
+        let cache_key = LlmCacheKey::new(app_name, profile.mode, instructions, text);
+        let cached = use_cache
+            .then(|| state.llm_cache.get(&cache_key, &cache_config))
+            .flatten();
+        if let Some(cached) = cached {
+            log::info!("Reusing processed text of an identical dictation");
+            Self::remember(state, text, &cached, history_turns);
+            return Ok(cached);
+        }
+
         let llm_client = state.llm.lock();
 
+        // Intent lives in how the dictation starts, so only the head is needed
+        let head = split_into_chunks(text, max_chars)
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+
         let action_required = match &*llm_client {
-            Some(client) => ActionIntentDetectorService::detect_intent(client, text).await?,
+            Some(_) if profile.mode == ProcessingMode::Transform => false,
+            Some(_) if profile.mode == ProcessingMode::Generate => true,
+            Some(client) => {
+                ActionIntentDetectorService::detect_intent(client, &head, timeouts.action_checker())
+                    .await?
+            }
             None => return Err(anyhow::anyhow!("LLM client not initialized")),
         };
 
         let processed_text = if action_required {
             log::info!("Action required, generating text");
+            let options = options
+                .clone()
+                .with_history(state.llm_history.lock().iter().cloned().collect())
+                .with_timeout(timeouts.text_generator());
             match &*llm_client {
-                Some(client) => TextGeneratorService::generate(client, app_name, text).await?,
+                Some(client) => {
+                    TextGeneratorService::generate(client, app_name, text, instructions, &options)
+                        .await?
+                }
                 None => return Err(anyhow::anyhow!("LLM client not initialized")),
             }
         } else {
             log::info!("No action required, transforming text");
+            let options = options.with_timeout(timeouts.text_transformer());
             match &*llm_client {
                 Some(client) => {
-                    TextTransformationService::transform(client, app_name, text).await?
+                    TextTransformationService::transform_chunked(
+                        client,
+                        app_name,
+                        text,
+                        instructions,
+                        &options,
+                        max_chars,
+                    )
+                    .await?
                 }
                 None => return Err(anyhow::anyhow!("LLM client not initialized")),
             }
         };
 
+        // Carrying out an instruction depends on context the key doesn't cover,
+        // such as earlier dictations, so only cleaned-up text is reused
+        if use_cache && !action_required {
+            state
+                .llm_cache
+                .insert(cache_key, &processed_text, &cache_config);
+        }
+
+        Self::remember(state, text, &processed_text, history_turns);
         Ok(processed_text)
     }
 
-    pub fn inject_text(text: &str) -> Result<(), anyhow::Error> {
-        TextInjectorService::inject_text(text)?;
+    /// Keeps the last `turns` dictations for `PromptOptions::history`.
+    fn remember(state: &AppState, text: &str, processed_text: &str, turns: usize) {
+        let mut history = state.llm_history.lock();
+        history.push_back(Message::user(text));
+        history.push_back(Message::assistant(processed_text));
+        while history.len() > turns * 2 {
+            history.pop_front();
+        }
+    }
+
+    pub fn inject_text(
+        app_handle: &AppHandle,
+        text: &str,
+    ) -> Result<InjectionOutcome, anyhow::Error> {
+        let (chunking, method) = app_handle
+            .try_state::<Arc<AppState>>()
+            .map(|state| {
+                let settings = state.settings.read();
+                (settings.chunking.clone(), settings.injection_method)
+            })
+            .unwrap_or_default();
+
+        if Self::accessibility_granted() {
+            let pasted = Self::should_paste(method, text, &chunking);
+            let result = {
+                let _clipboard = ClipboardGuard::snapshot();
+                if pasted {
+                    TextInjectorService::paste_text(text)
+                } else {
+                    TextInjectorService::inject_text_chunked(
+                        text,
+                        chunking.injection_chunk_chars,
+                        chunking.injection_delay_ms,
+                    )
+                }
+            };
+
+            match result {
+                Ok(_) => {
+                    if let Some(state) = app_handle.try_state::<Arc<AppState>>() {
+                        *state.last_injection.lock() = Some(LastInjection {
+                            app: AudioPipelineController::get_frontmost_app(),
+                            graphemes: grapheme_count(text),
+                            pasted,
+                        });
+                    }
+                    return Ok(InjectionOutcome::Injected);
+                }
+                // Permission may have been revoked between the check and the keystrokes
+                Err(e) if Self::accessibility_granted() => return Err(e.into()),
+                Err(e) => log::warn!("Injection failed without accessibility access: {}", e),
+            }
+        }
+
+        TextInjectorService::copy_to_clipboard(text)?;
+        PermissionManager::handle_accessibility_revoked(app_handle);
+        Ok(InjectionOutcome::CopiedToClipboard)
+    }
+
+    /// Takes the last injected text back out of the app it went into. A paste
+    /// is a single undo step there; typed text is undone in word-sized steps
+    /// by most apps, so it is deleted grapheme by grapheme instead. Each
+    /// injection can only be undone once.
+    pub fn undo_last_injection(app_handle: &AppHandle) -> Result<(), anyhow::Error> {
+        let state = app_handle
+            .try_state::<Arc<AppState>>()
+            .ok_or_else(|| anyhow!("App state unavailable"))?;
+        let last = state
+            .last_injection
+            .lock()
+            .take()
+            .ok_or_else(|| anyhow!("Nothing to undo"))?;
+        // The next dictation must not merge with text that is no longer there
+        state.dictation_merge.reset();
+
+        // The request may come from one of our own windows
+        if let Some(app) = &last.app {
+            AudioPipelineController::activate_app(app);
+            std::thread::sleep(Duration::from_millis(ACTIVATE_SETTLE_MS));
+        }
+
+        log::info!(
+            "Undoing injection of {} graphemes into {:?}",
+            last.graphemes,
+            last.app.as_ref().map(|app| &app.name)
+        );
+        if last.pasted {
+            TextInjectorService::undo()?;
+        } else {
+            TextInjectorService::delete_backward(last.graphemes)?;
+        }
+
         Ok(())
     }
+
+    /// `Auto` pastes whenever typing would be slow (more than one chunk) or
+    /// lossy (anything outside ASCII, such as emoji).
+    fn should_paste(method: InjectionMethod, text: &str, chunking: &ChunkingConfig) -> bool {
+        match method {
+            InjectionMethod::Keystroke => false,
+            InjectionMethod::ClipboardPaste => true,
+            InjectionMethod::Auto => {
+                !text.is_ascii() || text.chars().count() > chunking.injection_chunk_chars
+            }
+        }
+    }
+
+    fn accessibility_granted() -> bool {
+        PermissionManager::check_accessibility_permissions().unwrap_or(true)
+    }
 }
@@ -1,28 +1,110 @@
-use chrono::Utc;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
 use rand::Rng;
 use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 use tauri::{AppHandle, Emitter, Manager};
 use tauri_plugin_store::StoreExt;
 
-use crate::core::error::AudioError;
+use crate::{
+    core::{
+        app::AppState,
+        config::{AudioFormat, HistoryConfig},
+        error::AudioError,
+        utils::audio::{get_recordings_path, save_recording},
+    },
+    services::metrics_service::PipelineTimings,
+};
 
 const HISTORY_FILE: &str = "transcription_history.json";
+pub const DEFAULT_SEARCH_PAGE_SIZE: usize = 50;
+pub const MAX_SEARCH_PAGE_SIZE: usize = 200;
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TranscriptionHistory {
     pub id: u32,
     pub timestamp: String,
     pub text: String,
+    #[serde(default)]
+    pub timings: Option<PipelineTimings>,
+    /// File name of the kept audio in the recordings directory.
+    #[serde(default)]
+    pub recording: Option<String>,
+    /// The text and audio were removed on request; only the metadata is left.
+    #[serde(default)]
+    pub redacted: bool,
+}
+
+/// The audio a dictation was transcribed from.
+pub struct RecordingSource<'a> {
+    pub wav_path: &'a Path,
+    pub device: Option<String>,
+}
+
+/// Written next to each kept recording so the audio can be identified
+/// without the history store.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RecordingMetadata {
+    pub id: u32,
+    pub created_at: String,
+    /// Length of the audio in seconds.
+    pub duration: f64,
+    pub device: Option<String>,
+    pub sample_rate: u32,
+    pub transcript: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct HistoryMatch {
+    #[serde(flatten)]
+    pub entry: TranscriptionHistory,
+    /// `(start, end)` of each hit in `text`, in UTF-16 code units so the UI
+    /// can slice the string directly.
+    pub matches: Vec<(usize, usize)>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct HistoryPage {
+    /// Matching entries across all pages.
+    pub total: usize,
+    pub entries: Vec<HistoryMatch>,
 }
 
 pub struct TextTranscriptHistoryService;
 
 impl TextTranscriptHistoryService {
-    pub fn save_processed_text(app_handle: &AppHandle, text: &str) -> Result<(), AudioError> {
+    pub fn save_processed_text(
+        app_handle: &AppHandle,
+        text: &str,
+        timings: Option<PipelineTimings>,
+        recording: Option<RecordingSource>,
+    ) -> Result<(), AudioError> {
+        let id = Self::generate_id();
+        let created_at = Utc::now();
+
+        let keep_recordings = app_handle
+            .try_state::<Arc<AppState>>()
+            .is_some_and(|state| state.settings.read().audio.keep_recordings);
+
+        // The text is worth keeping even when its audio can't be
+        let recording = recording.filter(|_| keep_recordings).and_then(|source| {
+            Self::keep_recording(app_handle, id, created_at, text, source)
+                .map_err(|e| log::error!("Failed to keep recording: {}", e))
+                .ok()
+        });
+
         let new_entry = TranscriptionHistory {
-            id: Self::generate_id(),
-            timestamp: Utc::now().to_rfc3339(),
+            id,
+            timestamp: created_at.to_rfc3339(),
             text: text.to_string(),
+            timings,
+            recording,
+            redacted: false,
         };
 
         let store = app_handle
@@ -76,7 +158,266 @@ impl TextTranscriptHistoryService {
         Ok(history)
     }
 
+    /// Finds entries containing every word of `query`, ignoring case and
+    /// word order, newest first. An empty query lists everything.
+    pub fn search_history(
+        app_handle: &AppHandle,
+        query: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<HistoryPage, AudioError> {
+        let terms: Vec<Vec<char>> = query
+            .split_whitespace()
+            .map(|term| term.chars().map(fold_case).collect())
+            .collect();
+
+        let found: Vec<HistoryMatch> = Self::get_transcription_history(app_handle)?
+            .into_iter()
+            .rev()
+            .filter_map(|entry| {
+                let matches = find_matches(&entry.text, &terms)?;
+                Some(HistoryMatch { entry, matches })
+            })
+            .collect();
+
+        Ok(HistoryPage {
+            total: found.len(),
+            entries: found
+                .into_iter()
+                .skip(offset)
+                .take(limit.min(MAX_SEARCH_PAGE_SIZE))
+                .collect(),
+        })
+    }
+
+    /// Removes an entry along with its kept audio.
+    pub fn delete_entry(app_handle: &AppHandle, id: u32) -> Result<(), AudioError> {
+        let mut history = Self::get_transcription_history(app_handle)?;
+        let index = history
+            .iter()
+            .position(|entry| entry.id == id)
+            .ok_or_else(|| AudioError::Transcription(format!("No history entry {}", id)))?;
+
+        let entry = history.remove(index);
+        Self::remove_recording(app_handle, &entry);
+        Self::forget_processed_text(app_handle, &entry.text);
+        Self::write_history(app_handle, &history)
+    }
+
+    /// Blanks an entry's text and removes its audio, which holds the same
+    /// words, keeping the time and timings.
+    pub fn redact_entry(app_handle: &AppHandle, id: u32) -> Result<(), AudioError> {
+        let mut history = Self::get_transcription_history(app_handle)?;
+        let entry = history
+            .iter_mut()
+            .find(|entry| entry.id == id)
+            .ok_or_else(|| AudioError::Transcription(format!("No history entry {}", id)))?;
+
+        Self::remove_recording(app_handle, entry);
+        Self::forget_processed_text(app_handle, &entry.text);
+        entry.text.clear();
+        entry.recording = None;
+        entry.redacted = true;
+        Self::write_history(app_handle, &history)
+    }
+
+    pub fn clear_all(app_handle: &AppHandle) -> Result<(), AudioError> {
+        for entry in Self::get_transcription_history(app_handle)? {
+            Self::remove_recording(app_handle, &entry);
+        }
+        if let Some(state) = app_handle.try_state::<Arc<AppState>>() {
+            state.llm_cache.clear();
+        }
+        Self::write_history(app_handle, &[])
+    }
+
+    /// Keeps removed text from being replayed out of the LLM cache.
+    fn forget_processed_text(app_handle: &AppHandle, text: &str) {
+        if let Some(state) = app_handle.try_state::<Arc<AppState>>() {
+            state.llm_cache.forget(text);
+        }
+    }
+
+    /// Prunes the history to the configured limits now and every hour after.
+    pub fn watch_retention(app_handle: AppHandle) {
+        std::thread::spawn(move || loop {
+            let config = app_handle
+                .try_state::<Arc<AppState>>()
+                .map(|state| state.settings.read().history.clone())
+                .unwrap_or_default();
+            if let Err(e) = Self::prune(&app_handle, &config) {
+                log::error!("Failed to prune history: {}", e);
+            }
+
+            std::thread::sleep(PRUNE_INTERVAL);
+        });
+    }
+
+    /// Deletes entries older than the retention window, then the oldest ones
+    /// beyond the entry cap, with their audio. Returns how many went.
+    pub fn prune(app_handle: &AppHandle, config: &HistoryConfig) -> Result<usize, AudioError> {
+        let history = Self::get_transcription_history(app_handle)?;
+        let cutoff = config
+            .retention_days
+            .map(|days| Utc::now() - ChronoDuration::days(days.into()));
+
+        // Entries whose time can't be read are kept rather than guessed at
+        let (mut kept, mut pruned): (Vec<_>, Vec<_>) =
+            history.into_iter().partition(|entry| {
+                match (cutoff, DateTime::parse_from_rfc3339(&entry.timestamp)) {
+                    (Some(cutoff), Ok(timestamp)) => timestamp >= cutoff,
+                    _ => true,
+                }
+            });
+
+        // Entries are stored oldest first
+        if let Some(max_entries) = config.max_entries {
+            let excess = kept.len().saturating_sub(max_entries);
+            pruned.extend(kept.drain(..excess));
+        }
+
+        if pruned.is_empty() {
+            return Ok(0);
+        }
+
+        for entry in &pruned {
+            Self::remove_recording(app_handle, entry);
+        }
+        Self::write_history(app_handle, &kept)?;
+
+        log::info!("Pruned {} history entries", pruned.len());
+        Ok(pruned.len())
+    }
+
+    /// Deletes the kept audio of `entry` and its metadata sidecar. Failures
+    /// are logged; the history entry goes either way.
+    fn remove_recording(app_handle: &AppHandle, entry: &TranscriptionHistory) {
+        let Some(file_name) = &entry.recording else {
+            return;
+        };
+
+        let audio_path = get_recordings_path(app_handle).join(file_name);
+        for path in [audio_path.with_extension("json"), audio_path] {
+            if let Err(e) = fs::remove_file(&path) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    log::error!("Failed to delete {:?}: {}", path, e);
+                }
+            }
+        }
+    }
+
+    fn write_history(
+        app_handle: &AppHandle,
+        history: &[TranscriptionHistory],
+    ) -> Result<(), AudioError> {
+        let store = app_handle
+            .store(HISTORY_FILE)
+            .map_err(|e| AudioError::Transcription(format!("Failed to access store: {}", e)))?;
+
+        store.set("transcriptions", serde_json::json!(history));
+        store
+            .save()
+            .map_err(|e| AudioError::Transcription(format!("Failed to save history: {}", e)))?;
+
+        if let Err(e) = app_handle.emit("refresh-history", ()) {
+            log::error!("Failed to emit refresh-history event: {}", e);
+        }
+        Ok(())
+    }
+
+    /// Path of the audio kept for history entry `id`, if any.
+    pub fn recording_path(app_handle: &AppHandle, id: u32) -> Result<PathBuf, AudioError> {
+        let entry = Self::get_transcription_history(app_handle)?
+            .into_iter()
+            .find(|entry| entry.id == id)
+            .ok_or_else(|| AudioError::Recording(format!("No history entry {}", id)))?;
+        let file_name = entry
+            .recording
+            .ok_or_else(|| AudioError::Recording("No audio was kept for this entry".to_string()))?;
+
+        Ok(get_recordings_path(app_handle).join(file_name))
+    }
+
+    /// Copies the recording under a timestamped name, in the configured
+    /// format, with a metadata sidecar. Returns the audio's file name.
+    fn keep_recording(
+        app_handle: &AppHandle,
+        id: u32,
+        created_at: DateTime<Utc>,
+        text: &str,
+        source: RecordingSource,
+    ) -> Result<String, AudioError> {
+        let (spec, len) = hound::WavReader::open(source.wav_path)
+            .map(|reader| (reader.spec(), reader.duration()))
+            .map_err(|e| AudioError::Recording(format!("Failed to read recording: {}", e)))?;
+
+        let format = app_handle
+            .try_state::<Arc<AppState>>()
+            .map(|state| state.settings.read().audio.output_format)
+            .unwrap_or(AudioFormat::Wav);
+        let stem = format!("recording_{}_{}", created_at.format("%Y%m%d_%H%M%S"), id);
+        let dest = get_recordings_path(app_handle).join(&stem);
+        let audio_path = save_recording(source.wav_path, &dest, format)?;
+
+        let metadata = RecordingMetadata {
+            id,
+            created_at: created_at.to_rfc3339(),
+            duration: len as f64 / spec.sample_rate as f64,
+            device: source.device,
+            sample_rate: spec.sample_rate,
+            transcript: text.to_string(),
+        };
+        let sidecar = serde_json::to_string_pretty(&metadata)
+            .map_err(|e| AudioError::Recording(format!("Failed to serialize metadata: {}", e)))?;
+        if let Err(e) = fs::write(dest.with_extension("json"), sidecar) {
+            let _ = fs::remove_file(&audio_path);
+            return Err(AudioError::Recording(format!(
+                "Failed to write recording metadata: {}",
+                e
+            )));
+        }
+
+        log::info!("Kept recording as {:?}", audio_path);
+        Ok(audio_path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or(stem))
+    }
+
     fn generate_id() -> u32 {
         rand::thread_rng().gen()
     }
 }
+
+/// Offsets of every occurrence of each term in `text`, or `None` when a term
+/// doesn't occur at all.
+fn find_matches(text: &str, terms: &[Vec<char>]) -> Option<Vec<(usize, usize)>> {
+    let mut chars = Vec::with_capacity(text.len());
+    let mut offset = 0;
+    for c in text.chars() {
+        chars.push((offset, fold_case(c)));
+        offset += c.len_utf16();
+    }
+
+    let mut matches = vec![];
+    for term in terms {
+        let found = matches.len();
+        for (start, window) in chars.windows(term.len()).enumerate() {
+            if window.iter().map(|(_, c)| c).eq(term.iter()) {
+                let end = chars.get(start + term.len()).map_or(offset, |(o, _)| *o);
+                matches.push((window[0].0, end));
+            }
+        }
+        if matches.len() == found {
+            return None;
+        }
+    }
+
+    matches.sort_unstable();
+    Some(matches)
+}
+
+/// Lowercases one character to one character, so offsets stay aligned.
+fn fold_case(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
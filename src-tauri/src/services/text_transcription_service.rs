@@ -1,37 +1,333 @@
-use rune_whisper_local::{Whisper as WhisperModel, WhisperConfig};
-use std::path::PathBuf;
-use tauri::AppHandle;
+use parking_lot::{Mutex, MutexGuard, RwLock};
+use rune_whisper_local::{
+    decoder::{DecoderError, DecodingStrategy, TranscriptionProgress},
+    model::WhichModel,
+    preferred_device, TranscriptSegment, Whisper as WhisperModel, WhisperConfig,
+};
+use serde::Serialize;
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+use tauri::{path::BaseDirectory, AppHandle, Emitter, Manager};
 
-use crate::core::error::AudioError;
+use crate::{core::error::AudioError, services::model_download_service::ModelDownloadService};
 
+#[derive(Serialize, Debug, Clone)]
+struct TranscriptionProgressPayload {
+    segments_done: usize,
+    segments_total: usize,
+    fraction: f64,
+}
+
+/// Payload of `model-load-progress`, sent as each instance of the pool loads.
+#[derive(Serialize, Debug, Clone)]
+pub struct ModelLoadProgress {
+    pub instances_loaded: usize,
+    pub instances_total: usize,
+}
+
+/// Payload of `model-load-complete`.
+#[derive(Serialize, Debug, Clone)]
+pub struct ModelLoadComplete {
+    pub model_dir: Option<PathBuf>,
+    pub instances: usize,
+}
+
+/// Payload of `model-load-failed`, once every candidate directory failed.
+#[derive(Serialize, Debug, Clone)]
+pub struct ModelLoadFailed {
+    pub error: String,
+}
+
+/// Owns one or more loaded whisper instances. Each instance decodes one file at
+/// a time, so a pool lets independent transcriptions run side by side. The
+/// pool never changes after construction, which keeps status queries lock-free.
 pub struct TextTranscriptionService {
-    model: Option<WhisperModel>,
+    models: Vec<Mutex<WhisperModel>>,
+    strategy: RwLock<DecodingStrategy>,
+    initial_prompt: RwLock<Option<String>>,
+    vad_enabled: AtomicBool,
+    busy: AtomicUsize,
+    next: AtomicUsize,
+}
+
+/// Text of each decoded window, with the average token log-probability across
+/// them as a confidence score. Clear speech scores close to 0; near-silent or
+/// garbled recordings typically fall below -1.
+#[derive(Debug, Clone)]
+pub struct Transcription {
+    pub segments: Vec<String>,
+    pub confidence: f32,
+}
+
+impl Transcription {
+    /// All windows joined into one text, or `None` if nothing was said.
+    pub fn text(&self) -> Option<String> {
+        let text = self
+            .segments
+            .iter()
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        (!text.is_empty()).then_some(text)
+    }
+}
+
+/// A loaded transcriber kept on `AppState`, so rebuilding the pipeline after
+/// each dictation doesn't reload the weights from disk.
+pub struct SharedTranscriber {
+    /// The directory the pipeline asked for, which may differ from the one
+    /// actually loaded when a fallback path was used.
+    pub model_dir: Option<PathBuf>,
+    pub pool_size: usize,
+    pub service: Arc<TextTranscriptionService>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct TranscriberStatus {
+    pub loaded: bool,
+    pub instances: usize,
+    pub busy: usize,
 }
 
 impl TextTranscriptionService {
     pub fn new(
         model_dir: Option<PathBuf>,
-        _app_handle: Option<AppHandle>,
+        app_handle: Option<AppHandle>,
     ) -> Result<Self, AudioError> {
-        let model = if let Some(dir) = model_dir {
-            let config = WhisperConfig::new(Some(dir));
-            Some(WhisperModel::new(config).map_err(|e| AudioError::Transcription(e.to_string()))?)
-        } else {
-            None
-        };
+        Self::with_pool_size(model_dir, app_handle, 1)
+    }
+
+    pub fn with_pool_size(
+        model_dir: Option<PathBuf>,
+        app_handle: Option<AppHandle>,
+        pool_size: usize,
+    ) -> Result<Self, AudioError> {
+        let mut models = Vec::new();
+
+        if let Some(dir) = model_dir {
+            let instances_total = pool_size.max(1);
+            for instances_loaded in 0..instances_total {
+                if let Some(app_handle) = &app_handle {
+                    let progress = ModelLoadProgress {
+                        instances_loaded,
+                        instances_total,
+                    };
+                    if let Err(e) = app_handle.emit("model-load-progress", progress) {
+                        log::warn!("Failed to emit model load progress: {}", e);
+                    }
+                }
 
-        Ok(Self { model })
+                let mut config =
+                    WhisperConfig::new(Some(dir.clone())).with_device(preferred_device());
+                if let Some(app_handle) = app_handle.clone() {
+                    config.progress = Some(Arc::new(move |progress: TranscriptionProgress| {
+                        let payload = TranscriptionProgressPayload {
+                            segments_done: progress.segments_done,
+                            segments_total: progress.segments_total,
+                            fraction: progress.fraction(),
+                        };
+                        if let Err(e) = app_handle.emit("transcription-progress", payload) {
+                            log::warn!("Failed to emit transcription progress: {}", e);
+                        }
+                    }));
+                }
+                let model = WhisperModel::new(config)
+                    .map_err(|e| AudioError::Transcription(e.to_string()))?;
+                models.push(Mutex::new(model));
+            }
+        }
+
+        Ok(Self {
+            models,
+            strategy: RwLock::new(DecodingStrategy::default()),
+            initial_prompt: RwLock::new(None),
+            vad_enabled: AtomicBool::new(false),
+            busy: AtomicUsize::new(0),
+            next: AtomicUsize::new(0),
+        })
+    }
+
+    /// Where the files of a stock model are: bundled with the app, in the
+    /// user's data directory, or next to the working directory in development.
+    pub fn find_model_dir(app_handle: &AppHandle, model: WhichModel) -> Option<PathBuf> {
+        let dir_name = model.dir_name();
+        let candidates = [
+            app_handle
+                .path()
+                .resolve(format!("models/{}", dir_name), BaseDirectory::Resource)
+                .ok(),
+            ModelDownloadService::models_dir().map(|p| p.join(dir_name)),
+            Some(PathBuf::from("./models").join(dir_name)),
+            Some(PathBuf::from("../models").join(dir_name)),
+        ];
+
+        candidates.into_iter().flatten().find(|path| path.exists())
     }
 
-    pub fn transcribe(&mut self, audio_path: PathBuf) -> Result<Vec<String>, AudioError> {
-        let model = self.model.as_mut().ok_or_else(|| {
-            AudioError::Transcription("No model loaded for transcription".to_string())
-        })?;
+    pub fn is_loaded(&self) -> bool {
+        !self.models.is_empty()
+    }
+
+    pub fn status(&self) -> TranscriberStatus {
+        TranscriberStatus {
+            loaded: self.is_loaded(),
+            instances: self.models.len(),
+            busy: self.busy.load(Ordering::SeqCst),
+        }
+    }
+
+    /// Swaps every instance over to the model in `model_dir`, waiting for
+    /// running transcriptions to finish first.
+    pub fn reload_model(&self, model_dir: PathBuf) -> Result<(), AudioError> {
+        if self.models.is_empty() {
+            return Err(AudioError::Transcription(
+                "No model loaded to reload".to_string(),
+            ));
+        }
+
+        for model in &self.models {
+            model
+                .lock()
+                .reload_model(model_dir.clone())
+                .map_err(|e| AudioError::Transcription(format!("Model reload failed: {}", e)))?;
+        }
+
+        Ok(())
+    }
+
+    /// Primes every idle instance so the first dictation isn't slowed by lazy
+    /// kernel setup. Instances already transcribing are warm by then and are
+    /// skipped rather than waited for.
+    pub fn warmup(&self) {
+        for (index, model) in self.models.iter().enumerate() {
+            let Some(mut model) = model.try_lock() else {
+                continue;
+            };
+
+            match model.warmup() {
+                Ok(elapsed) => log::info!("Warmed up model instance {} in {:?}", index, elapsed),
+                Err(e) => log::warn!("Failed to warm up model instance {}: {}", index, e),
+            }
+        }
+    }
+
+    /// Takes effect on the next transcription; never waits on a running decode.
+    pub fn set_decoding_strategy(&self, strategy: DecodingStrategy) -> Result<(), AudioError> {
+        strategy
+            .validate()
+            .map_err(|e| AudioError::Transcription(e.to_string()))?;
+        *self.strategy.write() = strategy;
+        Ok(())
+    }
+
+    /// Like the decoding strategy, applied to each instance on its next use.
+    pub fn set_initial_prompt(&self, initial_prompt: Option<String>) {
+        *self.initial_prompt.write() = initial_prompt;
+    }
+
+    pub fn set_vad_enabled(&self, vad_enabled: bool) {
+        self.vad_enabled.store(vad_enabled, Ordering::SeqCst);
+    }
+
+    pub fn transcribe(&self, audio_path: PathBuf) -> Result<Vec<String>, AudioError> {
+        self.transcribe_cancellable(audio_path, None)
+            .map(|transcription| transcription.segments)
+    }
+
+    /// Like `transcribe`, but stops between decoded windows once `cancel` is
+    /// set, so a cancelled dictation doesn't keep the CPU busy.
+    pub fn transcribe_cancellable(
+        &self,
+        audio_path: PathBuf,
+        cancel: Option<Arc<AtomicBool>>,
+    ) -> Result<Transcription, AudioError> {
+        self.transcribe_segments(audio_path, cancel)
+            .map(|segments| {
+                // No segments means every window was skipped as silence
+                let confidence = if segments.is_empty() {
+                    f32::NEG_INFINITY
+                } else {
+                    segments.iter().map(|s| s.avg_logprob).sum::<f32>() / segments.len() as f32
+                };
+
+                Transcription {
+                    segments: segments.into_iter().map(|s| s.text).collect(),
+                    confidence,
+                }
+            })
+    }
+
+    /// Decoded windows with their timings and scores, as the model returned
+    /// them.
+    pub fn transcribe_segments(
+        &self,
+        audio_path: PathBuf,
+        cancel: Option<Arc<AtomicBool>>,
+    ) -> Result<Vec<TranscriptSegment>, AudioError> {
+        if self.models.is_empty() {
+            return Err(AudioError::Transcription(
+                "No model loaded for transcription".to_string(),
+            ));
+        }
+
+        let mut model = self.acquire_instance();
+        self.busy.fetch_add(1, Ordering::SeqCst);
+
+        let strategy = *self.strategy.read();
+        if model.decoding_strategy() != strategy {
+            log::info!("Switching decoding strategy to {}", strategy);
+            if let Err(e) = model.set_decoding_strategy(strategy) {
+                log::warn!("Keeping current decoding strategy: {}", e);
+            }
+        }
+
+        let initial_prompt = self.initial_prompt.read().clone();
+        if model.initial_prompt() != initial_prompt.as_deref() {
+            model.set_initial_prompt(initial_prompt);
+        }
+        model.set_vad_enabled(self.vad_enabled.load(Ordering::SeqCst));
+
+        model.set_cancel_token(cancel);
+        let transcription_result = model.transcribe_segments(audio_path).map_err(|e| {
+            match e.downcast_ref::<DecoderError>() {
+                Some(DecoderError::Cancelled) => {
+                    AudioError::Transcription("Transcription cancelled".to_string())
+                }
+                None => AudioError::Transcription(format!("Transcription failed: {}", e)),
+            }
+        });
+        model.set_cancel_token(None);
+
+        self.busy.fetch_sub(1, Ordering::SeqCst);
+        transcription_result
+    }
+
+    pub fn detect_language(&self, audio_path: PathBuf) -> Result<String, AudioError> {
+        if self.models.is_empty() {
+            return Err(AudioError::Transcription(
+                "No model loaded for language detection".to_string(),
+            ));
+        }
+
+        let mut model = self.acquire_instance();
+        model
+            .detect_language(audio_path)
+            .map_err(|e| AudioError::Transcription(format!("Language detection failed: {}", e)))
+    }
 
-        let transcription_result = model
-            .transcribe(audio_path.clone())
-            .map_err(|e| AudioError::Transcription(format!("Transcription failed: {}", e)))?;
+    /// Prefers an idle instance and otherwise queues on the instances in turn.
+    fn acquire_instance(&self) -> MutexGuard<'_, WhisperModel> {
+        if let Some(guard) = self.models.iter().find_map(|model| model.try_lock()) {
+            return guard;
+        }
 
-        Ok(transcription_result)
+        let index = self.next.fetch_add(1, Ordering::SeqCst) % self.models.len();
+        self.models[index].lock()
     }
 }
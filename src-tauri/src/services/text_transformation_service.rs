@@ -1,18 +1,61 @@
-use crate::prompts::text_transformer_prompt::TextTransformerPrompt;
-use rune_llm::LLMClient;
+use crate::{
+    core::utils::text::{split_into_chunks, split_trailing_whitespace},
+    prompts::text_transformer_prompt::TextTransformerPrompt,
+};
+use rune_llm::{LLMClient, PromptOptions};
 
 pub struct TextTransformationService;
 
 impl TextTransformationService {
+    /// Transforms text longer than `max_chars` piece by piece so the model
+    /// never sees (or has to return) more than one chunk. Paragraph and
+    /// sentence breaks between chunks are carried over unchanged.
+    pub async fn transform_chunked(
+        llm_client: &LLMClient,
+        app_name: &str,
+        text: &str,
+        instructions: Option<&str>,
+        options: &PromptOptions,
+        max_chars: usize,
+    ) -> Result<String, anyhow::Error> {
+        let chunks = split_into_chunks(text, max_chars);
+        if chunks.len() <= 1 {
+            return Self::transform(llm_client, app_name, text, instructions, options).await;
+        }
+
+        log::info!(
+            "Transforming {} chars in {} chunks",
+            text.chars().count(),
+            chunks.len()
+        );
+
+        let mut output = String::with_capacity(text.len());
+        for chunk in &chunks {
+            let (content, separator) = split_trailing_whitespace(chunk);
+            if !content.trim().is_empty() {
+                let transformed =
+                    Self::transform(llm_client, app_name, content, instructions, options).await?;
+                output.push_str(transformed.trim());
+            }
+            output.push_str(separator);
+        }
+
+        Ok(output)
+    }
+
     pub async fn transform(
         llm_client: &LLMClient,
         app_name: &str,
         text: &str,
+        instructions: Option<&str>,
+        options: &PromptOptions,
     ) -> Result<String, anyhow::Error> {
-        let prompt = TextTransformerPrompt::get_prompt(app_name, text);
+        let prompt = TextTransformerPrompt::get_prompt(app_name, text, instructions);
         let tool = TextTransformerPrompt::get_tool();
 
-        let response = llm_client.execute_prompt(&prompt, vec![tool]).await?;
+        let response = llm_client
+            .execute_prompt_with_options(&prompt, vec![tool], options)
+            .await?;
 
         for tool_call in &response.tool_calls {
             if tool_call.name == "transform_text" {
@@ -7,6 +7,16 @@ type TranscriptionHistory = {
   id: number;
   timestamp: string;
   text: string;
+  timings: PipelineTimings | null;
+  recording: string | null;
+  redacted: boolean;
+};
+
+type PipelineTimings = {
+  record_ms: number | null;
+  transcription_ms: number | null;
+  llm_ms: number | null;
+  injection_ms: number | null;
 };
 
 export const HistoryView = () => {
@@ -8,19 +8,32 @@ import posthog from "posthog-js"; // Make sure you have posthog-js installed
 type ProcessingStatus =
   | "idle"
   | "recording"
+  | "paused"
   | "transcribing"
   | "thinking_action"
   | "generating_text"
   | "completed"
   | "cancelled"
+  | "no_speech"
   | "error";
 
+// How long a notice such as "No speech detected" stays up before the window hides
+const NOTICE_MS = 1500;
+
+interface AudioLevels {
+  bands: number[];
+  peak: number;
+}
+
 function MainWindow() {
   const [levels, setLevels] = useState(new Array(8).fill(0));
   const [processingStatus, setProcessingStatus] =
     useState<ProcessingStatus>("idle");
   const [dotPosition, setDotPosition] = useState(0);
   const [transcript, setTranscript] = useState<string>("");
+  const [transcriptionProgress, setTranscriptionProgress] = useState<
+    number | null
+  >(null);
   const [sessionId, setSessionId] = useState<string>("");
 
   // Generate a unique session ID when the component mounts
@@ -72,6 +85,40 @@ function MainWindow() {
     };
   }, [processingStatus]);
 
+  useEffect(() => {
+    if (processingStatus !== "transcribing") {
+      setTranscriptionProgress(null);
+    }
+  }, [processingStatus]);
+
+  useEffect(() => {
+    if (processingStatus !== "no_speech") return;
+
+    const timeout = window.setTimeout(() => {
+      setProcessingStatus("idle");
+      getCurrentWindow().hide();
+    }, NOTICE_MS);
+
+    return () => clearTimeout(timeout);
+  }, [processingStatus]);
+
+  useEffect(() => {
+    const unlistenProgress = listen("transcription-progress", (event: any) => {
+      const { segments_total, fraction } = event.payload as {
+        segments_total: number;
+        fraction: number;
+      };
+      // A single window finishes at once, so a bar would only jump to 100%
+      if (segments_total > 1) {
+        setTranscriptionProgress(fraction);
+      }
+    });
+
+    return () => {
+      unlistenProgress.then((unlistenFn) => unlistenFn());
+    };
+  }, []);
+
   // Track status changes with PostHog
   useEffect(() => {
     if (processingStatus !== "idle") {
@@ -84,8 +131,8 @@ function MainWindow() {
   }, [processingStatus, sessionId, transcript]);
 
   useEffect(() => {
-    const unlisten = listen("audio-levels", (event: any) => {
-      const newLevels = event.payload as number[];
+    const unlisten = listen<AudioLevels>("audio-levels", (event) => {
+      const newLevels = event.payload.bands;
       if (Array.isArray(newLevels) && newLevels.length === 8) {
         setLevels((prevLevels) =>
           newLevels.map((level, i) => {
@@ -98,11 +145,24 @@ function MainWindow() {
       }
     });
 
+    // Catch up with a dictation that started before this window opened
+    invoke<ProcessingStatus>("get_processing_status")
+      .then(setProcessingStatus)
+      .catch((error) =>
+        console.error("Error fetching processing status:", error),
+      );
+
     const unlistenProcessingStatus = listen(
       "audio-processing-status",
       (event: any) => {
         const newStatus = event.payload as ProcessingStatus;
-        setProcessingStatus(newStatus);
+        // The pipeline goes idle right after a silent recording, which
+        // would hide the notice before it can be read
+        setProcessingStatus((previous) =>
+          previous === "no_speech" && newStatus === "idle"
+            ? previous
+            : newStatus,
+        );
 
         // For completed status, we need to track the full session completion
         if (newStatus === "completed") {
@@ -151,11 +211,15 @@ function MainWindow() {
   const getStatusText = () => {
     switch (processingStatus) {
       case "transcribing":
-        return "Transcribing...";
+        return transcriptionProgress !== null
+          ? `Transcribing... ${Math.round(transcriptionProgress * 100)}%`
+          : "Transcribing...";
       case "thinking_action":
         return "Thinking...";
       case "generating_text":
         return "Generating...";
+      case "no_speech":
+        return "No speech detected";
       default:
         return null;
     }
@@ -198,7 +262,12 @@ function MainWindow() {
             ))}
           </div>
         ) : (
-          <div className="flex items-center gap-[2px] h-8">
+          <div className="flex items-center gap-[2px] h-8 relative">
+            {statusText && (
+              <div className="absolute -top-7 left-1/2 transform -translate-x-1/2 whitespace-nowrap text-xs text-gray-300 bg-[#1C1C1C] px-2 py-1 rounded-md">
+                {statusText}
+              </div>
+            )}
             {levels.map((level, index) => {
               const height = Math.max(10, Math.min(60, level * 500));
 
@@ -1,5 +1,6 @@
 import { useState, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { Shield, Keyboard, Mic, CheckCircle2, User } from "lucide-react";
 import { Settings } from "./types";
 import { Permissions } from "./pages/Accessibility";
@@ -25,6 +26,8 @@ export const SettingsWindow = () => {
   const [completedSections, setCompletedSections] = useState<SectionId[]>([]);
   const [_settings, setSettings] = useState<Settings | null>(null);
   const [, setIsOnboardingComplete] = useState(false);
+  const [accessibilityLost, setAccessibilityLost] = useState(false);
+  const [safeMode, setSafeMode] = useState(false);
 
   useEffect(() => {
     document.body.style.overflow = "hidden";
@@ -81,6 +84,27 @@ export const SettingsWindow = () => {
   useEffect(() => {
     // Initial fetch
     fetchSettings();
+    invoke<boolean>("is_safe_mode").then(setSafeMode).catch(console.error);
+  }, []);
+
+  useEffect(() => {
+    // The lost event is emitted once, possibly before this window opened
+    invoke<boolean>("check_accessibility_permissions")
+      .then((granted) => setAccessibilityLost(!granted))
+      .catch(console.error);
+
+    const unlistenLost = listen("accessibility-permission-lost", () => {
+      setAccessibilityLost(true);
+    });
+    const unlistenRestored = listen("accessibility-permission-restored", () => {
+      setAccessibilityLost(false);
+      fetchSettings();
+    });
+
+    return () => {
+      unlistenLost.then((fn) => fn());
+      unlistenRestored.then((fn) => fn());
+    };
   }, []);
 
   const markSectionComplete = (sectionId: SectionId) => {
@@ -186,6 +210,20 @@ export const SettingsWindow = () => {
 
         {/* Content area */}
         <div className="flex-1 overflow-auto p-6">
+          {safeMode && (
+            <div className="mb-4 rounded-md border border-yellow-500/50 px-4 py-3 text-sm text-yellow-500">
+              Rune is running in safe mode. Recording, transcription and
+              shortcuts are disabled. Fix your settings, then restart Rune
+              normally.
+            </div>
+          )}
+          {accessibilityLost && (
+            <div className="mb-4 rounded-md border border-destructive/50 px-4 py-3 text-sm text-destructive">
+              Rune doesn&apos;t have Accessibility access, so dictations are
+              copied to the clipboard instead of typed. Enable Rune under
+              Privacy &amp; Security → Accessibility to restore typing.
+            </div>
+          )}
           {error && (
             <div className="text-destructive text-center mb-4">{error}</div>
           )}
@@ -1,8 +1,116 @@
 export interface Settings {
+  version: number;
   shortcuts: ShortcutConfig;
   audio: AudioConfig;
   window: WindowConfig;
   user_profile: UserProfile;
+  feedback: FeedbackConfig;
+  llm: LLMConfig;
+  launch_at_login: boolean;
+  transcription: TranscriptionConfig;
+  chunking: ChunkingConfig;
+  merge: MergeConfig;
+  injection_method: InjectionMethod;
+  processing_mode: ProcessingMode;
+  app_profiles: Record<string, AppProfile>;
+  history: HistoryConfig;
+}
+
+export interface HistoryConfig {
+  retention_days: number | null;
+  max_entries: number | null;
+}
+
+export type InjectionMethod = "keystroke" | "clipboard_paste" | "auto";
+
+export type ProcessingMode = "auto" | "transform" | "generate" | "raw";
+
+export interface AppProfile {
+  mode: ProcessingMode;
+  system_prompt: string | null;
+}
+
+export interface MergeConfig {
+  enabled: boolean;
+  grace_window_ms: number;
+  separator: "space" | "newline";
+  reprocess_combined: boolean;
+}
+
+export interface ChunkingConfig {
+  llm_chunk_chars: number;
+  injection_chunk_chars: number;
+  injection_delay_ms: number;
+}
+
+export interface TranscriptionConfig {
+  beam_size: number | null;
+  model_pool_size: number;
+  whisper_model: WhisperModel;
+  download_models: boolean;
+  custom_models: CustomModel[];
+  active_custom_model: string | null;
+  min_confidence: number | null;
+  initial_prompt: string | null;
+  vad_enabled: boolean;
+  reset_timeout_secs: number;
+}
+
+export type WhisperModel =
+  | "tiny"
+  | "tiny-en"
+  | "base"
+  | "base-en"
+  | "small"
+  | "small-en"
+  | "medium"
+  | "medium-en"
+  | "large"
+  | "large-v2"
+  | "large-v3"
+  | "large-v3-turbo"
+  | "distil-medium-en"
+  | "distil-large-v2"
+  | "distil-large-v3";
+
+export interface CustomModel {
+  name: string;
+  path: string;
+}
+
+export type LLMProviderConfig =
+  | { type: "rune" }
+  | { type: "open_ai"; api_key: string; model: string }
+  | { type: "anthropic"; api_key: string; model: string }
+  | { type: "ollama"; base_url: string | null; model: string };
+
+export interface LLMConfig {
+  provider: LLMProviderConfig;
+  base_url: string | null;
+  temperature: number;
+  seed: number | null;
+  history_turns: number;
+  timeouts: PromptTimeouts;
+  cache: LlmCacheConfig;
+}
+
+export interface PromptTimeouts {
+  action_checker_secs: number;
+  text_generator_secs: number;
+  text_transformer_secs: number;
+}
+
+export interface LlmCacheConfig {
+  enabled: boolean;
+  max_entries: number;
+  ttl_secs: number;
+}
+
+export interface FeedbackConfig {
+  enabled: boolean;
+  endpoint: string;
+  correction_window_secs: number;
+  include_app_name: boolean;
 }
 
 export interface UserProfile {
@@ -14,10 +122,28 @@ export interface UserProfile {
 export interface ShortcutConfig {
   record_key: string | null;
   record_modifier: string | null;
+  tap_threshold_ms: number;
+  recording_mode: RecordingMode;
+  cancel_key: string | null;
+  cancel_modifier: string | null;
 }
 
+export type RecordingMode = "tap_or_hold" | "push_to_talk" | "toggle";
+
 export interface AudioConfig {
   default_device: string | null;
+  max_buffer_mb: number | null;
+  buffer_overflow_policy: "spill_to_disk" | "auto_stop";
+  silence_timeout_ms: number | null;
+  silence_threshold: number;
+  min_recording_ms: number;
+  level_mode: "peak" | "rms";
+  input_gain: number;
+  normalization: "off" | "peak";
+  noise_reduction: "off" | "light" | "aggressive";
+  keep_recordings: boolean;
+  output_format: "wav" | "flac" | "mp3";
+  muted: boolean;
 }
 
 export interface WindowConfig {
@@ -28,4 +154,32 @@ export interface WindowConfig {
 export interface AudioDevice {
   name: string;
   id: string;
+  default: boolean;
+  sample_rates: number[];
+  channels: number[];
+}
+
+export interface LLMUsage {
+  input_tokens: number;
+  output_tokens: number;
+  cost: number;
+}
+
+export interface UsageMetrics {
+  dictations_started: number;
+  dictations_completed: number;
+  dictations_failed: number;
+  dictations_cancelled: number;
+  dictations_no_speech: number;
+  transcription_count: number;
+  transcription_ms_total: number;
+  llm_count: number;
+  llm_ms_total: number;
+  avg_transcription_ms: number | null;
+  avg_llm_ms: number | null;
+}
+
+export interface ShortcutOptions {
+  modifiers: string[];
+  keys: string[];
 }